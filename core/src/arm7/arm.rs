@@ -4,7 +4,9 @@ use super::{
     ARM7, HW,
 };
 
+use crate::disassembler::disassemble_arm;
 use crate::hw::AccessType;
+use crate::nds::CPU;
 
 impl ARM7 {
     pub(super) fn fill_arm_instr_buffer(&mut self, hw: &mut HW) {
@@ -20,12 +22,13 @@ impl ARM7 {
         {
             use Reg::*;
             trace!("{:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} \
-            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {:08X}",
+            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {:08X} {}",
             self.regs.get_reg(R0), self.regs.get_reg(R1), self.regs.get_reg(R2), self.regs.get_reg(R3),
             self.regs.get_reg(R4), self.regs.get_reg(R5), self.regs.get_reg(R6), self.regs.get_reg(R7),
             self.regs.get_reg(R8), self.regs.get_reg(R9), self.regs.get_reg(R10), self.regs.get_reg(R11),
             self.regs.get_reg(R12), self.regs.get_reg(R13), self.regs.get_reg(R14), self.regs.get_reg(R15),
-            self.regs.get_reg(CPSR), instr);
+            self.regs.get_reg(CPSR), instr,
+            disassemble_arm(instr, self.regs.pc.wrapping_sub(4), CPU::ARM7));
         }
         self.instr_buffer[0] = self.instr_buffer[1];
         self.regs.pc = self.regs.pc.wrapping_add(4);
@@ -597,6 +600,9 @@ impl ARM7 {
     // ARM.13: Software Interrupt (SWI)
     fn arm_software_interrupt(&mut self, hw: &mut HW, instr: u32) {
         assert_eq!(instr >> 24 & 0xF, 0b1111);
+        if hw.hle_bios() {
+            return self.hle_software_interrupt(hw, (instr >> 16 & 0xFF) as u8);
+        }
         self.instruction_prefetch::<u32>(hw, AccessType::N);
         self.regs.change_mode(Mode::SVC);
         self.regs.set_reg(Reg::R14, self.regs.pc.wrapping_sub(4));
@@ -4,10 +4,13 @@ mod arm;
 mod registers;
 mod thumb;
 
-use crate::hw::{AccessType, MemoryValue, HW};
+use serde::{Deserialize, Serialize};
+
+use crate::hw::{AccessType, MemoryValue, SWIResult, HW};
 use crate::num;
 use registers::{Mode, Reg, RegValues};
 
+#[derive(Serialize, Deserialize)]
 pub struct ARM7 {
     cycles_spent: usize,
     regs: RegValues,
@@ -15,8 +18,12 @@ pub struct ARM7 {
     next_access_type: AccessType,
     do_internal: bool,
 
+    // Lookup tables are rebuilt instead of being stored in save states
+    #[serde(skip, default = "instructions::gen_condition_table")]
     condition_lut: [bool; 256],
+    #[serde(skip, default = "arm::gen_lut")]
     arm_lut: [instructions::InstructionHandler<u32>; 4096],
+    #[serde(skip, default = "thumb::gen_lut")]
     thumb_lut: [instructions::InstructionHandler<u16>; 256],
 }
 
@@ -103,6 +110,96 @@ impl ARM7 {
         self.fill_arm_instr_buffer(hw);
     }
 
+    // Address of the next instruction to execute
+    pub fn pc(&self) -> u32 {
+        self.regs
+            .pc
+            .wrapping_sub(if self.regs.get_t() { 2 } else { 4 })
+    }
+
+    // Registers are numbered like GDB does, 0-15 are R0-R15 and 16 is the CPSR. 17 is the SPSR,
+    // which reads as the CPSR in modes without one.
+    pub fn reg(&self, i: usize) -> u32 {
+        match i {
+            0..=14 => self.regs.get_reg_i(i as u32),
+            15 => self.pc(),
+            16 => self.regs.get_reg(Reg::CPSR),
+            17 => self.regs.get_reg(Reg::SPSR),
+            _ => panic!("Invalid Register {}", i),
+        }
+    }
+
+    pub fn banked_regs(&self) -> Vec<(&'static str, u32)> {
+        self.regs.banked()
+    }
+
+    // Returns false if the value isn't valid for the register
+    pub fn set_reg(&mut self, hw: &mut HW, i: usize, value: u32) -> bool {
+        let pc = self.pc();
+        match i {
+            0..=14 => self.regs.set_reg_i(i as u32, value),
+            15 => {
+                self.regs.pc = value;
+                self.fill_instr_buffer(hw);
+            }
+            16 => {
+                if Mode::from_bits(value).is_none() {
+                    return false;
+                }
+                self.regs.set_reg(Reg::CPSR, value);
+                // Switching between ARM and THUMB changes the instructions in the pipeline
+                self.regs.pc = pc;
+                self.fill_instr_buffer(hw);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn fill_instr_buffer(&mut self, hw: &mut HW) {
+        if self.regs.get_t() {
+            self.fill_thumb_instr_buffer(hw);
+        } else {
+            self.fill_arm_instr_buffer(hw);
+        }
+    }
+
+    pub(self) fn hle_software_interrupt(&mut self, hw: &mut HW, swi: u8) {
+        use Reg::*;
+        let mut regs = [
+            self.regs.get_reg(R0),
+            self.regs.get_reg(R1),
+            self.regs.get_reg(R2),
+            self.regs.get_reg(R3),
+        ];
+        let result = hw.hle_swi(false, swi, &mut regs);
+        for (reg, value) in [R0, R1, R2, R3].iter().zip(regs.iter()) {
+            self.regs.set_reg(*reg, *value);
+        }
+        let thumb = self.regs.get_t();
+        match result {
+            SWIResult::Return | SWIResult::Delay(_) => {
+                if thumb {
+                    self.instruction_prefetch::<u16>(hw, AccessType::S);
+                } else {
+                    self.instruction_prefetch::<u32>(hw, AccessType::S);
+                }
+                if let SWIResult::Delay(cycles) = result {
+                    self.cycles_spent += cycles;
+                }
+            }
+            SWIResult::Repeat => {
+                if thumb {
+                    self.regs.pc = self.regs.pc.wrapping_sub(4);
+                    self.fill_thumb_instr_buffer(hw);
+                } else {
+                    self.regs.pc = self.regs.pc.wrapping_sub(8);
+                    self.fill_arm_instr_buffer(hw);
+                }
+            }
+        }
+    }
+
     pub(self) fn should_exec(&self, condition: u32) -> bool {
         self.condition_lut[(self.regs.get_flags() | condition) as usize]
     }
@@ -1,4 +1,5 @@
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Reg {
@@ -21,7 +22,7 @@ pub enum Reg {
     CPSR,
     SPSR,
 }
-#[derive(PartialEq)]
+#[derive(PartialEq, Serialize, Deserialize)]
 pub enum Mode {
     USR = 0b10000,
     FIQ = 0b10001,
@@ -32,7 +33,23 @@ pub enum Mode {
     UND = 0b11011,
 }
 
+impl Mode {
+    pub fn from_bits(bits: u32) -> Option<Mode> {
+        match bits & 0x1F {
+            0b10000 => Some(Mode::USR),
+            0b10001 => Some(Mode::FIQ),
+            0b10010 => Some(Mode::IRQ),
+            0b10011 => Some(Mode::SVC),
+            0b10111 => Some(Mode::ABT),
+            0b11111 => Some(Mode::SYS),
+            0b11011 => Some(Mode::UND),
+            _ => None,
+        }
+    }
+}
+
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     struct StatusReg: u32 {
         const N =  0x80000000;
         const Z =  0x40000000;
@@ -72,7 +89,7 @@ impl StatusReg {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RegValues {
     usr: [u32; 15],
     fiq: [u32; 7],
@@ -87,7 +104,7 @@ pub struct RegValues {
 
 impl RegValues {
     pub fn new() -> RegValues {
-        RegValues {
+        let mut regs = RegValues {
             usr: [0; 15],
             fiq: [0; 7],
             abt: [0; 2],
@@ -97,7 +114,9 @@ impl RegValues {
             pc: 0,
             cpsr: StatusReg::reset(),
             spsr: [StatusReg::reset(); 5],
-        }
+        };
+        regs.cpsr.bits = 0xD3; // Reset enters SVC mode with IRQs and FIQs disabled
+        regs
     }
 
     pub fn direct_boot(pc: u32) -> RegValues {
@@ -172,6 +191,38 @@ impl RegValues {
         }
     }
 
+    // Registers of every mode
+    pub fn banked(&self) -> Vec<(&'static str, u32)> {
+        const FIQ_NAMES: [&str; 7] = [
+            "r8_fiq", "r9_fiq", "r10_fiq", "r11_fiq", "r12_fiq", "r13_fiq", "r14_fiq",
+        ];
+        const USR_NAMES: [&str; 7] = [
+            "r8_usr", "r9_usr", "r10_usr", "r11_usr", "r12_usr", "r13_usr", "r14_usr",
+        ];
+        let mut banked: Vec<(&'static str, u32)> = USR_NAMES
+            .iter()
+            .copied()
+            .zip(self.usr[8..15].iter().copied())
+            .chain(FIQ_NAMES.iter().copied().zip(self.fiq.iter().copied()))
+            .collect();
+        banked.extend_from_slice(&[
+            ("r13_svc", self.svc[0]),
+            ("r14_svc", self.svc[1]),
+            ("r13_abt", self.abt[0]),
+            ("r14_abt", self.abt[1]),
+            ("r13_irq", self.irq[0]),
+            ("r14_irq", self.irq[1]),
+            ("r13_und", self.und[0]),
+            ("r14_und", self.und[1]),
+            ("spsr_fiq", self.spsr[0].bits),
+            ("spsr_svc", self.spsr[1].bits),
+            ("spsr_abt", self.spsr[2].bits),
+            ("spsr_irq", self.spsr[3].bits),
+            ("spsr_und", self.spsr[4].bits),
+        ]);
+        banked
+    }
+
     pub fn restore_cpsr(&mut self) {
         self.cpsr.bits = self.get_reg(Reg::SPSR);
     }
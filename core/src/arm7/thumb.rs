@@ -4,7 +4,9 @@ use super::{
     ARM7, HW,
 };
 
+use crate::disassembler::disassemble_thumb;
 use crate::hw::AccessType;
+use crate::nds::CPU;
 
 impl ARM7 {
     pub(super) fn fill_thumb_instr_buffer(&mut self, hw: &mut HW) {
@@ -20,14 +22,15 @@ impl ARM7 {
         {
             use Reg::*;
             trace!("{:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} \
-            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {}",
+            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {} {}",
             self.regs.get_reg(R0), self.regs.get_reg(R1), self.regs.get_reg(R2), self.regs.get_reg(R3),
             self.regs.get_reg(R4), self.regs.get_reg(R5), self.regs.get_reg(R6), self.regs.get_reg(R7),
             self.regs.get_reg(R8), self.regs.get_reg(R9), self.regs.get_reg(R10), self.regs.get_reg(R11),
             self.regs.get_reg(R12), self.regs.get_reg(R13), self.regs.get_reg(R14), self.regs.get_reg(R15),
             self.regs.get_reg(CPSR), if instr & 0b1111_1000_0000_0000 == 0b1111_0000_0000_0000 {
                 format!("{:04X}{:04X}", instr, self.instr_buffer[1])
-            } else { format!("    {:04X}", instr) });
+            } else { format!("    {:04X}", instr) },
+            disassemble_thumb(instr, self.instr_buffer[1] as u16, self.regs.pc.wrapping_sub(2), CPU::ARM7));
         }
         self.instr_buffer[0] = self.instr_buffer[1];
         self.regs.pc = self.regs.pc.wrapping_add(2);
@@ -570,6 +573,9 @@ impl ARM7 {
     // THUMB.17: software interrupt
     fn thumb_software_interrupt(&mut self, hw: &mut HW, instr: u16) {
         assert_eq!(instr >> 8 & 0xFF, 0b11011111);
+        if hw.hle_bios() {
+            return self.hle_software_interrupt(hw, instr as u8);
+        }
         self.instruction_prefetch::<u16>(hw, AccessType::N);
         self.regs.change_mode(Mode::SVC);
         self.regs.set_reg(Reg::R14, self.regs.pc.wrapping_sub(2));
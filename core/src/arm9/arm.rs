@@ -1,6 +1,8 @@
 use super::{instructions::InstructionHandler, registers::Mode, ARM9, HW};
 
+use crate::disassembler::disassemble_arm;
 use crate::hw::AccessType;
+use crate::nds::CPU;
 
 impl ARM9 {
     pub(super) fn fill_arm_instr_buffer(&mut self, hw: &mut HW) {
@@ -15,10 +17,11 @@ impl ARM9 {
         let instr = self.instr_buffer[0];
         {
             trace!("{:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} \
-            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {:08X}",
+            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {:08X} {}",
             self.regs[0], self.regs[1], self.regs[2], self.regs[3], self.regs[4], self.regs[5], self.regs[6],
             self.regs[7], self.regs[8], self.regs[9], self.regs[10], self.regs[11], self.regs[12],
-            self.regs[13], self.regs[14], self.regs[15], self.regs.cpsr(), instr);
+            self.regs[13], self.regs[14], self.regs[15], self.regs.cpsr(), instr,
+            disassemble_arm(instr, self.regs[15].wrapping_sub(4), CPU::ARM9));
         }
         self.instr_buffer[0] = self.instr_buffer[1];
         self.regs[15] = self.regs[15].wrapping_add(4);
@@ -720,6 +723,9 @@ impl ARM9 {
     // ARM.13: Software Interrupt (SWI)
     fn arm_software_interrupt(&mut self, hw: &mut HW, instr: u32) {
         assert_eq!(instr >> 24 & 0xF, 0b1111);
+        if hw.hle_bios() {
+            return self.hle_software_interrupt(hw, (instr >> 16 & 0xFF) as u8);
+        }
         self.instruction_prefetch::<u32>(hw, AccessType::N);
         self.regs.change_mode(Mode::SVC);
         self.regs.set_lr(self.regs[15].wrapping_sub(4));
@@ -1,6 +1,7 @@
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Mode {
     USR = 0b10000,
     FIQ = 0b10001,
@@ -11,7 +12,23 @@ pub enum Mode {
     UND = 0b11011,
 }
 
+impl Mode {
+    pub fn from_bits(bits: u32) -> Option<Mode> {
+        match bits & 0x1F {
+            0b10000 => Some(Mode::USR),
+            0b10001 => Some(Mode::FIQ),
+            0b10010 => Some(Mode::IRQ),
+            0b10011 => Some(Mode::SVC),
+            0b10111 => Some(Mode::ABT),
+            0b11111 => Some(Mode::SYS),
+            0b11011 => Some(Mode::UND),
+            _ => None,
+        }
+    }
+}
+
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     struct StatusReg: u32 {
         const N =  0x80000000;
         const Z =  0x40000000;
@@ -52,7 +69,7 @@ impl StatusReg {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RegValues {
     regs: [u32; 16],
     usr: [u32; 2], // R13 and R14
@@ -73,6 +90,7 @@ impl RegValues {
             spsr: [StatusReg::reset(); 2], // SVC and IRQ
         };
         regs[15] = 0xFFFF_0000;
+        regs.cpsr.bits = 0xD3; // Reset enters SVC mode with IRQs and FIQs disabled
         regs
     }
 
@@ -150,6 +168,32 @@ impl RegValues {
         self.cpsr.bits
     }
 
+    // Registers of every mode, the current mode's are live in regs
+    pub fn banked(&self) -> Vec<(&'static str, u32)> {
+        let bank = |mode: Mode, bank: &[u32; 2]| {
+            if self.cpsr.get_mode() == mode
+                || mode == Mode::USR && self.cpsr.get_mode() == Mode::SYS
+            {
+                [self.regs[13], self.regs[14]]
+            } else {
+                *bank
+            }
+        };
+        let usr = bank(Mode::USR, &self.usr);
+        let svc = bank(Mode::SVC, &self.svc);
+        let irq = bank(Mode::IRQ, &self.irq);
+        vec![
+            ("r13_usr", usr[0]),
+            ("r14_usr", usr[1]),
+            ("r13_svc", svc[0]),
+            ("r14_svc", svc[1]),
+            ("r13_irq", irq[0]),
+            ("r14_irq", irq[1]),
+            ("spsr_svc", self.spsr[0].bits),
+            ("spsr_irq", self.spsr[1].bits),
+        ]
+    }
+
     pub fn cpsr_mut(&mut self) -> &mut u32 {
         &mut self.cpsr.bits
     }
@@ -1,6 +1,8 @@
 use super::{instructions::InstructionHandler, registers::Mode, ARM9, HW};
 
+use crate::disassembler::disassemble_thumb;
 use crate::hw::AccessType;
+use crate::nds::CPU;
 
 impl ARM9 {
     pub(super) fn fill_thumb_instr_buffer(&mut self, hw: &mut HW) {
@@ -15,13 +17,14 @@ impl ARM9 {
         let instr = self.instr_buffer[0] as u16;
         {
             trace!("{:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} {:08X} \
-            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {}",
+            {:08X} {:08X} {:08X} {:08X} cpsr: {:08X} | {} {}",
             self.regs[0], self.regs[1], self.regs[2], self.regs[3], self.regs[4], self.regs[5], self.regs[6],
             self.regs[7], self.regs[8], self.regs[9], self.regs[10], self.regs[11], self.regs[12],
             self.regs[13], self.regs[14], self.regs[15], self.regs.cpsr(),
             if instr & 0b1111_1000_0000_0000 == 0b1111_0000_0000_0000 {
                 format!("{:04X}{:04X}", instr, self.instr_buffer[1])
-            } else { format!("    {:04X}", instr) });
+            } else { format!("    {:04X}", instr) },
+            disassemble_thumb(instr, self.instr_buffer[1] as u16, self.regs[15].wrapping_sub(2), CPU::ARM9));
         }
         self.instr_buffer[0] = self.instr_buffer[1];
         self.regs[15] = self.regs[15].wrapping_add(2);
@@ -551,6 +554,9 @@ impl ARM9 {
     // THUMB.17: software interrupt
     fn thumb_software_interrupt(&mut self, hw: &mut HW, instr: u16) {
         assert_eq!(instr >> 8 & 0xFF, 0b11011111);
+        if hw.hle_bios() {
+            return self.hle_software_interrupt(hw, instr as u8);
+        }
         self.instruction_prefetch::<u16>(hw, AccessType::N);
         self.regs.change_mode(Mode::SVC);
         self.regs.set_lr(self.regs[15].wrapping_sub(2));
@@ -0,0 +1,375 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::hw::{generate_firmware, AudioSink, NullSink, Save, SaveType, UserSettings};
+use crate::movie::Movie;
+use crate::nds::NDS;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BootMode {
+    // Copies the ROM's binaries into memory and jumps straight to them
+    Direct,
+    // Runs the BIOS and firmware boot sequence
+    Firmware,
+}
+
+impl FromStr for BootMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "direct" => Ok(BootMode::Direct),
+            "firmware" => Ok(BootMode::Firmware),
+            _ => Err(format!(
+                "Unknown boot mode {}, expected direct or firmware",
+                s
+            )),
+        }
+    }
+}
+
+pub struct NDSConfig {
+    pub boot_mode: BootMode,
+    // Runs BIOS calls natively so no BIOS dumps are needed, only works with direct boot
+    pub hle_bios: bool,
+    pub save: Save,
+    // Overrides the save type found in the game database
+    pub save_type: Option<SaveType>,
+    // Overrides the user settings stored in the firmware
+    pub user_settings: Option<UserSettings>,
+    pub audio: Box<dyn AudioSink>,
+    // Records inputs from power on, see NDS::stop_recording
+    pub record_movie: bool,
+    // Plays back a movie, overrides the boot mode, HLE BIOS, save and user settings
+    pub movie: Option<Movie>,
+}
+
+impl Default for NDSConfig {
+    fn default() -> Self {
+        NDSConfig {
+            boot_mode: BootMode::Direct,
+            hle_bios: false,
+            save: Save::Memory(Vec::new()),
+            save_type: None,
+            user_settings: None,
+            audio: Box::new(NullSink::new()),
+            record_movie: false,
+            movie: None,
+        }
+    }
+}
+
+pub struct NDSBuilder {
+    bios7: Vec<u8>,
+    bios9: Vec<u8>,
+    firmware: Vec<u8>,
+    rom: Vec<u8>,
+    config: NDSConfig,
+}
+
+impl NDSBuilder {
+    const BIOS7_SIZE: usize = 0x4000;
+    const BIOS9_SIZE: usize = 0x1000;
+    const FIRMWARE_SIZE: usize = 0x4_0000;
+    const ROM_HEADER_SIZE: usize = 0x200;
+    const UNIT_CODES: [u8; 3] = [0x00, 0x02, 0x03];
+    const REGIONS: [u8; 3] = [0x00, 0x40, 0x80];
+    // Where the header allows each binary to be loaded, end exclusive
+    const ARM9_RAM_RANGES: &'static [(u32, u32)] = &[(0x0200_0000, 0x023B_FE00)];
+    const ARM7_RAM_RANGES: &'static [(u32, u32)] =
+        &[(0x0200_0000, 0x023B_FE00), (0x037F_8000, 0x0380_7E00)];
+
+    pub fn new(bios7: Vec<u8>, bios9: Vec<u8>, firmware: Vec<u8>, rom: Vec<u8>) -> Self {
+        NDSBuilder {
+            bios7,
+            bios9,
+            firmware,
+            rom,
+            config: NDSConfig::default(),
+        }
+    }
+
+    pub fn config(mut self, config: NDSConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn boot_mode(mut self, boot_mode: BootMode) -> Self {
+        self.config.boot_mode = boot_mode;
+        self
+    }
+
+    pub fn hle_bios(mut self, hle_bios: bool) -> Self {
+        self.config.hle_bios = hle_bios;
+        self
+    }
+
+    pub fn save_file(mut self, save_file: PathBuf) -> Self {
+        self.config.save = Save::File(save_file);
+        self
+    }
+
+    pub fn save_data(mut self, save_data: Vec<u8>) -> Self {
+        self.config.save = Save::Memory(save_data);
+        self
+    }
+
+    pub fn save_type(mut self, save_type: SaveType) -> Self {
+        self.config.save_type = Some(save_type);
+        self
+    }
+
+    pub fn user_settings(mut self, user_settings: UserSettings) -> Self {
+        self.config.user_settings = Some(user_settings);
+        self
+    }
+
+    pub fn audio(mut self, audio: Box<dyn AudioSink>) -> Self {
+        self.config.audio = audio;
+        self
+    }
+
+    pub fn record_movie(mut self, record_movie: bool) -> Self {
+        self.config.record_movie = record_movie;
+        self
+    }
+
+    pub fn play_movie(mut self, movie: Movie) -> Self {
+        self.config.movie = Some(movie);
+        self
+    }
+
+    // An empty firmware is replaced by a generated one when booting directly
+    pub fn build(mut self) -> Result<NDS, LoadError> {
+        if let Some(movie) = &mut self.config.movie {
+            if movie.rom_hash != Movie::hash_rom(&self.rom) {
+                return Err(LoadError::MovieWrongRom);
+            }
+            self.config.boot_mode = movie.boot_mode;
+            self.config.hle_bios = movie.hle_bios;
+            self.config.save = Save::Memory(std::mem::take(&mut movie.save));
+            self.config.save_type = Some(movie.save_type);
+            self.config.user_settings = None;
+        }
+        if !self.config.hle_bios {
+            NDSBuilder::check_size("ARM7 BIOS", NDSBuilder::BIOS7_SIZE, self.bios7.len())?;
+            NDSBuilder::check_size("ARM9 BIOS", NDSBuilder::BIOS9_SIZE, self.bios9.len())?;
+        } else if self.config.boot_mode == BootMode::Firmware {
+            return Err(LoadError::BiosRequired);
+        }
+        if self.firmware.is_empty() && self.config.boot_mode == BootMode::Direct {
+            self.firmware = match &self.config.user_settings {
+                Some(user_settings) => generate_firmware(user_settings),
+                None => generate_firmware(&UserSettings::default()),
+            };
+        }
+        if self.firmware.len() < NDSBuilder::FIRMWARE_SIZE {
+            return Err(LoadError::FirmwareTooSmall(self.firmware.len()));
+        }
+        if let Some(movie) = &self.config.movie {
+            let addr = NDSBuilder::FIRMWARE_SIZE - movie.firmware_user_settings.len();
+            self.firmware[addr..NDSBuilder::FIRMWARE_SIZE]
+                .copy_from_slice(&movie.firmware_user_settings);
+        }
+        if self.rom.len() < NDSBuilder::ROM_HEADER_SIZE {
+            return Err(LoadError::RomTooSmall {
+                size: self.rom.len(),
+                needed: NDSBuilder::ROM_HEADER_SIZE,
+            });
+        }
+        let unit_code = self.rom[0x12];
+        if !NDSBuilder::UNIT_CODES.contains(&unit_code) {
+            return Err(LoadError::UnknownUnitCode(unit_code));
+        }
+        let region = self.rom[0x1D];
+        if !NDSBuilder::REGIONS.contains(&region) {
+            return Err(LoadError::UnknownRegion(region));
+        }
+        if self.config.boot_mode == BootMode::Direct {
+            // The ARM9 and ARM7 binaries are copied out of the ROM
+            let binaries = [
+                ("ARM9", 0x20, NDSBuilder::ARM9_RAM_RANGES),
+                ("ARM7", 0x30, NDSBuilder::ARM7_RAM_RANGES),
+            ];
+            for (name, header_addr, ram_ranges) in binaries.iter() {
+                let offset = self.read_rom_header(*header_addr) as usize;
+                let ram_addr = self.read_rom_header(header_addr + 0x8);
+                let size = self.read_rom_header(header_addr + 0xC);
+                if offset + size as usize > self.rom.len() {
+                    return Err(LoadError::RomTooSmall {
+                        size: self.rom.len(),
+                        needed: offset + size as usize,
+                    });
+                }
+                let end_addr = ram_addr as u64 + size as u64;
+                if !ram_ranges
+                    .iter()
+                    .any(|(start, end)| ram_addr >= *start && end_addr <= *end as u64)
+                {
+                    return Err(LoadError::BinaryOutOfRange {
+                        name,
+                        ram_addr,
+                        size,
+                    });
+                }
+            }
+        }
+
+        let game_code = [self.rom[0xC], self.rom[0xD], self.rom[0xE], self.rom[0xF]];
+        let save_type = match self.config.save_type {
+            Some(save_type) => save_type,
+            None => SaveType::detect(game_code).ok_or(LoadError::UnknownSaveType(game_code))?,
+        };
+        if !save_type.has_valid_size() {
+            return Err(LoadError::InvalidSaveSize(save_type));
+        }
+        Ok(NDS::new(
+            self.bios7,
+            self.bios9,
+            self.firmware,
+            self.rom,
+            self.config,
+            save_type,
+        ))
+    }
+
+    fn check_size(name: &'static str, expected: usize, found: usize) -> Result<(), LoadError> {
+        if expected == found {
+            Ok(())
+        } else {
+            Err(LoadError::BiosWrongSize {
+                name,
+                expected,
+                found,
+            })
+        }
+    }
+
+    fn read_rom_header(&self, addr: usize) -> u32 {
+        u32::from_le_bytes([
+            self.rom[addr],
+            self.rom[addr + 1],
+            self.rom[addr + 2],
+            self.rom[addr + 3],
+        ])
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    RomTooSmall {
+        size: usize,
+        needed: usize,
+    },
+    BiosWrongSize {
+        name: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    BiosRequired,
+    FirmwareTooSmall(usize),
+    UnknownUnitCode(u8),
+    UnknownRegion(u8),
+    BinaryOutOfRange {
+        name: &'static str,
+        ram_addr: u32,
+        size: u32,
+    },
+    UnknownSaveType([u8; 4]),
+    InvalidSaveSize(SaveType),
+    MovieWrongRom,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::RomTooSmall { size, needed } => write!(
+                f,
+                "ROM is 0x{:X} bytes but its header needs at least 0x{:X} bytes",
+                size, needed
+            ),
+            LoadError::BiosWrongSize {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} is 0x{:X} bytes but should be 0x{:X} bytes",
+                name, found, expected
+            ),
+            LoadError::BiosRequired => write!(f, "Booting through the firmware needs BIOS dumps"),
+            LoadError::FirmwareTooSmall(size) => write!(
+                f,
+                "Firmware is 0x{:X} bytes but should be at least 0x{:X} bytes",
+                size,
+                NDSBuilder::FIRMWARE_SIZE
+            ),
+            LoadError::UnknownUnitCode(unit_code) => {
+                write!(f, "ROM header has an unknown unit code 0x{:02X}", unit_code)
+            }
+            LoadError::UnknownRegion(region) => {
+                write!(f, "ROM header has an unknown region 0x{:02X}", region)
+            }
+            LoadError::BinaryOutOfRange {
+                name,
+                ram_addr,
+                size,
+            } => write!(
+                f,
+                "{} binary of 0x{:X} bytes at 0x{:08X} does not fit in RAM",
+                name, size, ram_addr
+            ),
+            LoadError::UnknownSaveType(game_code) => write!(
+                f,
+                "Save type of {} is not supported, set it manually",
+                String::from_utf8_lossy(game_code)
+            ),
+            LoadError::InvalidSaveSize(save_type) => match save_type {
+                SaveType::None => unreachable!(),
+                SaveType::EEPROM(size) => {
+                    write!(f, "EEPROM of 0x{:X} bytes is not supported", size)
+                }
+                SaveType::Flash(size) => write!(f, "Flash of 0x{:X} bytes is not supported", size),
+            },
+            LoadError::MovieWrongRom => write!(f, "Movie was recorded with a different ROM"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nds::tests::test_rom;
+
+    #[test]
+    fn save_type_sizes() {
+        let cases = [
+            (SaveType::None, true),
+            (SaveType::EEPROM(0x200), true),
+            (SaveType::EEPROM(128 * 0x400), true),
+            (SaveType::EEPROM(0x300), false),
+            (SaveType::EEPROM(256 * 0x400), false),
+            (SaveType::Flash(256 * 0x400), true),
+            (SaveType::Flash(8 * 0x10_0000), true),
+            (SaveType::Flash(0x200), false),
+            (SaveType::Flash(0), false),
+        ];
+        for (save_type, valid) in cases.iter() {
+            let result = NDSBuilder::new(vec![], vec![], vec![], test_rom())
+                .hle_bios(true)
+                .save_type(*save_type)
+                .build();
+            match result {
+                Ok(_) => assert!(valid, "{:?} was accepted", save_type),
+                Err(LoadError::InvalidSaveSize(found)) => {
+                    assert!(!valid, "{:?} was rejected", save_type);
+                    assert_eq!(found, *save_type);
+                }
+                Err(err) => panic!("{:?} failed with {}", save_type, err),
+            }
+        }
+    }
+}
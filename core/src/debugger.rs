@@ -0,0 +1,221 @@
+use crate::nds::CPU;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+// Fires when an access through the CPU's bus overlaps start..=end. DMA and sound channels
+// go through the same buses so they can fire it too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Watchpoint {
+    pub cpu: CPU,
+    pub start: u32,
+    pub end: u32,
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    pub fn new(cpu: CPU, start: u32, end: u32, kind: WatchKind) -> Self {
+        Watchpoint {
+            cpu,
+            start,
+            end,
+            kind,
+        }
+    }
+
+    pub(crate) fn matches(&self, cpu: CPU, addr: u32, len: u32, write: bool) -> bool {
+        let kind_matches = match self.kind {
+            WatchKind::Read => !write,
+            WatchKind::Write => write,
+            WatchKind::Access => true,
+        };
+        kind_matches
+            && self.cpu == cpu
+            && addr <= self.end
+            && addr.wrapping_add(len - 1) >= self.start
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    // The CPU is about to run the instruction after the one it was stepped over
+    Step(CPU),
+    // The CPU is about to run the instruction at the address
+    Breakpoint(CPU, u32),
+    // The instruction or device which made the access has completed
+    Watchpoint { cpu: CPU, addr: u32, write: bool },
+    // VCOUNT has changed since the scanline step started
+    Scanline,
+    // The requested number of frames has been emulated, or a step reached the end of the frame
+    Frame,
+}
+
+impl StopReason {
+    pub fn cpu(&self) -> Option<CPU> {
+        match *self {
+            StopReason::Step(cpu) | StopReason::Breakpoint(cpu, _) => Some(cpu),
+            StopReason::Watchpoint { cpu, .. } => Some(cpu),
+            StopReason::Scanline | StopReason::Frame => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchpoint_matches() {
+        let cases = [
+            // (watchpoint, cpu, addr, len, write, matches)
+            (
+                (0x100, 0x103, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                true,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                4,
+                true,
+                true,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Access),
+                CPU::ARM7,
+                0x100,
+                4,
+                true,
+                false,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Read),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                true,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Read),
+                CPU::ARM9,
+                0x100,
+                4,
+                true,
+                false,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Write),
+                CPU::ARM9,
+                0x100,
+                4,
+                true,
+                true,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Write),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                false,
+            ),
+            // Partial overlaps at either end
+            (
+                (0x102, 0x102, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                true,
+            ),
+            (
+                (0x102, 0x105, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                true,
+            ),
+            (
+                (0x0FE, 0x101, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                2,
+                false,
+                true,
+            ),
+            (
+                (0x103, 0x103, WatchKind::Access),
+                CPU::ARM9,
+                0x103,
+                1,
+                false,
+                true,
+            ),
+            // Adjacent accesses
+            (
+                (0x104, 0x107, WatchKind::Access),
+                CPU::ARM9,
+                0x100,
+                4,
+                false,
+                false,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Access),
+                CPU::ARM9,
+                0x104,
+                2,
+                false,
+                false,
+            ),
+            (
+                (0x100, 0x103, WatchKind::Access),
+                CPU::ARM9,
+                0x0FF,
+                1,
+                false,
+                false,
+            ),
+            // The end of the address space doesn't wrap
+            (
+                (0xFFFF_FFFF, 0xFFFF_FFFF, WatchKind::Access),
+                CPU::ARM9,
+                0xFFFF_FFFC,
+                4,
+                false,
+                true,
+            ),
+            (
+                (0x0000_0000, 0x0000_0003, WatchKind::Access),
+                CPU::ARM9,
+                0xFFFF_FFFC,
+                4,
+                false,
+                false,
+            ),
+        ];
+        for ((start, end, kind), cpu, addr, len, write, expected) in cases.iter() {
+            let watchpoint = Watchpoint::new(CPU::ARM9, *start, *end, *kind);
+            assert_eq!(
+                watchpoint.matches(*cpu, *addr, *len, *write),
+                *expected,
+                "{:?} {:?} 0x{:08X} {} {}",
+                watchpoint,
+                cpu,
+                addr,
+                len,
+                write
+            );
+        }
+    }
+}
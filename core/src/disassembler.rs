@@ -0,0 +1,549 @@
+use crate::nds::CPU;
+
+const REGS: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc",
+];
+const CONDS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "", "nv",
+];
+const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+const DATA_PROC_OPS: [&str; 16] = [
+    "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc", "tst", "teq", "cmp", "cmn", "orr",
+    "mov", "bic", "mvn",
+];
+const THUMB_ALU_OPS: [&str; 16] = [
+    "and", "eor", "lsl", "lsr", "asr", "adc", "sbc", "ror", "tst", "neg", "cmp", "cmn", "orr",
+    "mul", "bic", "mvn",
+];
+
+// Disassembles the ARM instruction at addr, instructions the CPU doesn't implement are shown as data
+pub fn disassemble_arm(instr: u32, addr: u32, cpu: CPU) -> String {
+    let arm9 = cpu == CPU::ARM9;
+    let cond = CONDS[(instr >> 28) as usize];
+    let rn = reg(instr >> 16);
+    let rd = reg(instr >> 12);
+    let rs = reg(instr >> 8);
+    let rm = reg(instr);
+    let bit = |i: u32| instr >> i & 0x1 != 0;
+
+    if instr >> 28 == 0xF && arm9 {
+        return if instr & 0x0E00_0000 == 0x0A00_0000 {
+            let target = addr
+                .wrapping_add(8)
+                .wrapping_add(branch_offset(instr))
+                .wrapping_add((bit(24) as u32) << 1);
+            format!("blx 0x{:08X}", target)
+        } else {
+            format!(".word 0x{:08X}", instr)
+        };
+    }
+
+    if instr & 0x0FF0_00D0 == 0x0120_0010 && (arm9 || !bit(5)) {
+        let op = if bit(5) { "blx" } else { "bx" };
+        format!("{}{} {}", op, cond, rm)
+    } else if instr & 0x0FC0_00F0 == 0x0000_0090 {
+        let s = if bit(20) { "s" } else { "" };
+        if bit(21) {
+            format!("mla{}{} {}, {}, {}, {}", cond, s, rn, rm, rs, rd)
+        } else {
+            format!("mul{}{} {}, {}, {}", cond, s, rn, rm, rs)
+        }
+    } else if instr & 0x0F80_00F0 == 0x0080_0090 {
+        let op = match (bit(22), bit(21)) {
+            (false, false) => "umull",
+            (false, true) => "umlal",
+            (true, false) => "smull",
+            (true, true) => "smlal",
+        };
+        let s = if bit(20) { "s" } else { "" };
+        format!("{}{}{} {}, {}, {}, {}", op, cond, s, rd, rn, rm, rs)
+    } else if instr & 0x0F80_00F0 == 0x0100_0090 {
+        let b = if bit(22) { "b" } else { "" };
+        format!("swp{}{} {}, {}, [{}]", cond, b, rd, rm, rn)
+    } else if instr & 0x0E00_0090 == 0x0000_0090 {
+        let op = match (bit(20), bit(6), bit(5)) {
+            (false, false, true) => "strh",
+            (true, false, true) => "ldrh",
+            (true, true, false) => "ldrsb",
+            (true, true, true) => "ldrsh",
+            (false, true, false) if arm9 => "ldrd",
+            (false, true, true) if arm9 => "strd",
+            _ => return format!(".word 0x{:08X}", instr),
+        };
+        let offset = if bit(22) {
+            imm(instr >> 4 & 0xF0 | instr & 0xF, bit(23))
+        } else {
+            format!("{}{}", if bit(23) { "" } else { "-" }, rm)
+        };
+        format!(
+            "{}{} {}, {}",
+            op,
+            cond,
+            rd,
+            address(rn, &offset, bit(24), bit(21))
+        )
+    } else if arm9 && instr & 0x0FF0_00F0 == 0x0160_0010 {
+        format!("clz{} {}, {}", cond, rd, rm)
+    } else if arm9 && instr & 0x0F90_00F0 == 0x0100_0050 {
+        let op = ["qadd", "qsub", "qdadd", "qdsub"][(instr >> 21 & 0x3) as usize];
+        format!("{}{} {}, {}, {}", op, cond, rd, rm, rn)
+    } else if arm9 && instr & 0x0F90_0090 == 0x0100_0080 {
+        let x = if bit(5) { "t" } else { "b" };
+        let y = if bit(6) { "t" } else { "b" };
+        match instr >> 21 & 0x3 {
+            0 => format!("smla{}{}{} {}, {}, {}, {}", x, y, cond, rn, rm, rs, rd),
+            1 if !bit(5) => format!("smlaw{}{} {}, {}, {}, {}", y, cond, rn, rm, rs, rd),
+            1 => format!("smulw{}{} {}, {}, {}", y, cond, rn, rm, rs),
+            2 => format!("smlal{}{}{} {}, {}, {}, {}", x, y, cond, rd, rn, rm, rs),
+            _ => format!("smul{}{}{} {}, {}, {}", x, y, cond, rn, rm, rs),
+        }
+    } else if instr & 0x0D90_0000 == 0x0100_0000 {
+        let psr = if bit(22) { "spsr" } else { "cpsr" };
+        if bit(21) {
+            let fields: String = ["c", "x", "s", "f"]
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| bit(16 + *i as u32))
+                .map(|(_, field)| *field)
+                .collect();
+            let operand = if bit(25) {
+                format!("#0x{:X}", rotated_imm(instr))
+            } else {
+                rm.to_string()
+            };
+            format!("msr{} {}_{}, {}", cond, psr, fields, operand)
+        } else {
+            format!("mrs{} {}, {}", cond, rd, psr)
+        }
+    } else if instr & 0x0C00_0000 == 0x0000_0000 {
+        let opcode = (instr >> 21 & 0xF) as usize;
+        let op = DATA_PROC_OPS[opcode];
+        let operand = if bit(25) {
+            format!("#0x{:X}", rotated_imm(instr))
+        } else {
+            shifted_reg(instr)
+        };
+        match opcode {
+            0x8..=0xB => format!("{}{} {}, {}", op, cond, rn, operand),
+            0xD | 0xF => format!("{}{}{} {}, {}", op, cond, s_suffix(bit(20)), rd, operand),
+            _ => format!(
+                "{}{}{} {}, {}, {}",
+                op,
+                cond,
+                s_suffix(bit(20)),
+                rd,
+                rn,
+                operand
+            ),
+        }
+    } else if instr & 0x0C00_0000 == 0x0400_0000 {
+        let op = if bit(20) { "ldr" } else { "str" };
+        let b = if bit(22) { "b" } else { "" };
+        // Post-indexed with write back accesses memory as user mode
+        let t = if !bit(24) && bit(21) { "t" } else { "" };
+        let offset = if bit(25) {
+            format!("{}{}", if bit(23) { "" } else { "-" }, shifted_reg(instr))
+        } else {
+            imm(instr & 0xFFF, bit(23))
+        };
+        let text = format!(
+            "{}{}{}{} {}, {}",
+            op,
+            cond,
+            b,
+            t,
+            rd,
+            address(rn, &offset, bit(24), bit(21))
+        );
+        if instr >> 16 & 0xF == 15 && !bit(25) && bit(24) && !bit(21) {
+            let offset = instr & 0xFFF;
+            let target = if bit(23) {
+                addr.wrapping_add(8).wrapping_add(offset)
+            } else {
+                addr.wrapping_add(8).wrapping_sub(offset)
+            };
+            format!("{} ; 0x{:08X}", text, target)
+        } else {
+            text
+        }
+    } else if instr & 0x0E00_0000 == 0x0800_0000 {
+        let op = if bit(20) { "ldm" } else { "stm" };
+        let mode = match (bit(24), bit(23)) {
+            (false, false) => "da",
+            (false, true) => "ia",
+            (true, false) => "db",
+            (true, true) => "ib",
+        };
+        format!(
+            "{}{}{} {}{}, {}{}",
+            op,
+            cond,
+            mode,
+            rn,
+            if bit(21) { "!" } else { "" },
+            reg_list(instr & 0xFFFF),
+            if bit(22) { "^" } else { "" }
+        )
+    } else if instr & 0x0E00_0000 == 0x0A00_0000 {
+        let op = if bit(24) { "bl" } else { "b" };
+        let target = addr.wrapping_add(8).wrapping_add(branch_offset(instr));
+        format!("{}{} 0x{:08X}", op, cond, target)
+    } else if instr & 0x0F00_0000 == 0x0F00_0000 {
+        format!("swi{} #0x{:X}", cond, instr & 0xFF_FFFF)
+    } else if instr & 0x0E00_0000 == 0x0C00_0000 {
+        let op = if bit(20) { "ldc" } else { "stc" };
+        let l = if bit(22) { "l" } else { "" };
+        let offset = imm((instr & 0xFF) << 2, bit(23));
+        format!(
+            "{}{}{} p{}, c{}, {}",
+            op,
+            cond,
+            l,
+            instr >> 8 & 0xF,
+            instr >> 12 & 0xF,
+            address(rn, &offset, bit(24), bit(21))
+        )
+    } else if instr & 0x0F00_0010 == 0x0E00_0000 {
+        format!(
+            "cdp{} p{}, {}, c{}, c{}, c{}, {}",
+            cond,
+            instr >> 8 & 0xF,
+            instr >> 20 & 0xF,
+            instr >> 12 & 0xF,
+            instr >> 16 & 0xF,
+            instr & 0xF,
+            instr >> 5 & 0x7
+        )
+    } else if instr & 0x0F00_0010 == 0x0E00_0010 {
+        let op = if bit(20) { "mrc" } else { "mcr" };
+        format!(
+            "{}{} p{}, {}, {}, c{}, c{}, {}",
+            op,
+            cond,
+            instr >> 8 & 0xF,
+            instr >> 21 & 0x7,
+            rd,
+            instr >> 16 & 0xF,
+            instr & 0xF,
+            instr >> 5 & 0x7
+        )
+    } else {
+        format!(".word 0x{:08X}", instr)
+    }
+}
+
+// Disassembles the THUMB instruction at addr, next is the following halfword which completes BL
+pub fn disassemble_thumb(instr: u16, next: u16, addr: u32, cpu: CPU) -> String {
+    let arm9 = cpu == CPU::ARM9;
+    let instr = instr as u32;
+    let opcode = instr >> 8;
+    let rd = reg(instr & 0x7);
+    let rs = reg(instr >> 3 & 0x7);
+    let rb = rs;
+    let bit = |i: u32| instr >> i & 0x1 != 0;
+
+    if opcode & 0xF8 == 0x18 {
+        let op = if bit(9) { "sub" } else { "add" };
+        let operand = if bit(10) {
+            format!("#0x{:X}", instr >> 6 & 0x7)
+        } else {
+            reg(instr >> 6 & 0x7).to_string()
+        };
+        format!("{} {}, {}, {}", op, rd, rs, operand)
+    } else if opcode & 0xE0 == 0x00 {
+        let shift_type = instr >> 11 & 0x3;
+        let shift = match instr >> 6 & 0x1F {
+            0 if shift_type != 0 => 32,
+            shift => shift,
+        };
+        format!("{} {}, {}, #{}", SHIFTS[shift_type as usize], rd, rs, shift)
+    } else if opcode & 0xE0 == 0x20 {
+        let op = ["mov", "cmp", "add", "sub"][(instr >> 11 & 0x3) as usize];
+        format!("{} {}, #0x{:X}", op, reg(instr >> 8 & 0x7), instr & 0xFF)
+    } else if opcode & 0xFC == 0x40 {
+        format!(
+            "{} {}, {}",
+            THUMB_ALU_OPS[(instr >> 6 & 0xF) as usize],
+            rd,
+            rs
+        )
+    } else if opcode & 0xFC == 0x44 {
+        let rd = reg((bit(7) as u32) << 3 | instr & 0x7);
+        let rs = reg((bit(6) as u32) << 3 | instr >> 3 & 0x7);
+        match instr >> 8 & 0x3 {
+            0 => format!("add {}, {}", rd, rs),
+            1 => format!("cmp {}, {}", rd, rs),
+            2 => format!("mov {}, {}", rd, rs),
+            _ if arm9 && bit(7) => format!("blx {}", rs),
+            _ => format!("bx {}", rs),
+        }
+    } else if opcode & 0xF8 == 0x48 {
+        let offset = (instr & 0xFF) << 2;
+        let target = (addr.wrapping_add(4) & !0x3).wrapping_add(offset);
+        format!(
+            "ldr {}, [pc, #0x{:X}] ; 0x{:08X}",
+            reg(instr >> 8 & 0x7),
+            offset,
+            target
+        )
+    } else if opcode & 0xF2 == 0x50 {
+        let op = ["str", "strb", "ldr", "ldrb"][(instr >> 10 & 0x3) as usize];
+        format!("{} {}, [{}, {}]", op, rd, rb, reg(instr >> 6 & 0x7))
+    } else if opcode & 0xF2 == 0x52 {
+        let op = ["strh", "ldrsb", "ldrh", "ldrsh"][(instr >> 10 & 0x3) as usize];
+        format!("{} {}, [{}, {}]", op, rd, rb, reg(instr >> 6 & 0x7))
+    } else if opcode & 0xE0 == 0x60 {
+        let (op, offset) = match (bit(12), bit(11)) {
+            (false, false) => ("str", (instr >> 6 & 0x1F) << 2),
+            (false, true) => ("ldr", (instr >> 6 & 0x1F) << 2),
+            (true, false) => ("strb", instr >> 6 & 0x1F),
+            (true, true) => ("ldrb", instr >> 6 & 0x1F),
+        };
+        format!("{} {}, [{}, #0x{:X}]", op, rd, rb, offset)
+    } else if opcode & 0xF0 == 0x80 {
+        let op = if bit(11) { "ldrh" } else { "strh" };
+        format!("{} {}, [{}, #0x{:X}]", op, rd, rb, (instr >> 6 & 0x1F) << 1)
+    } else if opcode & 0xF0 == 0x90 {
+        let op = if bit(11) { "ldr" } else { "str" };
+        let rd = reg(instr >> 8 & 0x7);
+        format!("{} {}, [sp, #0x{:X}]", op, rd, (instr & 0xFF) << 2)
+    } else if opcode & 0xF0 == 0xA0 {
+        let rd = reg(instr >> 8 & 0x7);
+        let offset = (instr & 0xFF) << 2;
+        if bit(11) {
+            format!("add {}, sp, #0x{:X}", rd, offset)
+        } else {
+            let target = (addr.wrapping_add(4) & !0x3).wrapping_add(offset);
+            format!("add {}, pc, #0x{:X} ; 0x{:08X}", rd, offset, target)
+        }
+    } else if opcode == 0xB0 {
+        let sign = if bit(7) { "-" } else { "" };
+        format!("add sp, #{}0x{:X}", sign, (instr & 0x7F) << 2)
+    } else if opcode & 0xF6 == 0xB4 {
+        let (op, extra_reg) = if bit(11) { ("pop", 15) } else { ("push", 14) };
+        let regs = instr & 0xFF | (bit(8) as u32) << extra_reg;
+        format!("{} {}", op, reg_list(regs))
+    } else if opcode & 0xF0 == 0xC0 {
+        let op = if bit(11) { "ldmia" } else { "stmia" };
+        format!(
+            "{} {}!, {}",
+            op,
+            reg(instr >> 8 & 0x7),
+            reg_list(instr & 0xFF)
+        )
+    } else if opcode == 0xDF {
+        format!("swi #0x{:X}", instr & 0xFF)
+    } else if opcode & 0xF0 == 0xD0 {
+        let offset = (instr & 0xFF) as u8 as i8 as u32;
+        let target = addr.wrapping_add(4).wrapping_add(offset << 1);
+        format!("b{} 0x{:08X}", CONDS[(instr >> 8 & 0xF) as usize], target)
+    } else if opcode & 0xF8 == 0xE0 {
+        let offset = ((instr << 21) as i32 >> 20) as u32;
+        format!("b 0x{:08X}", addr.wrapping_add(4).wrapping_add(offset))
+    } else if opcode & 0xF8 == 0xF0 {
+        let next = next as u32;
+        let high = ((instr << 21) as i32 >> 9) as u32;
+        let target = addr.wrapping_add(4).wrapping_add(high);
+        match next >> 11 {
+            0b11111 => format!("bl 0x{:08X}", target.wrapping_add((next & 0x7FF) << 1)),
+            0b11101 if arm9 => format!(
+                "blx 0x{:08X}",
+                target.wrapping_add((next & 0x7FF) << 1) & !0x3
+            ),
+            _ => format!("bl (prefix) 0x{:08X}", target),
+        }
+    } else if opcode & 0xF8 == 0xF8 {
+        format!("bl (suffix) lr + 0x{:X}", (instr & 0x7FF) << 1)
+    } else if arm9 && opcode & 0xF8 == 0xE8 {
+        format!("blx (suffix) lr + 0x{:X}", (instr & 0x7FF) << 1)
+    } else {
+        format!(".hword 0x{:04X}", instr)
+    }
+}
+
+fn reg(i: u32) -> &'static str {
+    REGS[(i & 0xF) as usize]
+}
+
+fn s_suffix(s: bool) -> &'static str {
+    if s {
+        "s"
+    } else {
+        ""
+    }
+}
+
+fn imm(value: u32, add: bool) -> String {
+    format!("#{}0x{:X}", if add { "" } else { "-" }, value)
+}
+
+fn rotated_imm(instr: u32) -> u32 {
+    (instr & 0xFF).rotate_right((instr >> 8 & 0xF) * 2)
+}
+
+fn branch_offset(instr: u32) -> u32 {
+    ((instr << 8) as i32 >> 6) as u32
+}
+
+fn shifted_reg(instr: u32) -> String {
+    let rm = reg(instr);
+    let shift_type = instr >> 5 & 0x3;
+    if instr >> 4 & 0x1 != 0 {
+        return format!(
+            "{}, {} {}",
+            rm,
+            SHIFTS[shift_type as usize],
+            reg(instr >> 8)
+        );
+    }
+    match (shift_type, instr >> 7 & 0x1F) {
+        (0, 0) => rm.to_string(),
+        (3, 0) => format!("{}, rrx", rm),
+        (_, 0) => format!("{}, {} #32", rm, SHIFTS[shift_type as usize]),
+        (_, shift) => format!("{}, {} #{}", rm, SHIFTS[shift_type as usize], shift),
+    }
+}
+
+fn address(base: &str, offset: &str, pre_index: bool, write_back: bool) -> String {
+    if !pre_index {
+        format!("[{}], {}", base, offset)
+    } else if offset == "#0x0" {
+        format!("[{}]{}", base, if write_back { "!" } else { "" })
+    } else {
+        format!(
+            "[{}, {}]{}",
+            base,
+            offset,
+            if write_back { "!" } else { "" }
+        )
+    }
+}
+
+fn reg_list(regs: u32) -> String {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < 16 {
+        if regs & 1 << i == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < 16 && regs & 1 << i != 0 {
+            i += 1;
+        }
+        ranges.push(match i - start {
+            1 => reg(start).to_string(),
+            2 => format!("{}, {}", reg(start), reg(start + 1)),
+            _ => format!("{}-{}", reg(start), reg(i - 1)),
+        });
+    }
+    format!("{{{}}}", ranges.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: u32 = 0x0200_0000;
+
+    #[test]
+    fn arm() {
+        let cases = [
+            (0xE3A0_0301, CPU::ARM9, "mov r0, #0x4000000"),
+            (0xE280_0001, CPU::ARM9, "add r0, r0, #0x1"),
+            (0xE091_0002, CPU::ARM9, "adds r0, r1, r2"),
+            (0xE150_0001, CPU::ARM9, "cmp r0, r1"),
+            (0x01A0_0000, CPU::ARM9, "moveq r0, r0"),
+            (0xE1A0_0620, CPU::ARM9, "mov r0, r0, lsr #12"),
+            (0xE1A0_0061, CPU::ARM9, "mov r0, r1, rrx"),
+            (0xE1A0_1312, CPU::ARM9, "mov r1, r2, lsl r3"),
+            (0xE001_0392, CPU::ARM9, "mul r1, r2, r3"),
+            (0xE0A1_0392, CPU::ARM9, "umlal r0, r1, r2, r3"),
+            (0xE101_0092, CPU::ARM9, "swp r0, r2, [r1]"),
+            (0xE1D1_00B2, CPU::ARM9, "ldrh r0, [r1, #0x2]"),
+            (0xE1C0_20D0, CPU::ARM9, "ldrd r2, [r0]"),
+            (0xE1C0_20D0, CPU::ARM7, ".word 0xE1C020D0"),
+            (0xE16F_0F11, CPU::ARM9, "clz r0, r1"),
+            (0xE101_0052, CPU::ARM9, "qadd r0, r2, r1"),
+            (0xE100_0382, CPU::ARM9, "smlabb r0, r2, r3, r0"),
+            (0xE10F_0000, CPU::ARM9, "mrs r0, cpsr"),
+            (0xE129_F000, CPU::ARM9, "msr cpsr_cf, r0"),
+            (0xE321_F0D3, CPU::ARM9, "msr cpsr_c, #0xD3"),
+            (0xE591_0004, CPU::ARM9, "ldr r0, [r1, #0x4]"),
+            (0xE59F_0004, CPU::ARM9, "ldr r0, [pc, #0x4] ; 0x0200000C"),
+            (0xE491_0004, CPU::ARM9, "ldr r0, [r1], #0x4"),
+            (0xE4B1_0004, CPU::ARM9, "ldrt r0, [r1], #0x4"),
+            (0xE5A1_0004, CPU::ARM9, "str r0, [r1, #0x4]!"),
+            (0xE791_0102, CPU::ARM9, "ldr r0, [r1, r2, lsl #2]"),
+            (0xE92D_500F, CPU::ARM9, "stmdb sp!, {r0-r3, r12, lr}"),
+            (0xE8BD_8003, CPU::ARM9, "ldmia sp!, {r0, r1, pc}"),
+            (0xE8D0_8000, CPU::ARM9, "ldmia r0, {pc}^"),
+            (0xEAFF_FFFE, CPU::ARM9, "b 0x02000000"),
+            (0xEB00_0000, CPU::ARM9, "bl 0x02000008"),
+            (0xE12F_FF1E, CPU::ARM7, "bx lr"),
+            (0xE12F_FF3E, CPU::ARM9, "blx lr"),
+            (0xFA00_0000, CPU::ARM9, "blx 0x02000008"),
+            (0xFB00_0000, CPU::ARM9, "blx 0x0200000A"),
+            (0xFA00_0000, CPU::ARM7, "bnv 0x02000008"),
+            (0xEF00_0005, CPU::ARM7, "swi #0x5"),
+            (0xEE19_0F11, CPU::ARM9, "mrc p15, 0, r0, c9, c1, 0"),
+        ];
+        for (instr, cpu, expected) in cases.iter() {
+            assert_eq!(
+                disassemble_arm(*instr, ADDR, *cpu),
+                *expected,
+                "0x{:08X} on {:?}",
+                instr,
+                cpu
+            );
+        }
+    }
+
+    #[test]
+    fn thumb() {
+        let cases = [
+            (0x1888, 0, CPU::ARM9, "add r0, r1, r2"),
+            (0x1E48, 0, CPU::ARM9, "sub r0, r1, #0x1"),
+            (0x0088, 0, CPU::ARM9, "lsl r0, r1, #2"),
+            (0x0808, 0, CPU::ARM9, "lsr r0, r1, #32"),
+            (0x2001, 0, CPU::ARM9, "mov r0, #0x1"),
+            (0x4248, 0, CPU::ARM9, "neg r0, r1"),
+            (0x4770, 0, CPU::ARM9, "bx lr"),
+            (0x47F0, 0, CPU::ARM9, "blx lr"),
+            (0x47F0, 0, CPU::ARM7, "bx lr"),
+            (0x4801, 0, CPU::ARM9, "ldr r0, [pc, #0x4] ; 0x02000008"),
+            (0x5888, 0, CPU::ARM9, "ldr r0, [r1, r2]"),
+            (0x5E88, 0, CPU::ARM9, "ldrsh r0, [r1, r2]"),
+            (0x6848, 0, CPU::ARM9, "ldr r0, [r1, #0x4]"),
+            (0x8848, 0, CPU::ARM9, "ldrh r0, [r1, #0x2]"),
+            (0x9801, 0, CPU::ARM9, "ldr r0, [sp, #0x4]"),
+            (0xA801, 0, CPU::ARM9, "add r0, sp, #0x4"),
+            (0xA001, 0, CPU::ARM9, "add r0, pc, #0x4 ; 0x02000008"),
+            (0xB082, 0, CPU::ARM9, "add sp, #-0x8"),
+            (0xB510, 0, CPU::ARM9, "push {r4, lr}"),
+            (0xBD10, 0, CPU::ARM9, "pop {r4, pc}"),
+            (0xC103, 0, CPU::ARM9, "stmia r1!, {r0, r1}"),
+            (0xDF05, 0, CPU::ARM9, "swi #0x5"),
+            (0xD0FE, 0, CPU::ARM9, "beq 0x02000000"),
+            (0xE7FE, 0, CPU::ARM9, "b 0x02000000"),
+            (0xF000, 0xF802, CPU::ARM9, "bl 0x02000008"),
+            (0xF7FF, 0xFFFE, CPU::ARM9, "bl 0x02000000"),
+            (0xF000, 0xE802, CPU::ARM9, "blx 0x02000008"),
+            (0xF000, 0xE802, CPU::ARM7, "bl (prefix) 0x02000004"),
+            (0xF802, 0, CPU::ARM9, "bl (suffix) lr + 0x4"),
+            (0xE802, 0, CPU::ARM9, "blx (suffix) lr + 0x4"),
+            (0xE802, 0, CPU::ARM7, ".hword 0xE802"),
+        ];
+        for (instr, next, cpu, expected) in cases.iter() {
+            assert_eq!(
+                disassemble_thumb(*instr, *next, ADDR, *cpu),
+                *expected,
+                "0x{:04X} 0x{:04X} on {:?}",
+                instr,
+                next,
+                cpu
+            );
+        }
+    }
+}
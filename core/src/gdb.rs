@@ -0,0 +1,497 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::nds::{StopReason, CPU, NDS};
+
+// Only the core registers are described so the g packet is R0-R15 followed by the CPSR
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>armv5te</architecture>
+  <feature name="org.gnu.gdb.arm.core">
+    <reg name="r0" bitsize="32" type="uint32"/>
+    <reg name="r1" bitsize="32" type="uint32"/>
+    <reg name="r2" bitsize="32" type="uint32"/>
+    <reg name="r3" bitsize="32" type="uint32"/>
+    <reg name="r4" bitsize="32" type="uint32"/>
+    <reg name="r5" bitsize="32" type="uint32"/>
+    <reg name="r6" bitsize="32" type="uint32"/>
+    <reg name="r7" bitsize="32" type="uint32"/>
+    <reg name="r8" bitsize="32" type="uint32"/>
+    <reg name="r9" bitsize="32" type="uint32"/>
+    <reg name="r10" bitsize="32" type="uint32"/>
+    <reg name="r11" bitsize="32" type="uint32"/>
+    <reg name="r12" bitsize="32" type="uint32"/>
+    <reg name="sp" bitsize="32" type="data_ptr"/>
+    <reg name="lr" bitsize="32"/>
+    <reg name="pc" bitsize="32" type="code_ptr"/>
+    <reg name="cpsr" bitsize="32" regnum="25"/>
+  </feature>
+</target>
+"#;
+const CPSR_REGNUM: usize = 25;
+const NUM_REGS: usize = 17;
+const MAX_MEM_LEN: usize = 0x800;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+enum Event {
+    Connected(TcpStream),
+    Data(Vec<u8>),
+    Disconnected,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Detached,
+    Halted,
+    Running,
+    // Whether the CPU has already run its instruction
+    Stepping(CPU, bool),
+}
+
+// GDB remote serial protocol stub, the ARM9 is thread 1 and the ARM7 is thread 2.
+// Breakpoints apply to both CPUs.
+pub struct GdbStub {
+    events: Receiver<Event>,
+    stream: Option<TcpStream>,
+    buffer: Vec<u8>,
+    state: State,
+    // Selected with Hg for register and memory accesses
+    thread: CPU,
+    signal: u8,
+    breakpoints: Vec<u32>,
+}
+
+impl GdbStub {
+    pub fn new(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        info!("GDB stub listening on port {}", port);
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                match stream.try_clone() {
+                    Ok(writer) => {
+                        if sender.send(Event::Connected(writer)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => continue,
+                }
+                let mut buffer = [0; 0x1000];
+                loop {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(len) => {
+                            if sender.send(Event::Data(buffer[..len].to_vec())).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                if sender.send(Event::Disconnected).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(GdbStub {
+            events,
+            stream: None,
+            buffer: Vec::new(),
+            state: State::Detached,
+            thread: CPU::ARM9,
+            signal: SIGTRAP,
+            breakpoints: Vec::new(),
+        })
+    }
+
+    pub fn attached(&self) -> bool {
+        self.state != State::Detached
+    }
+
+    // Handles the debugger's requests and emulates a frame unless it stopped the CPUs.
+    // Returns StopReason::Frame if a whole frame was emulated, why emulation stopped if no
+    // debugger is attached, or None if the debugger is handling the stop.
+    pub fn emulate_frame(&mut self, nds: &mut NDS) -> Option<StopReason> {
+        self.poll(nds);
+        let breakpoints = &self.breakpoints;
+        let stopped = match self.state {
+            State::Detached => return Some(nds.run_until(nds.frame() + 1)),
+            State::Halted => return None,
+            State::Running => nds.emulate_frame_until(|_, pc| breakpoints.contains(&pc)),
+            State::Stepping(step_cpu, mut stepped) => {
+                let stopped = nds.emulate_frame_until(|cpu, pc| {
+                    if cpu != step_cpu {
+                        breakpoints.contains(&pc)
+                    } else if stepped {
+                        true
+                    } else {
+                        stepped = true;
+                        false
+                    }
+                });
+                self.state = State::Stepping(step_cpu, stepped);
+                stopped
+            }
+        };
+        match stopped {
+            Some(reason) => {
+                self.stop(reason.cpu().unwrap_or(self.thread), SIGTRAP);
+                None
+            }
+            None => Some(StopReason::Frame),
+        }
+    }
+
+    // Handles the debugger's requests without emulating anything
+    pub fn poll(&mut self, nds: &mut NDS) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Event::Connected(stream) => {
+                    info!("GDB connected");
+                    self.stream = Some(stream);
+                    self.buffer.clear();
+                    self.breakpoints.clear();
+                    self.state = State::Halted;
+                    self.thread = CPU::ARM9;
+                    self.signal = SIGTRAP;
+                }
+                Event::Data(data) => self.buffer.extend(data),
+                Event::Disconnected => self.detach(),
+            }
+        }
+
+        while !self.buffer.is_empty() {
+            match self.buffer[0] {
+                b'$' => {
+                    let end = match self.buffer.iter().position(|&byte| byte == b'#') {
+                        Some(end) if self.buffer.len() >= end + 3 => end,
+                        _ => break,
+                    };
+                    let packet = String::from_utf8_lossy(&self.buffer[1..end]).into_owned();
+                    let checksum = std::str::from_utf8(&self.buffer[end + 1..end + 3])
+                        .ok()
+                        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+                    self.buffer.drain(..end + 3);
+                    if checksum == Some(GdbStub::checksum(packet.as_bytes())) {
+                        self.write(b"+");
+                        self.handle_packet(nds, &packet);
+                    } else {
+                        self.write(b"-");
+                    }
+                }
+                // Interrupt from Ctrl-C
+                0x03 => {
+                    self.buffer.remove(0);
+                    if let State::Running | State::Stepping(_, _) = self.state {
+                        self.stop(self.thread, SIGINT);
+                    }
+                }
+                // Acknowledgements
+                _ => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+    }
+
+    fn handle_packet(&mut self, nds: &mut NDS, packet: &str) {
+        let reply = if packet == "?" {
+            self.stop_reply()
+        } else if packet.starts_with("qSupported") {
+            "PacketSize=1000;qXfer:features:read+;vContSupported+".to_string()
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            GdbStub::read_xfer(TARGET_XML, args)
+        } else if packet == "qfThreadInfo" {
+            "m01,02".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if packet == "qC" {
+            format!("QC{:02x}", GdbStub::thread_id(self.thread))
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if let Some(id) = packet.strip_prefix("qThreadExtraInfo,") {
+            match GdbStub::parse_thread(id) {
+                Some(Some(cpu)) => GdbStub::encode_hex(format!("{:?}", cpu).as_bytes()),
+                _ => "E01".to_string(),
+            }
+        } else if let Some(id) = packet.strip_prefix("Hg") {
+            match GdbStub::parse_thread(id) {
+                Some(cpu) => {
+                    self.thread = cpu.unwrap_or(self.thread);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        } else if packet.starts_with('H') {
+            "OK".to_string()
+        } else if let Some(id) = packet.strip_prefix('T') {
+            match GdbStub::parse_thread(id) {
+                Some(Some(_)) => "OK".to_string(),
+                _ => "E01".to_string(),
+            }
+        } else if packet == "g" {
+            (0..NUM_REGS)
+                .map(|i| format!("{:08x}", nds.reg(self.thread, i).swap_bytes()))
+                .collect()
+        } else if let Some(data) = packet.strip_prefix('G') {
+            self.write_regs(nds, data)
+        } else if let Some(args) = packet.strip_prefix('p') {
+            match GdbStub::parse_reg(args) {
+                Some(i) => format!("{:08x}", nds.reg(self.thread, i).swap_bytes()),
+                None => "E01".to_string(),
+            }
+        } else if let Some(args) = packet.strip_prefix('P') {
+            self.write_reg(nds, args)
+        } else if let Some(args) = packet.strip_prefix('m') {
+            self.read_mem(nds, args)
+        } else if let Some(args) = packet.strip_prefix('M') {
+            self.write_mem(nds, args)
+        } else if let Some(args) = packet
+            .strip_prefix("Z0,")
+            .or_else(|| packet.strip_prefix("Z1,"))
+        {
+            match GdbStub::parse_breakpoint(args) {
+                Some(addr) => {
+                    if !self.breakpoints.contains(&addr) {
+                        self.breakpoints.push(addr);
+                    }
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        } else if let Some(args) = packet
+            .strip_prefix("z0,")
+            .or_else(|| packet.strip_prefix("z1,"))
+        {
+            match GdbStub::parse_breakpoint(args) {
+                Some(addr) => {
+                    self.breakpoints.retain(|breakpoint| *breakpoint != addr);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        } else if packet == "vCont?" {
+            "vCont;c;C;s;S".to_string()
+        } else if let Some(actions) = packet.strip_prefix("vCont;") {
+            // Only one CPU can be stepped, the other one keeps running alongside it
+            let step = actions.split(';').find_map(|action| {
+                if !action.starts_with('s') && !action.starts_with('S') {
+                    return None;
+                }
+                match action.split(':').nth(1).map(GdbStub::parse_thread) {
+                    Some(Some(Some(cpu))) => Some(cpu),
+                    _ => Some(self.thread),
+                }
+            });
+            self.resume(step);
+            return;
+        } else if packet.starts_with('c') || packet.starts_with('C') {
+            self.resume(None);
+            return;
+        } else if packet.starts_with('s') || packet.starts_with('S') {
+            self.resume(Some(self.thread));
+            return;
+        } else if packet == "D" {
+            self.send("OK");
+            self.detach();
+            return;
+        } else if packet == "k" {
+            self.detach();
+            return;
+        } else {
+            String::new()
+        };
+        self.send(&reply);
+    }
+
+    fn resume(&mut self, step: Option<CPU>) {
+        self.state = match step {
+            Some(cpu) => {
+                self.thread = cpu;
+                State::Stepping(cpu, false)
+            }
+            None => State::Running,
+        };
+    }
+
+    fn stop(&mut self, cpu: CPU, signal: u8) {
+        self.state = State::Halted;
+        self.thread = cpu;
+        self.signal = signal;
+        let reply = self.stop_reply();
+        self.send(&reply);
+    }
+
+    fn stop_reply(&self) -> String {
+        format!(
+            "T{:02x}thread:{:02x};",
+            self.signal,
+            GdbStub::thread_id(self.thread)
+        )
+    }
+
+    fn detach(&mut self) {
+        if self.stream.take().is_some() {
+            info!("GDB disconnected");
+        }
+        self.breakpoints.clear();
+        self.state = State::Detached;
+    }
+
+    fn write_regs(&mut self, nds: &mut NDS, data: &str) -> String {
+        if data.len() != NUM_REGS * 8 {
+            return "E01".to_string();
+        }
+        for i in 0..NUM_REGS {
+            let value = match GdbStub::parse_reg_value(&data[i * 8..i * 8 + 8]) {
+                Some(value) => value,
+                None => return "E01".to_string(),
+            };
+            if !nds.set_reg(self.thread, i, value) {
+                return "E01".to_string();
+            }
+        }
+        "OK".to_string()
+    }
+
+    fn write_reg(&mut self, nds: &mut NDS, args: &str) -> String {
+        let mut args = args.splitn(2, '=');
+        let i = args.next().and_then(GdbStub::parse_reg);
+        let value = args.next().and_then(GdbStub::parse_reg_value);
+        match (i, value) {
+            (Some(i), Some(value)) if nds.set_reg(self.thread, i, value) => "OK".to_string(),
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn read_mem(&mut self, nds: &mut NDS, args: &str) -> String {
+        match GdbStub::parse_addr_len(args) {
+            Some((addr, len)) => (0..len.min(MAX_MEM_LEN) as u32)
+                .map(|i| {
+                    let addr = addr.wrapping_add(i);
+                    let value: u8 = match self.thread {
+                        CPU::ARM9 => nds.peek9(addr),
+                        CPU::ARM7 => nds.peek7(addr),
+                    };
+                    format!("{:02x}", value)
+                })
+                .collect(),
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_mem(&mut self, nds: &mut NDS, args: &str) -> String {
+        let mut args = args.splitn(2, ':');
+        let addr_len = args.next().and_then(GdbStub::parse_addr_len);
+        let data = args.next().and_then(GdbStub::decode_hex);
+        match (addr_len, data) {
+            (Some((addr, len)), Some(data)) if data.len() == len => {
+                for (i, byte) in data.iter().enumerate() {
+                    let addr = addr.wrapping_add(i as u32);
+                    match self.thread {
+                        CPU::ARM9 => nds.poke9(addr, *byte),
+                        CPU::ARM7 => nds.poke7(addr, *byte),
+                    }
+                }
+                "OK".to_string()
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn read_xfer(data: &str, args: &str) -> String {
+        let (offset, len) = match GdbStub::parse_addr_len(args) {
+            Some((offset, len)) => (offset as usize, len),
+            None => return "E01".to_string(),
+        };
+        if offset >= data.len() {
+            return "l".to_string();
+        }
+        let end = (offset + len).min(data.len());
+        let prefix = if end == data.len() { 'l' } else { 'm' };
+        format!("{}{}", prefix, &data[offset..end])
+    }
+
+    fn send(&mut self, data: &str) {
+        let packet = format!("${}#{:02x}", data, GdbStub::checksum(data.as_bytes()));
+        self.write(packet.as_bytes());
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if let Some(stream) = &mut self.stream {
+            if stream.write_all(data).is_err() {
+                self.detach();
+            }
+        }
+    }
+
+    fn checksum(data: &[u8]) -> u8 {
+        data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+    }
+
+    fn thread_id(cpu: CPU) -> u32 {
+        match cpu {
+            CPU::ARM9 => 1,
+            CPU::ARM7 => 2,
+        }
+    }
+
+    // Returns Some(None) for any thread
+    fn parse_thread(id: &str) -> Option<Option<CPU>> {
+        match id {
+            "-1" | "0" => Some(None),
+            _ => match u32::from_str_radix(id, 16).ok()? {
+                1 => Some(Some(CPU::ARM9)),
+                2 => Some(Some(CPU::ARM7)),
+                _ => None,
+            },
+        }
+    }
+
+    fn parse_reg(regnum: &str) -> Option<usize> {
+        match usize::from_str_radix(regnum, 16).ok()? {
+            CPSR_REGNUM => Some(16),
+            i if i < 16 => Some(i),
+            _ => None,
+        }
+    }
+
+    // Registers are sent as little endian bytes
+    fn parse_reg_value(value: &str) -> Option<u32> {
+        Some(u32::from_str_radix(value, 16).ok()?.swap_bytes())
+    }
+
+    fn parse_addr_len(args: &str) -> Option<(u32, usize)> {
+        let mut args = args.splitn(2, ',');
+        let addr = u32::from_str_radix(args.next()?, 16).ok()?;
+        let len = usize::from_str_radix(args.next()?, 16).ok()?;
+        Some((addr, len))
+    }
+
+    // The kind of the breakpoint is ignored because the address is enough to match the PC
+    fn parse_breakpoint(args: &str) -> Option<u32> {
+        u32::from_str_radix(args.split(',').next()?, 16).ok()
+    }
+
+    fn encode_hex(data: &[u8]) -> String {
+        data.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn decode_hex(data: &str) -> Option<Vec<u8>> {
+        if !data.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..data.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,449 @@
+use std::f64::consts::PI;
+
+use super::{interrupt_controller::InterruptMasterEnable, spi::firmware::crc16, MemoryValue, HW};
+
+pub enum SWIResult {
+    Return,
+    // Return after spending the given number of cycles
+    Delay(usize),
+    // Rerun the SWI once the CPU is woken up by an interrupt
+    Repeat,
+}
+
+impl HW {
+    const BIOS7_SIZE: usize = 0x4000;
+    const BIOS9_SIZE: usize = 0x1000;
+    const IRQ_HANDLER_ADDR: usize = 0x20;
+
+    // Branches to the user IRQ handler at [0x0380FFFC] like the real BIOS
+    const IRQ_HANDLER7: [u32; 6] = [
+        0xE92D500F, // stmfd sp!, {r0-r3, r12, lr}
+        0xE3A00301, // mov r0, #0x04000000
+        0xE28FE000, // add lr, pc, #0
+        0xE510F004, // ldr pc, [r0, #-4]
+        0xE8BD500F, // ldmfd sp!, {r0-r3, r12, lr}
+        0xE25EF004, // subs pc, lr, #4
+    ];
+    // Branches to the user IRQ handler at [DTCM + 0x3FFC] like the real BIOS
+    const IRQ_HANDLER9: [u32; 9] = [
+        0xE92D500F, // stmfd sp!, {r0-r3, r12, lr}
+        0xEE190F11, // mrc p15, 0, r0, c9, c1, 0
+        0xE1A00620, // mov r0, r0, lsr #12
+        0xE1A00600, // mov r0, r0, lsl #12
+        0xE2800901, // add r0, r0, #0x4000
+        0xE28FE000, // add lr, pc, #0
+        0xE510F004, // ldr pc, [r0, #-4]
+        0xE8BD500F, // ldmfd sp!, {r0-r3, r12, lr}
+        0xE25EF004, // subs pc, lr, #4
+    ];
+
+    pub(super) fn hle_bios7() -> Vec<u8> {
+        HW::gen_hle_bios(HW::BIOS7_SIZE, &HW::IRQ_HANDLER7)
+    }
+
+    pub(super) fn hle_bios9() -> Vec<u8> {
+        HW::gen_hle_bios(HW::BIOS9_SIZE, &HW::IRQ_HANDLER9)
+    }
+
+    // SWIs are handled natively, so only the IRQ vector does anything
+    fn gen_hle_bios(size: usize, irq_handler: &[u32]) -> Vec<u8> {
+        let mut bios = vec![0; size];
+        for vector in (0x00..HW::IRQ_HANDLER_ADDR).step_by(4) {
+            let instr = if vector == 0x18 {
+                0xEA00_0000 | ((HW::IRQ_HANDLER_ADDR - vector - 8) / 4) as u32 // b irq_handler
+            } else {
+                0xEAFF_FFFE // b .
+            };
+            bios[vector..vector + 4].copy_from_slice(&instr.to_le_bytes());
+        }
+        for (i, instr) in irq_handler.iter().enumerate() {
+            let addr = HW::IRQ_HANDLER_ADDR + i * 4;
+            bios[addr..addr + 4].copy_from_slice(&instr.to_le_bytes());
+        }
+        bios
+    }
+
+    pub fn hle_swi(&mut self, is_arm9: bool, swi: u8, regs: &mut [u32; 4]) -> SWIResult {
+        match swi {
+            // Each loop iteration takes roughly 4 cycles
+            0x03 => return SWIResult::Delay(4 * regs[0] as usize),
+            0x04 => return self.intr_wait(is_arm9, regs[0] != 0, regs[1]),
+            0x05 => return self.intr_wait(is_arm9, true, 0x1),
+            0x06 => self.bios_halt(is_arm9),
+            0x09 => HW::bios_div(regs),
+            0x0B => self.cpu_set(is_arm9, regs[0], regs[1], regs[2]),
+            0x0C => self.cpu_fast_set(is_arm9, regs[0], regs[1], regs[2]),
+            0x0D => regs[0] = (regs[0] as f64).sqrt() as u32,
+            0x0E => regs[0] = self.get_crc16(is_arm9, regs[0] as u16, regs[1], regs[2]) as u32,
+            // Callback variants read the source directly, like the callbacks libnds passes
+            0x11 | 0x12 => {
+                let data = self.lz77_uncomp(is_arm9, regs[0]);
+                self.bios_write_output(is_arm9, regs[1], &data, if swi == 0x11 { 1 } else { 2 });
+            }
+            0x13 => {
+                let data = self.huff_uncomp(is_arm9, regs[0]);
+                self.bios_write_output(is_arm9, regs[1], &data, 4);
+            }
+            0x14 | 0x15 => {
+                let data = self.rl_uncomp(is_arm9, regs[0]);
+                self.bios_write_output(is_arm9, regs[1], &data, if swi == 0x14 { 1 } else { 2 });
+            }
+            0x1A if !is_arm9 => regs[0] = HW::get_sine_table(regs[0] as usize),
+            0x1B if !is_arm9 => regs[0] = HW::get_pitch_table(regs[0] as usize),
+            0x1C if !is_arm9 => regs[0] = HW::get_volume_table(regs[0] as usize),
+            _ => warn!(
+                "Unimplemented HLE SWI 0x{:X} on ARM{}",
+                swi,
+                if is_arm9 { 9 } else { 7 }
+            ),
+        }
+        SWIResult::Return
+    }
+
+    fn intr_wait(&mut self, is_arm9: bool, discard_old: bool, flags: u32) -> SWIResult {
+        let check_addr = if is_arm9 {
+            self.cp15.dtcm_base().wrapping_add(0x3FF8)
+        } else {
+            0x0380_FFF8
+        };
+        let check = self.bios_read::<u32>(is_arm9, check_addr);
+        // Old flags are only discarded on the first call, not when rerun after an interrupt
+        if check & flags != 0 && (!discard_old || self.intr_waiting[is_arm9 as usize]) {
+            self.intr_waiting[is_arm9 as usize] = false;
+            self.bios_write(is_arm9, check_addr, check & !flags);
+            SWIResult::Return
+        } else {
+            if !self.intr_waiting[is_arm9 as usize] {
+                self.intr_waiting[is_arm9 as usize] = true;
+                self.bios_write(is_arm9, check_addr, check & !flags);
+            }
+            self.interrupts[is_arm9 as usize].master_enable = InterruptMasterEnable::ENABLE;
+            self.bios_halt(is_arm9);
+            SWIResult::Repeat
+        }
+    }
+
+    fn bios_halt(&mut self, is_arm9: bool) {
+        if is_arm9 {
+            self.cp15.arm9_halted = true;
+        } else {
+            self.haltcnt.halt();
+        }
+    }
+
+    fn bios_div(regs: &mut [u32; 4]) {
+        let numerator = regs[0] as i32;
+        let denominator = regs[1] as i32;
+        if denominator == 0 {
+            warn!("BIOS Division by 0");
+            regs[0] = if numerator < 0 { -1i32 as u32 } else { 1 };
+            regs[1] = numerator as u32;
+            regs[3] = 1;
+        } else {
+            let quotient = numerator.wrapping_div(denominator);
+            regs[0] = quotient as u32;
+            regs[1] = numerator.wrapping_rem(denominator) as u32;
+            regs[3] = quotient.wrapping_abs() as u32;
+        }
+    }
+
+    fn cpu_set(&mut self, is_arm9: bool, src: u32, dest: u32, control: u32) {
+        let count = control & 0x1F_FFFF;
+        let fixed = control >> 24 & 0x1 != 0;
+        if control >> 26 & 0x1 != 0 {
+            self.bios_copy::<u32>(is_arm9, src & !0x3, dest & !0x3, count, fixed);
+        } else {
+            self.bios_copy::<u16>(is_arm9, src & !0x1, dest & !0x1, count, fixed);
+        }
+    }
+
+    fn cpu_fast_set(&mut self, is_arm9: bool, src: u32, dest: u32, control: u32) {
+        // Copies in blocks of 8 words
+        let count = ((control & 0x1F_FFFF) + 7) & !0x7;
+        let fixed = control >> 24 & 0x1 != 0;
+        self.bios_copy::<u32>(is_arm9, src & !0x3, dest & !0x3, count, fixed);
+    }
+
+    fn bios_copy<T: MemoryValue>(
+        &mut self,
+        is_arm9: bool,
+        src: u32,
+        dest: u32,
+        count: u32,
+        fixed: bool,
+    ) {
+        let size = std::mem::size_of::<T>() as u32;
+        let fill_value = if fixed {
+            Some(self.bios_read::<T>(is_arm9, src))
+        } else {
+            None
+        };
+        for i in 0..count {
+            let value = match fill_value {
+                Some(value) => value,
+                None => self.bios_read::<T>(is_arm9, src.wrapping_add(i * size)),
+            };
+            self.bios_write(is_arm9, dest.wrapping_add(i * size), value);
+        }
+    }
+
+    fn get_crc16(&mut self, is_arm9: bool, initial: u16, addr: u32, len: u32) -> u16 {
+        let data = (0..len & !0x1)
+            .map(|i| self.bios_read::<u8>(is_arm9, addr.wrapping_add(i)))
+            .collect::<Vec<_>>();
+        crc16(initial, &data)
+    }
+
+    fn lz77_uncomp(&mut self, is_arm9: bool, src: u32) -> Vec<u8> {
+        let size = (self.bios_read::<u32>(is_arm9, src) >> 8) as usize;
+        let mut src = src.wrapping_add(4);
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let flags = self.bios_read::<u8>(is_arm9, src);
+            src = src.wrapping_add(1);
+            for i in (0..8).rev() {
+                if data.len() >= size {
+                    break;
+                }
+                if flags >> i & 0x1 == 0 {
+                    data.push(self.bios_read::<u8>(is_arm9, src));
+                    src = src.wrapping_add(1);
+                } else {
+                    let byte0 = self.bios_read::<u8>(is_arm9, src) as usize;
+                    let byte1 = self.bios_read::<u8>(is_arm9, src.wrapping_add(1)) as usize;
+                    src = src.wrapping_add(2);
+                    let disp = ((byte0 & 0xF) << 8 | byte1) + 1;
+                    let len = (byte0 >> 4) + 3;
+                    for _ in 0..len {
+                        let byte = match data.len().checked_sub(disp) {
+                            Some(addr) => data[addr],
+                            None => 0,
+                        };
+                        data.push(byte);
+                    }
+                }
+            }
+        }
+        data.truncate(size);
+        data
+    }
+
+    fn huff_uncomp(&mut self, is_arm9: bool, src: u32) -> Vec<u8> {
+        let header = self.bios_read::<u32>(is_arm9, src);
+        let data_bits = header & 0xF;
+        let size = (header >> 8) as usize;
+        if data_bits != 4 && data_bits != 8 {
+            warn!("Unsupported Huffman data size of {} bits", data_bits);
+            return Vec::new();
+        }
+        let tree_size = self.bios_read::<u8>(is_arm9, src.wrapping_add(4)) as u32;
+        let root_addr = src.wrapping_add(5);
+        let mut bitstream_addr = src.wrapping_add(4 + (tree_size + 1) * 2);
+
+        let mut data = Vec::with_capacity(size);
+        let mut cur_byte = 0;
+        let mut cur_bits = 0;
+        let mut node_addr = root_addr;
+        let mut node = self.bios_read::<u8>(is_arm9, node_addr);
+        while data.len() < size {
+            let bits = self.bios_read::<u32>(is_arm9, bitstream_addr);
+            bitstream_addr = bitstream_addr.wrapping_add(4);
+            for i in (0..32).rev() {
+                let bit = bits >> i & 0x1;
+                let child_addr =
+                    (node_addr & !0x1).wrapping_add((node & 0x3F) as u32 * 2 + 2 + bit);
+                if node >> (7 - bit) & 0x1 != 0 {
+                    // Child is a data node
+                    let value = self.bios_read::<u8>(is_arm9, child_addr) as u32;
+                    cur_byte |= (value & ((1 << data_bits) - 1)) << cur_bits;
+                    cur_bits += data_bits;
+                    if cur_bits >= 8 {
+                        data.push(cur_byte as u8);
+                        cur_byte = 0;
+                        cur_bits = 0;
+                    }
+                    node_addr = root_addr;
+                    if data.len() >= size {
+                        break;
+                    }
+                } else {
+                    node_addr = child_addr;
+                }
+                node = self.bios_read::<u8>(is_arm9, node_addr);
+            }
+        }
+        data.truncate(size);
+        data
+    }
+
+    fn rl_uncomp(&mut self, is_arm9: bool, src: u32) -> Vec<u8> {
+        let size = (self.bios_read::<u32>(is_arm9, src) >> 8) as usize;
+        let mut src = src.wrapping_add(4);
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let flag = self.bios_read::<u8>(is_arm9, src);
+            src = src.wrapping_add(1);
+            if flag & 0x80 != 0 {
+                let byte = self.bios_read::<u8>(is_arm9, src);
+                src = src.wrapping_add(1);
+                for _ in 0..(flag & 0x7F) + 3 {
+                    data.push(byte);
+                }
+            } else {
+                for _ in 0..(flag & 0x7F) + 1 {
+                    data.push(self.bios_read::<u8>(is_arm9, src));
+                    src = src.wrapping_add(1);
+                }
+            }
+        }
+        data.truncate(size);
+        data
+    }
+
+    fn bios_write_output(&mut self, is_arm9: bool, dest: u32, data: &[u8], unit_size: usize) {
+        for (i, unit) in data.chunks(unit_size).enumerate() {
+            let addr = dest.wrapping_add((i * unit_size) as u32);
+            let mut bytes = [0; 4];
+            bytes[..unit.len()].copy_from_slice(unit);
+            match unit_size {
+                1 => self.bios_write(is_arm9, addr, bytes[0]),
+                2 => self.bios_write(is_arm9, addr, u16::from_le_bytes([bytes[0], bytes[1]])),
+                4 => self.bios_write(is_arm9, addr, u32::from_le_bytes(bytes)),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // Tables are generated from their formulas instead of being copied from the BIOS
+    fn get_sine_table(index: usize) -> u32 {
+        let index = index & 0x3F;
+        ((index as f64 * PI / 128.0).sin() * 0x7FFF as f64).round() as u32
+    }
+
+    fn get_pitch_table(index: usize) -> u32 {
+        let index = index % 0x300;
+        ((2f64.powf(index as f64 / 768.0) - 1.0) * 0x10000 as f64).round() as u32
+    }
+
+    fn get_volume_table(index: usize) -> u32 {
+        // Indices are 0.1 dB steps up to 0 dB, quieter ranges are scaled up to match the
+        // shift the sound driver applies for them
+        let index = index.min(0x2D3);
+        let decibels = (index as f64 - 723.0) / 10.0;
+        let scale = if decibels < -24.0 {
+            16.0
+        } else if decibels < -12.0 {
+            4.0
+        } else if decibels < -6.0 {
+            2.0
+        } else {
+            1.0
+        };
+        (127.0 * 10f64.powf(decibels / 20.0) * scale)
+            .round()
+            .min(127.0) as u32
+    }
+
+    fn bios_read<T: MemoryValue>(&mut self, is_arm9: bool, addr: u32) -> T {
+        if is_arm9 {
+            self.arm9_read(addr)
+        } else {
+            self.arm7_read(addr)
+        }
+    }
+
+    fn bios_write<T: MemoryValue>(&mut self, is_arm9: bool, addr: u32, value: T) {
+        if is_arm9 {
+            self.arm9_write(addr, value)
+        } else {
+            self.arm7_write(addr, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nds::tests::test_hw;
+
+    const SRC: u32 = 0x0200_0000;
+
+    fn uncomp(uncomp: fn(&mut HW, bool, u32) -> Vec<u8>, cases: &[(&[u8], &[u8])]) {
+        let mut hw = test_hw();
+        for (compressed, expected) in cases.iter() {
+            for (i, byte) in compressed.iter().enumerate() {
+                hw.bios_write(true, SRC + i as u32, *byte);
+            }
+            assert_eq!(uncomp(&mut hw, true, SRC), *expected, "{:02X?}", compressed);
+        }
+    }
+
+    #[test]
+    fn lz77() {
+        uncomp(
+            HW::lz77_uncomp,
+            &[
+                (&[0x10, 0x03, 0x00, 0x00, 0x00, b'A', b'B', b'C'], b"ABC"),
+                // 3 literals then 9 bytes copied from 3 back
+                (
+                    &[0x10, 0x0C, 0x00, 0x00, 0x10, b'A', b'B', b'C', 0x60, 0x02],
+                    b"ABCABCABCABC",
+                ),
+                // A copy longer than the remaining size is cut off
+                (&[0x10, 0x04, 0x00, 0x00, 0x40, b'x', 0xF0, 0x00], b"xxxx"),
+            ],
+        );
+    }
+
+    #[test]
+    fn rl() {
+        uncomp(
+            HW::rl_uncomp,
+            &[
+                (
+                    &[0x30, 0x07, 0x00, 0x00, 0x82, b'A', 0x01, b'B', b'C'],
+                    b"AAAAABC",
+                ),
+                (&[0x30, 0x03, 0x00, 0x00, 0x80, b'Z'], b"ZZZ"),
+                (&[0x30, 0x02, 0x00, 0x00, 0x00, b'Q', 0x00, b'R'], b"QR"),
+            ],
+        );
+    }
+
+    #[test]
+    fn huff() {
+        uncomp(
+            HW::huff_uncomp,
+            &[
+                // A root with the data nodes A (0) and B (1)
+                (
+                    &[
+                        0x28, 0x04, 0x00, 0x00, 0x01, 0xC0, b'A', b'B', 0x00, 0x00, 0x00, 0x60,
+                    ],
+                    b"ABBA",
+                ),
+                // 4 (0), 1 (10) and 2 (11) decode the nibbles of "AB" low nibble first
+                (
+                    &[
+                        0x24, 0x02, 0x00, 0x00, 0x03, 0x80, 0x04, 0xC0, 0x01, 0x02, 0x00, 0x00,
+                        0x00, 0x00, 0x00, 0x98,
+                    ],
+                    b"AB",
+                ),
+                // Only 4 and 8 bit data is supported
+                (&[0x20, 0x04, 0x00, 0x00, 0x01, 0xC0, b'A', b'B'], b""),
+                (&[0x22, 0x04, 0x00, 0x00, 0x01, 0xC0, b'A', b'B'], b""),
+            ],
+        );
+    }
+
+    #[test]
+    fn copy_wraps() {
+        let mut hw = test_hw();
+        // Fill 8 words from the end of the address space into the start of ITCM
+        hw.bios_copy::<u32>(true, SRC, 0xFFFF_FFF0, 8, true);
+        hw.lz77_uncomp(true, 0xFFFF_FFFC);
+        hw.rl_uncomp(true, 0xFFFF_FFFC);
+        hw.huff_uncomp(true, 0xFFFF_FFFC);
+    }
+}
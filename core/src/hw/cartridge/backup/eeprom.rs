@@ -1,12 +1,17 @@
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 use std::path::PathBuf;
 
-use super::Backup;
+use super::{Backup, Save};
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
 
+#[derive(Serialize, Deserialize)]
 pub struct EEPROM<T: EEPROMType> {
     eeprom_type: PhantomData<T>,
     mem: Vec<u8>,
-    save_file: PathBuf,
+    #[serde(skip)]
+    save_file: Option<PathBuf>,
+    #[serde(skip)]
     dirty: bool,
 
     mode: Mode,
@@ -17,10 +22,11 @@ pub struct EEPROM<T: EEPROMType> {
 }
 
 impl<T: EEPROMType> EEPROM<T> {
-    pub fn new(save_file: PathBuf, size: usize) -> EEPROM<T> {
+    pub fn new(save: Save, size: usize) -> EEPROM<T> {
+        let (save_file, mem) = Backup::get_initial_mem(save, 0, size);
         EEPROM {
             eeprom_type: PhantomData,
-            mem: Backup::get_initial_mem(&save_file, 0, size),
+            mem,
             save_file,
             dirty: false,
 
@@ -97,23 +103,35 @@ impl<T: EEPROMType> Backup for EEPROM<T> {
     fn mem(&self) -> &Vec<u8> {
         &self.mem
     }
-    fn save_file(&self) -> &PathBuf {
-        &self.save_file
+    fn save_file(&self) -> Option<&PathBuf> {
+        self.save_file.as_ref()
     }
     fn dirty(&mut self) -> bool {
         let old = self.dirty;
         self.dirty = false;
         old
     }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write(self);
+    }
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        let mut backup: EEPROM<T> = state.read()?;
+        backup.save_file = self.save_file.take();
+        // Write the restored contents back so the save file matches the loaded state
+        backup.dirty = true;
+        *self = backup;
+        Ok(())
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum Mode {
     ReadCommand,
     HandleCommand(Command),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum Command {
     WR(usize, usize), // Write
     RD(usize, usize), // Read
@@ -137,7 +155,7 @@ impl Command {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 enum WriteProtect {
     None = 0,
     _UpperQuarter = 1,
@@ -1,10 +1,15 @@
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-use super::Backup;
+use super::{Backup, Save};
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
 
+#[derive(Serialize, Deserialize)]
 pub struct Flash {
-    save_file: PathBuf,
+    #[serde(skip)]
+    save_file: Option<PathBuf>,
     mem: Vec<u8>,
+    #[serde(skip)]
     dirty: bool,
 
     mode: Mode,
@@ -14,9 +19,10 @@ pub struct Flash {
 }
 
 impl Flash {
-    pub fn new_backup(save_file: PathBuf, size: usize) -> Self {
+    pub fn new_backup(save: Save, size: usize) -> Self {
+        let (save_file, mem) = Backup::get_initial_mem(save, 0xFF, size);
         Flash {
-            mem: Backup::get_initial_mem(&save_file, 0xFF, size),
+            mem,
             save_file,
             dirty: false,
 
@@ -30,7 +36,7 @@ impl Flash {
     pub fn new_firmware(firmware: Vec<u8>) -> Self {
         Flash {
             mem: firmware,
-            save_file: PathBuf::new(),
+            save_file: None,
             dirty: false,
 
             mode: Mode::ReadInstr,
@@ -108,23 +114,35 @@ impl Backup for Flash {
     fn mem(&self) -> &Vec<u8> {
         &self.mem
     }
-    fn save_file(&self) -> &PathBuf {
-        &self.save_file
+    fn save_file(&self) -> Option<&PathBuf> {
+        self.save_file.as_ref()
     }
     fn dirty(&mut self) -> bool {
         let old = self.dirty;
         self.dirty = false;
         old
     }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write(self);
+    }
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        let mut backup: Flash = state.read()?;
+        backup.save_file = self.save_file.take();
+        // Write the restored contents back so the save file matches the loaded state
+        backup.dirty = true;
+        *self = backup;
+        Ok(())
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum Mode {
     ReadInstr,
     HandleInstr(Instr),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum Instr {
     IR,
     READ(usize, usize),
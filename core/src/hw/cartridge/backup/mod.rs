@@ -6,7 +6,7 @@ mod no_backup;
 use std::fs;
 use std::path::PathBuf;
 
-use super::Header;
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
 
 use eeprom::{EEPROMNormal, EEPROMSmall, EEPROM};
 pub use flash::Flash;
@@ -17,47 +17,86 @@ pub trait Backup {
     fn write(&mut self, hold: bool, value: u8);
 
     fn mem(&self) -> &Vec<u8>;
-    fn save_file(&self) -> &PathBuf;
+    fn save_file(&self) -> Option<&PathBuf>;
     fn dirty(&mut self) -> bool;
+
+    fn save_state(&self, state: &mut StateWriter);
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError>;
 }
 
 impl dyn Backup {
-    pub fn detect_type(header: &Header, save_file: PathBuf) -> Box<dyn Backup> {
-        let game_code = u32::from_le_bytes(header.game_code);
-        if let Some(pos) = Backup::GAME_DB
-            .iter()
-            .position(|game_info| game_info.game_code == game_code)
-        {
-            let game_info = &Backup::GAME_DB[pos];
-            let sram_size = Backup::SRAM_SIZES[game_info.sram_type];
-            match game_info.sram_type {
-                1 => Box::new(EEPROM::<EEPROMSmall>::new(save_file, sram_size)),
-                2..=4 => Box::new(EEPROM::<EEPROMNormal>::new(save_file, sram_size)),
-                5..=8 => Box::new(Flash::new_backup(save_file, sram_size)),
-                _ => todo!(),
-            }
-        } else {
-            warn!("Game not found in DB!");
-            Box::new(NoBackup::new())
+    pub fn from_type(save_type: SaveType, save: Save) -> Box<dyn Backup> {
+        match save_type {
+            SaveType::None => Box::new(NoBackup::new()),
+            SaveType::EEPROM(0x200) => Box::new(EEPROM::<EEPROMSmall>::new(save, 0x200)),
+            SaveType::EEPROM(size) => Box::new(EEPROM::<EEPROMNormal>::new(save, size)),
+            SaveType::Flash(size) => Box::new(Flash::new_backup(save, size)),
         }
     }
 
-    fn get_initial_mem(save_file: &PathBuf, default_val: u8, size: usize) -> Vec<u8> {
-        if let Ok(mem) = fs::read(save_file) {
-            if mem.len() == size {
-                mem
-            } else {
-                vec![default_val; size]
+    fn get_initial_mem(save: Save, default_val: u8, size: usize) -> (Option<PathBuf>, Vec<u8>) {
+        let (save_file, mem) = match save {
+            Save::File(save_file) => {
+                let mem = fs::read(&save_file).ok();
+                (Some(save_file), mem)
             }
-        } else {
-            vec![default_val; size]
+            Save::Memory(mem) => (None, Some(mem)),
+        };
+        match mem {
+            Some(mem) if mem.len() == size => (save_file, mem),
+            _ => (save_file, vec![default_val; size]),
         }
     }
 
     pub fn save(&mut self) {
         if self.dirty() {
-            fs::write(self.save_file(), self.mem())
-                .unwrap_or_else(|err| warn!("Unable to Save to File: {}!", err))
+            if let Some(save_file) = self.save_file() {
+                fs::write(save_file, self.mem())
+                    .unwrap_or_else(|err| warn!("Unable to Save to File: {}!", err))
+            }
+        }
+    }
+}
+
+pub enum Save {
+    File(PathBuf),
+    // Starts from the given contents and is never written to disk
+    Memory(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SaveType {
+    None,
+    EEPROM(usize),
+    Flash(usize),
+}
+
+impl SaveType {
+    // Returns None if the game's save type is known to exist but isn't supported
+    pub fn detect(game_code: [u8; 4]) -> Option<SaveType> {
+        let game_code = u32::from_le_bytes(game_code);
+        if let Some(game_info) = Backup::GAME_DB
+            .iter()
+            .find(|game_info| game_info.game_code == game_code)
+        {
+            match game_info.sram_type {
+                0 => Some(SaveType::None),
+                1..=4 => Some(SaveType::EEPROM(Backup::SRAM_SIZES[game_info.sram_type])),
+                5..=8 => Some(SaveType::Flash(Backup::SRAM_SIZES[game_info.sram_type])),
+                _ => None,
+            }
+        } else {
+            warn!("Game not found in DB!");
+            Some(SaveType::None)
+        }
+    }
+
+    // Only the chip sizes found in the game database are emulated
+    pub fn has_valid_size(&self) -> bool {
+        match self {
+            SaveType::None => true,
+            SaveType::EEPROM(size) => <dyn Backup>::SRAM_SIZES[1..=4].contains(size),
+            SaveType::Flash(size) => <dyn Backup>::SRAM_SIZES[5..=8].contains(size),
         }
     }
 }
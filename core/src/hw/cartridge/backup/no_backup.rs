@@ -1,8 +1,11 @@
 use std::path::PathBuf;
 
 use super::Backup;
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
 
-pub struct NoBackup {}
+pub struct NoBackup {
+    mem: Vec<u8>, // Always empty
+}
 
 impl Backup for NoBackup {
     fn read(&self) -> u8 {
@@ -11,18 +14,23 @@ impl Backup for NoBackup {
     fn write(&mut self, _hold: bool, _value: u8) {}
 
     fn mem(&self) -> &Vec<u8> {
-        unreachable!()
+        &self.mem
     }
-    fn save_file(&self) -> &PathBuf {
-        unreachable!()
+    fn save_file(&self) -> Option<&PathBuf> {
+        None
     }
     fn dirty(&mut self) -> bool {
         false
     }
+
+    fn save_state(&self, _state: &mut StateWriter) {}
+    fn load_state(&mut self, _state: &mut StateReader) -> Result<(), LoadStateError> {
+        Ok(())
+    }
 }
 
 impl NoBackup {
     pub fn new() -> Self {
-        NoBackup {}
+        NoBackup { mem: Vec::new() }
     }
 }
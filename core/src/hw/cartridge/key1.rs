@@ -0,0 +1,178 @@
+use std::convert::TryInto;
+
+// Blowfish based KEY1 encryption used by the cartridge protocol and the secure area
+pub struct Key1 {
+    keybuf: Vec<u32>,
+    keycode: [u32; 3],
+}
+
+impl Key1 {
+    const KEYBUF_LEN: usize = 0x412;
+    const BIOS_KEYBUF_ADDR: usize = 0x30;
+
+    pub fn new(bios7: &[u8], idcode: u32, level: usize, modulo: usize) -> Self {
+        let keybuf = bios7[Key1::BIOS_KEYBUF_ADDR..Key1::BIOS_KEYBUF_ADDR + Key1::KEYBUF_LEN * 4]
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        let mut key1 = Key1 {
+            keybuf,
+            keycode: [idcode, idcode >> 1, idcode << 1],
+        };
+        if level >= 1 {
+            key1.apply_keycode(modulo)
+        }
+        if level >= 2 {
+            key1.apply_keycode(modulo)
+        }
+        key1.keycode[1] <<= 1;
+        key1.keycode[2] >>= 1;
+        if level >= 3 {
+            key1.apply_keycode(modulo)
+        }
+        key1
+    }
+
+    pub fn encrypt(&self, data: [u32; 2]) -> [u32; 2] {
+        let [mut y, mut x] = data;
+        for i in 0x00..=0x0F {
+            let z = self.keybuf[i] ^ x;
+            x = y ^ self.mix(z);
+            y = z;
+        }
+        [x ^ self.keybuf[0x10], y ^ self.keybuf[0x11]]
+    }
+
+    pub fn decrypt(&self, data: [u32; 2]) -> [u32; 2] {
+        let [mut y, mut x] = data;
+        for i in (0x02..=0x11).rev() {
+            let z = self.keybuf[i] ^ x;
+            x = y ^ self.mix(z);
+            y = z;
+        }
+        [x ^ self.keybuf[0x01], y ^ self.keybuf[0x00]]
+    }
+
+    fn mix(&self, z: u32) -> u32 {
+        let mut x = self.keybuf[0x012 + (z >> 24 & 0xFF) as usize];
+        x = x.wrapping_add(self.keybuf[0x112 + (z >> 16 & 0xFF) as usize]);
+        x ^= self.keybuf[0x212 + (z >> 8 & 0xFF) as usize];
+        x.wrapping_add(self.keybuf[0x312 + (z & 0xFF) as usize])
+    }
+
+    fn apply_keycode(&mut self, modulo: usize) {
+        let [a, b] = self.encrypt([self.keycode[1], self.keycode[2]]);
+        self.keycode[1] = a;
+        self.keycode[2] = b;
+        let [a, b] = self.encrypt([self.keycode[0], self.keycode[1]]);
+        self.keycode[0] = a;
+        self.keycode[1] = b;
+        for i in 0..=0x11 {
+            self.keybuf[i] ^= self.keycode[(i * 4 % modulo) / 4].swap_bytes();
+        }
+        let mut scratch = [0; 2];
+        for i in (0..Key1::KEYBUF_LEN).step_by(2) {
+            scratch = self.encrypt(scratch);
+            self.keybuf[i] = scratch[1];
+            self.keybuf[i + 1] = scratch[0];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Cartridge;
+    use super::*;
+
+    // Stand-in for the BIOS key table, which can't be shipped
+    fn test_bios7() -> Vec<u8> {
+        let mut seed = 0x1234_5678u32;
+        let mut bios7 = vec![0; 0x4000];
+        for byte in bios7.iter_mut() {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *byte = (seed >> 24) as u8;
+        }
+        bios7
+    }
+
+    #[test]
+    fn zero_key() {
+        // With an empty key table every round only swaps the halves
+        let key1 = Key1::new(&[0; 0x4000], 0, 0, 8);
+        let cases = [
+            ([0x0000_0000, 0x0000_0000], [0x0000_0000, 0x0000_0000]),
+            ([0x1234_5678, 0x9ABC_DEF0], [0x9ABC_DEF0, 0x1234_5678]),
+            ([0xFFFF_FFFF, 0x0000_0001], [0x0000_0001, 0xFFFF_FFFF]),
+        ];
+        for (plain, encrypted) in cases.iter() {
+            assert_eq!(key1.encrypt(*plain), *encrypted);
+            assert_eq!(key1.decrypt(*encrypted), *plain);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let bios7 = test_bios7();
+        let cases = [
+            (u32::from_le_bytes(*b"AMCE"), 1, 8),
+            (u32::from_le_bytes(*b"AMCE"), 2, 8),
+            (u32::from_le_bytes(*b"AMCE"), 3, 8),
+            (u32::from_le_bytes(*b"ASME"), 2, 12),
+            (0, 3, 8),
+        ];
+        let blocks = [
+            [0, 0],
+            [0x6F72_6379, 0x6A62_4F6E],
+            [0xFFFF_FFFF, 0x8000_0001],
+        ];
+        for &(idcode, level, modulo) in cases.iter() {
+            let key1 = Key1::new(&bios7, idcode, level, modulo);
+            for block in blocks.iter() {
+                let encrypted = key1.encrypt(*block);
+                assert_ne!(encrypted, *block, "{:08X} level {}", idcode, level);
+                assert_eq!(
+                    key1.decrypt(encrypted),
+                    *block,
+                    "{:08X} level {}",
+                    idcode,
+                    level
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn secure_area() {
+        let bios7 = test_bios7();
+        let idcode = u32::from_le_bytes(*b"AMCE");
+        let mut rom = vec![0; 0x8000];
+        for (i, byte) in rom[0x4000..0x4800].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        rom[0x4000..0x4008].copy_from_slice(&[0xFF, 0xDE, 0xFF, 0xE7, 0xFF, 0xDE, 0xFF, 0xE7]);
+        let plain = rom.clone();
+        Cartridge::encrypt_secure_area(&mut rom, &bios7, idcode);
+        assert_ne!(rom[0x4000..0x4800], plain[0x4000..0x4800]);
+        assert_eq!(rom[0x4800..], plain[0x4800..]);
+
+        // Decrypt the way the BIOS does, the ID block at level 2 then everything at level 3
+        let read = |rom: &[u8], addr: usize| {
+            let word = |addr: usize| u32::from_le_bytes(rom[addr..addr + 4].try_into().unwrap());
+            [word(addr), word(addr + 4)]
+        };
+        let mut decrypted = vec![Key1::new(&bios7, idcode, 2, 8).decrypt(read(&rom, 0x4000))];
+        decrypted.extend((0x4008..0x4800).step_by(8).map(|addr| read(&rom, addr)));
+        let level3 = Key1::new(&bios7, idcode, 3, 8);
+        let decrypted: Vec<u8> = decrypted
+            .into_iter()
+            .flat_map(|block| {
+                let [lo, hi] = level3.decrypt(block);
+                let mut bytes = lo.to_le_bytes().to_vec();
+                bytes.extend_from_slice(&hi.to_le_bytes());
+                bytes
+            })
+            .collect();
+        assert_eq!(&decrypted[..8], b"encryObj");
+        assert_eq!(decrypted[8..], plain[0x4008..0x4800]);
+    }
+}
@@ -1,10 +1,11 @@
 mod backup;
 mod header;
+mod key1;
 
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::ops::Range;
-use std::path::PathBuf;
 
 use super::{
     dma::DMAOccasion,
@@ -12,20 +13,25 @@ use super::{
     scheduler::{Event, Scheduler},
     HW,
 };
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
 
 use header::Header;
+use key1::Key1;
 
 pub(super) use backup::{Backup, Flash}; // For Firmware
+pub use backup::{Save, SaveType};
 
 pub struct Cartridge {
     chip_id: u32,
     header: Header,
     rom: Vec<u8>,
+    key1: Option<Key1>,
     // Registers
     pub spicnt: SPICNT,
     romctrl: ROMCTRL,
     command: [u8; 8],
     cur_game_card_word: u32,
+    key1_active: bool,
     // Data Transfer
     rom_bytes_left: usize,
     game_card_words: VecDeque<u32>,
@@ -34,18 +40,37 @@ pub struct Cartridge {
 }
 
 impl Cartridge {
-    pub fn new(rom: Vec<u8>, save_file: PathBuf) -> Self {
+    const SECURE_AREA_ADDR: usize = 0x4000;
+    const SECURE_AREA_ENCRYPTED_LEN: usize = 0x800;
+
+    pub fn new(
+        mut rom: Vec<u8>,
+        bios7: &[u8],
+        save: Save,
+        save_type: SaveType,
+        direct_boot: bool,
+    ) -> Self {
         let header = Header::new(&rom);
-        let backup = Backup::detect_type(&header, save_file);
+        let backup = Backup::from_type(save_type, save);
+        // Only the BIOS talks KEY1, direct boot skips it entirely
+        let key1 = if direct_boot {
+            None
+        } else {
+            let idcode = u32::from_le_bytes(header.game_code);
+            Cartridge::encrypt_secure_area(&mut rom, bios7, idcode);
+            Some(Key1::new(bios7, idcode, 2, 8))
+        };
         Cartridge {
             chip_id: 0x000_01FC2u32, // TODO: Actually Calculate
             header,
             rom,
+            key1,
             // Registers
             spicnt: SPICNT::new(),
             romctrl: ROMCTRL::new(),
             command: [0; 8],
             cur_game_card_word: 0,
+            key1_active: false,
             // Data Transfer
             rom_bytes_left: 0,
             game_card_words: VecDeque::new(),
@@ -72,6 +97,99 @@ impl Cartridge {
         };
         self.romctrl.block_busy = true;
         self.romctrl.data_word_ready = false;
+        if self.key1_active {
+            self.run_key1_command();
+        } else {
+            self.run_unencrypted_command();
+        }
+
+        // TODO: Take into account WR bit
+        if self.rom_bytes_left == 0 {
+            // 8 command bytes transferred
+            scheduler.schedule(
+                Event::ROMBlockEnded(is_arm9),
+                HW::on_rom_block_ended,
+                self.transfer_byte_time() * 8,
+            );
+        } else {
+            // 8 command bytes + 4 bytes for word
+            scheduler.schedule(
+                Event::ROMWordTransfered,
+                HW::on_rom_word_transfered,
+                self.transfer_byte_time() * (8 + 4),
+            );
+        }
+    }
+
+    fn run_key1_command(&mut self) {
+        let key1 = self.key1.as_ref().unwrap();
+        let command = u64::from_be_bytes(self.command);
+        let [lo, hi] = key1.decrypt([command as u32, (command >> 32) as u32]);
+        let command = ((hi as u64) << 32 | lo as u64).to_be_bytes();
+        match command[0] >> 4 {
+            0x1 => {
+                // Chip ID is repeated
+                for _ in 0..self.rom_bytes_left / 4 {
+                    self.game_card_words.push_back(self.chip_id);
+                }
+            }
+            0x2 => {
+                // Secure Area Block, reads wrap within the 4K block
+                let block_addr = ((command[2] & 0xF0) as usize) << 8;
+                for i in 0..self.rom_bytes_left / 4 {
+                    let addr = block_addr + ((i * 4) & 0xFFF);
+                    self.game_card_words
+                        .push_back(match self.rom.get(addr..addr + 4) {
+                            Some(bytes) => u32::from_le_bytes(bytes.try_into().unwrap()),
+                            None => 0xFFFF_FFFF,
+                        });
+                }
+            }
+            0x4 | 0xA => {
+                // Activate KEY2 Encryption Mode and Enter Main Data Mode
+                // KEY2 isn't emulated, so main data mode commands are sent unencrypted
+                if command[0] >> 4 == 0xA {
+                    self.key1_active = false;
+                }
+                for _ in 0..self.rom_bytes_left / 4 {
+                    self.game_card_words.push_back(0xFFFF_FFFF);
+                }
+            }
+            _ => {
+                warn!("Unimplemented KEY1 Cartridge Command: {:X}", command[0]);
+                for _ in 0..self.rom_bytes_left / 4 {
+                    self.game_card_words.push_back(0);
+                }
+            }
+        }
+    }
+
+    // The BIOS expects the first 2K of the secure area to be encrypted like on a real cartridge
+    fn encrypt_secure_area(rom: &mut [u8], bios7: &[u8], idcode: u32) {
+        let start = Cartridge::SECURE_AREA_ADDR;
+        let end = start + Cartridge::SECURE_AREA_ENCRYPTED_LEN;
+        let read_word =
+            |rom: &[u8], addr: usize| u32::from_le_bytes(rom[addr..addr + 4].try_into().unwrap());
+        if rom.len() < end
+            || read_word(rom, start) != 0xE7FF_DEFF
+            || read_word(rom, start + 4) != 0xE7FF_DEFF
+        {
+            // Homebrew has no secure area and dumps may already be encrypted
+            return;
+        }
+        rom[start..start + 8].copy_from_slice(b"encryObj");
+        let mut encrypt_range = |key1: &Key1, range: Range<usize>| {
+            for addr in range.step_by(8) {
+                let data = key1.encrypt([read_word(rom, addr), read_word(rom, addr + 4)]);
+                rom[addr..addr + 4].copy_from_slice(&data[0].to_le_bytes());
+                rom[addr + 4..addr + 8].copy_from_slice(&data[1].to_le_bytes());
+            }
+        };
+        encrypt_range(&Key1::new(bios7, idcode, 3, 8), start..end);
+        encrypt_range(&Key1::new(bios7, idcode, 2, 8), start..start + 8);
+    }
+
+    fn run_unencrypted_command(&mut self) {
         let out_words = &mut self.game_card_words;
         let rom = &self.rom;
         let mut copy_rom = |range: Range<usize>| {
@@ -134,6 +252,17 @@ impl Cartridge {
                     self.game_card_words.push_back(0xFFFF_FFFF);
                 }
             }
+            0x3C => {
+                // Activate KEY1 Encryption Mode
+                if self.key1.is_some() {
+                    self.key1_active = true;
+                } else {
+                    warn!("KEY1 Encryption Mode is only supported when booting through the BIOS");
+                }
+                for _ in 0..self.rom_bytes_left / 4 {
+                    self.game_card_words.push_back(0xFFFF_FFFF);
+                }
+            }
             _ => {
                 warn!("Unimplemented Cartridge Command: {:X}", self.command[0]);
                 for _ in 0..self.rom_bytes_left / 4 {
@@ -141,23 +270,6 @@ impl Cartridge {
                 }
             }
         };
-
-        // TODO: Take into account WR bit
-        if self.rom_bytes_left == 0 {
-            // 8 command bytes transferred
-            scheduler.schedule(
-                Event::ROMBlockEnded(is_arm9),
-                HW::on_rom_block_ended,
-                self.transfer_byte_time() * 8,
-            );
-        } else {
-            // 8 command bytes + 4 bytes for word
-            scheduler.schedule(
-                Event::ROMWordTransfered,
-                HW::on_rom_word_transfered,
-                self.transfer_byte_time() * (8 + 4),
-            );
-        }
     }
 
     pub fn read_gamecard(
@@ -243,6 +355,32 @@ impl Cartridge {
         self.backup.save()
     }
 
+    pub fn backup_mem(&self) -> &[u8] {
+        self.backup.mem()
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write(&self.spicnt);
+        state.write(&self.romctrl);
+        state.write(&self.command);
+        state.write(&self.cur_game_card_word);
+        state.write(&self.key1_active);
+        state.write(&self.rom_bytes_left);
+        state.write(&self.game_card_words);
+        self.backup.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        self.spicnt = state.read()?;
+        self.romctrl = state.read()?;
+        self.command = state.read()?;
+        self.cur_game_card_word = state.read()?;
+        self.key1_active = state.read()?;
+        self.rom_bytes_left = state.read()?;
+        self.game_card_words = state.read()?;
+        self.backup.load_state(state)
+    }
+
     fn transfer_byte_time(&self) -> usize {
         if self.romctrl.transfer_clk_rate {
             8
@@ -253,13 +391,13 @@ impl Cartridge {
 }
 
 impl HW {
-    fn on_rom_word_transfered(&mut self, _event: Event) {
+    pub(super) fn on_rom_word_transfered(&mut self, _event: Event) {
         self.cartridge.cur_game_card_word = self.cartridge.game_card_words.pop_front().unwrap();
         self.cartridge.romctrl.data_word_ready = true;
         self.run_dmas(DMAOccasion::DSCartridge);
     }
 
-    fn on_rom_block_ended(&mut self, event: Event) {
+    pub(super) fn on_rom_block_ended(&mut self, event: Event) {
         let is_arm9 = match event {
             Event::ROMBlockEnded(is_arm9) => is_arm9,
             _ => unreachable!(),
@@ -272,6 +410,7 @@ impl HW {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct SPICNT {
     // Registers
     baudrate: u8,
@@ -333,6 +472,7 @@ impl SPICNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ROMCTRL {
     key1_gap1_len: u16,
     key2_encrypt_data: bool,
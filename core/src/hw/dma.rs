@@ -1,10 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+
 use super::{
     interrupt_controller::InterruptRequest,
     mem::{AccessType, IORegister, MemoryValue},
     scheduler::{Event, Scheduler},
     HW,
 };
+use crate::nds::CPU;
 
+#[derive(Serialize, Deserialize)]
 pub struct DMAController {
     channels: [DMAChannel; 4],
     pub by_type: [Vec<usize>; DMAOccasion::num()],
@@ -95,8 +101,71 @@ impl std::ops::IndexMut<usize> for DMAController {
     }
 }
 
+// A DMA as it started running, addresses are the latched ones
+#[derive(Clone, Copy, Debug)]
+pub struct DMATransfer {
+    pub cpu: CPU,
+    pub channel: usize,
+    pub occasion: DMAOccasion,
+    pub src_addr: u32,
+    pub dest_addr: u32,
+    pub dest_addr_ctrl: u8,
+    pub count: u32,
+    pub unit_size: usize,
+    pub frame: usize,
+    pub vcount: u16,
+    pub cycle: usize,
+}
+
+impl DMATransfer {
+    // Lowest and highest byte written, following the destination address control
+    pub fn dest_range(&self) -> (u32, u32) {
+        let unit_size = self.unit_size as u32;
+        let last_offset = self.count.saturating_sub(1).saturating_mul(unit_size);
+        let start = match self.dest_addr_ctrl {
+            1 => self.dest_addr.saturating_sub(last_offset),
+            _ => self.dest_addr,
+        };
+        let end = match self.dest_addr_ctrl {
+            0 | 3 => self.dest_addr.saturating_add(last_offset),
+            _ => self.dest_addr,
+        };
+        (start, end.saturating_add(unit_size - 1))
+    }
+}
+
 impl HW {
-    fn on_dma(&mut self, event: Event) {
+    const DMA_TRACE_LEN: usize = 0x1_0000;
+
+    pub fn dma_trace(&self) -> &VecDeque<DMATransfer> {
+        &self.dma_trace
+    }
+
+    pub fn set_trace_dmas(&mut self, trace: bool) {
+        self.trace_dmas = trace;
+        if !trace {
+            self.clear_dma_trace();
+        }
+    }
+
+    pub fn clear_dma_trace(&mut self) {
+        self.dma_trace.clear();
+        self.new_dmas.clear();
+    }
+
+    // Moves the transfers that ran since the last call into the trace. Only NDS counts frames,
+    // so it passes in the one they ran in.
+    pub fn tag_dma_trace(&mut self, frame: usize) {
+        for mut transfer in self.new_dmas.drain(..) {
+            transfer.frame = frame;
+            if self.dma_trace.len() == HW::DMA_TRACE_LEN {
+                self.dma_trace.pop_front();
+            }
+            self.dma_trace.push_back(transfer);
+        }
+    }
+
+    pub(super) fn on_dma(&mut self, event: Event) {
         let (is_nds9, num) = match event {
             Event::DMA(is_nds9, num) => (is_nds9, num),
             _ => unreachable!(),
@@ -168,6 +237,21 @@ impl HW {
             src_addr,
             if transfer_32 { 32 } else { 16 }
         );
+        if self.trace_dmas {
+            self.new_dmas.push(DMATransfer {
+                cpu: if IS_NDS9 { CPU::ARM9 } else { CPU::ARM7 },
+                channel: num,
+                occasion: self.dmas[i][num].cnt.start_timing,
+                src_addr,
+                dest_addr,
+                dest_addr_ctrl,
+                count,
+                unit_size: if transfer_32 { 4 } else { 2 },
+                frame: 0, // Set by tag_dma_trace
+                vcount: self.gpu.vcount,
+                cycle: self.scheduler.cycle,
+            });
+        }
 
         let (addr_change, addr_mask) = if transfer_32 { (4, 0x3) } else { (2, 0x1) };
         src_addr &= !addr_mask;
@@ -225,7 +309,7 @@ impl HW {
         }
     }
 
-    fn check_geometry_command_fifo_handler(&mut self, _event: Event) {
+    pub(super) fn check_geometry_command_fifo_handler(&mut self, _event: Event) {
         self.check_geometry_command_fifo();
     }
 
@@ -250,6 +334,7 @@ impl HW {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DMAChannel {
     pub num: usize,
     pub is_nds9: bool,
@@ -343,7 +428,7 @@ impl IORegister for DMAChannel {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DMAOccasion {
     Immediate = 0,
     VBlank = 1,
@@ -405,6 +490,7 @@ impl DMAOccasion {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DMACNT {
     count: u32,
     pub count_latch: u32,
@@ -493,6 +579,7 @@ impl IORegister for DMACNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Address {
     pub addr: u32,
     mask: u32,
@@ -1,4 +1,7 @@
-use super::{engine2d::BGMode, Engine2D, EngineType, GPU, VRAM};
+use super::{
+    engine2d::{BGMode, DISPCNTFlags, RotationScalingParameter},
+    Engine2D, EngineType, GPU, VRAM,
+};
 
 impl GPU {
     pub fn render_palettes<F: Fn(usize) -> u16>(
@@ -251,3 +254,212 @@ impl<E: EngineType> Engine2D<E> {
         }
     }
 }
+
+// Parts of the 2D rendering that can be turned off for debugging without touching any registers
+#[derive(Clone, Copy, PartialEq)]
+pub struct LayerToggles {
+    pub bgs: [bool; 4],
+    pub obj: bool,
+    pub engine3d: bool, // Only used by engine A
+    pub windows: bool,
+    pub blending: bool,
+    pub master_bright: bool,
+}
+
+impl Default for LayerToggles {
+    fn default() -> Self {
+        LayerToggles {
+            bgs: [true; 4],
+            obj: true,
+            engine3d: true,
+            windows: true,
+            blending: true,
+            master_bright: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OBJMode {
+    Normal,
+    SemiTransparent,
+    Window,
+    Bitmap,
+}
+
+// Decoded OAM entry, width and height are 0 for the prohibited shape
+#[derive(Clone, Copy)]
+pub struct OBJInfo {
+    pub x: i16,
+    pub y: u16,
+    pub width: usize,
+    pub height: usize,
+    pub shape: u8,
+    pub size: u8,
+    pub affine: bool,
+    pub double_size: bool,
+    pub disabled: bool,
+    pub affine_group: usize,
+    pub affine_params: [f64; 4], // PA, PB, PC, PD
+    pub flip: [bool; 2],
+    pub mode: OBJMode,
+    pub mosaic: bool,
+    pub bpp8: bool,
+    pub priority: u8,
+    pub palette: usize,
+    pub tile: usize,
+}
+
+impl OBJInfo {
+    // Size of the area the OBJ covers on screen
+    pub fn bounds(&self) -> (usize, usize) {
+        if self.double_size {
+            (self.width * 2, self.height * 2)
+        } else {
+            (self.width, self.height)
+        }
+    }
+}
+
+impl<E: EngineType> Engine2D<E> {
+    pub fn layer_toggles(&self) -> LayerToggles {
+        self.layers
+    }
+
+    pub fn set_layer_toggles(&mut self, layers: LayerToggles) {
+        self.layers = layers;
+    }
+
+    pub fn objs(&self) -> Vec<OBJInfo> {
+        let attr = |i: usize, num: usize| {
+            u16::from_le_bytes([self.oam[i * 8 + num * 2], self.oam[i * 8 + num * 2 + 1]])
+        };
+        (0..0x80)
+            .map(|i| {
+                let (attr0, attr1, attr2) = (attr(i, 0), attr(i, 1), attr(i, 2));
+                let shape = (attr0 >> 14 & 0x3) as u8;
+                let size = (attr1 >> 14 & 0x3) as u8;
+                let (width, height) = Engine2D::<E>::OBJ_SIZES[size as usize]
+                    .get(shape as usize)
+                    .map_or((0, 0), |&(width, height)| (width as usize, height as usize));
+                let affine = attr0 >> 8 & 0x1 != 0;
+                let double_size_or_disable = attr0 >> 9 & 0x1 != 0;
+                let affine_group = (attr1 >> 9 & 0x1F) as usize;
+                let mut affine_params = [0.0; 4];
+                for (j, param) in affine_params.iter_mut().enumerate() {
+                    *param =
+                        RotationScalingParameter::get_float_from_u16(attr(affine_group * 4 + j, 3));
+                }
+                let x = attr1 & 0x1FF;
+                OBJInfo {
+                    x: if x & 0x100 != 0 { 0xFE00 | x } else { x } as i16,
+                    y: attr0 & 0xFF,
+                    width,
+                    height,
+                    shape,
+                    size,
+                    affine,
+                    double_size: affine && double_size_or_disable,
+                    disabled: !affine && double_size_or_disable,
+                    affine_group,
+                    affine_params,
+                    flip: [
+                        !affine && attr1 >> 12 & 0x1 != 0,
+                        !affine && attr1 >> 13 & 0x1 != 0,
+                    ],
+                    mode: match attr0 >> 10 & 0x3 {
+                        0 => OBJMode::Normal,
+                        1 => OBJMode::SemiTransparent,
+                        2 => OBJMode::Window,
+                        3 => OBJMode::Bitmap,
+                        _ => unreachable!(),
+                    },
+                    mosaic: attr0 >> 12 & 0x1 != 0,
+                    bpp8: attr0 >> 13 & 0x1 != 0,
+                    priority: (attr2 >> 10 & 0x3) as u8,
+                    palette: (attr2 >> 12 & 0xF) as usize,
+                    tile: (attr2 & 0x3FF) as usize,
+                }
+            })
+            .collect()
+    }
+
+    // Renders the OBJ as stored in VRAM, without flipping or affine transformation
+    pub fn render_obj(&self, vram: &VRAM, obj: &OBJInfo) -> (Vec<u16>, usize, usize) {
+        let (width, height) = (obj.width, obj.height);
+        let mut pixels = vec![0; width * height];
+        if obj.mode == OBJMode::Bitmap {
+            let (tile_start_addr, vram_width) =
+                if self.dispcnt.contains(DISPCNTFlags::BITMAP_OBJ_1D) {
+                    // Reserved, displays nothing
+                    if self.dispcnt.contains(DISPCNTFlags::BITMAP_OBJ_SQUARE) {
+                        return (pixels, width, height);
+                    }
+                    let boundary = if self.dispcnt.contains(DISPCNTFlags::BITMAP_OBJ_1D_BOUND) {
+                        256
+                    } else {
+                        128
+                    };
+                    (obj.tile * boundary, width)
+                } else {
+                    let (mask_x, vram_width) =
+                        if self.dispcnt.contains(DISPCNTFlags::BITMAP_OBJ_SQUARE) {
+                            (0x1F, 256)
+                        } else {
+                            (0x0F, 128)
+                        };
+                    (
+                        (obj.tile & mask_x) * 0x10 + (obj.tile & !mask_x) * 0x80,
+                        vram_width,
+                    )
+                };
+            for y in 0..height {
+                for x in 0..width {
+                    let addr = tile_start_addr + 2 * (y * vram_width + x);
+                    let color = vram.get_obj::<E, u16>(addr);
+                    if color & 0x8000 != 0 {
+                        pixels[y * width + x] = color;
+                    }
+                }
+            }
+            return (pixels, width, height);
+        }
+
+        let bit_depth = if obj.bpp8 { 8 } else { 4 };
+        let extended = obj.bpp8 && self.dispcnt.contains(DISPCNTFlags::OBJ_EXTENDED_PALETTES);
+        for y in 0..height {
+            for x in 0..width {
+                let (boundary, tile_offset) = if self.dispcnt.contains(DISPCNTFlags::TILE_OBJ_1D) {
+                    (
+                        32 << self.dispcnt.tile_obj_1d_bound,
+                        (y / 8 * width + x) / 8,
+                    )
+                } else {
+                    (32, y / 8 * 0x80 / bit_depth + x / 8)
+                };
+                let addr = boundary * obj.tile + tile_offset * bit_depth * 8;
+                let (palette_num, color_num) = Engine2D::<E>::get_color_from_tile(
+                    vram,
+                    VRAM::get_obj::<E, u8>,
+                    addr,
+                    false,
+                    false,
+                    bit_depth,
+                    x % 8,
+                    y % 8,
+                    obj.palette,
+                );
+                if color_num == 0 {
+                    continue;
+                }
+                pixels[y * width + x] = 0x8000
+                    | if extended {
+                        vram.get_obj_ext_pal::<E>(obj.palette * 256 + color_num)
+                    } else {
+                        self.obj_palettes()[palette_num * 16 + color_num]
+                    };
+            }
+        }
+        (pixels, width, height)
+    }
+}
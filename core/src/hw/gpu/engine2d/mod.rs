@@ -1,11 +1,15 @@
 mod registers;
 
-pub use registers::{BGMode, DisplayMode};
+use serde::{Deserialize, Serialize};
 
-use super::{Engine3D, EngineType, GPU, VRAM};
+pub use registers::{BGMode, DISPCNTFlags, DisplayMode, RotationScalingParameter};
+
+use super::{debug::LayerToggles, Engine3D, EngineType, GPU, VRAM};
 use crate::hw::{mem::IORegister, Scheduler};
 use registers::*;
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Engine2D<E: EngineType> {
     // Registers
     pub(super) dispcnt: DISPCNT<E>,
@@ -42,9 +46,16 @@ pub struct Engine2D<E: EngineType> {
 
     // Important Rendering Variables
     pixels: Vec<u16>,
+    // Line buffers are redrawn every scanline, so they aren't saved
+    #[serde(skip, default = "empty_bg_lines")]
     bg_lines: [[u16; GPU::WIDTH]; 4],
+    #[serde(skip, default = "empty_objs_line")]
     objs_line: [OBJPixel; GPU::WIDTH],
+    #[serde(skip, default = "empty_windows_lines")]
     windows_lines: [[bool; GPU::WIDTH]; 3],
+    // Debugging
+    #[serde(skip)]
+    pub(super) layers: LayerToggles,
 }
 
 impl<E: EngineType> Engine2D<E> {
@@ -85,13 +96,15 @@ impl<E: EngineType> Engine2D<E> {
 
             // Important Rendering Variables
             pixels: vec![0; GPU::WIDTH * GPU::HEIGHT],
-            bg_lines: [[0; GPU::WIDTH]; 4],
-            objs_line: [OBJPixel::none(); GPU::WIDTH],
-            windows_lines: [[false; GPU::WIDTH]; 3],
+            bg_lines: empty_bg_lines(),
+            objs_line: empty_objs_line(),
+            windows_lines: empty_windows_lines(),
+            // Debugging
+            layers: LayerToggles::default(),
         }
     }
 
-    const OBJ_SIZES: [[(i16, u16); 3]; 4] = [
+    pub(super) const OBJ_SIZES: [[(i16, u16); 3]; 4] = [
         [(8, 8), (16, 8), (8, 16)],
         [(16, 16), (32, 8), (8, 32)],
         [(32, 32), (32, 16), (16, 32)],
@@ -224,15 +237,20 @@ impl<E: EngineType> Engine2D<E> {
             }
         }
         bgs.sort_by_key(|a| a.1);
+        let is_3d = E::is_a() && self.dispcnt.contains(DISPCNTFlags::IS_3D);
         let master_enabled = [
-            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG0),
-            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG1),
-            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG2),
-            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG3),
-            self.dispcnt.contains(DISPCNTFlags::DISPLAY_OBJ),
+            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG0)
+                && self.layers.bgs[0]
+                && (self.layers.engine3d || !is_3d),
+            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG1) && self.layers.bgs[1],
+            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG2) && self.layers.bgs[2],
+            self.dispcnt.contains(DISPCNTFlags::DISPLAY_BG3) && self.layers.bgs[3],
+            self.dispcnt.contains(DISPCNTFlags::DISPLAY_OBJ) && self.layers.obj,
         ];
         for dot_x in 0..GPU::WIDTH {
-            let window_control = if self.windows_lines[0][dot_x] {
+            let window_control = if !self.layers.windows {
+                WindowControl::all()
+            } else if self.windows_lines[0][dot_x] {
                 self.win_0_cnt
             } else if self.windows_lines[1][dot_x] {
                 self.win_1_cnt
@@ -293,7 +311,8 @@ impl<E: EngineType> Engine2D<E> {
             let target1_enabled =
                 self.bldcnt.target_pixel1.enabled[layers[0] as usize] || trans_obj;
             let target2_enabled = self.bldcnt.target_pixel2.enabled[layers[1] as usize];
-            let final_color = if window_control.color_special_enable && target1_enabled {
+            let color_special_enable = self.layers.blending && window_control.color_special_enable;
+            let final_color = if color_special_enable && target1_enabled {
                 let effect = if trans_obj && target2_enabled {
                     ColorSFX::AlphaBlend
                 } else {
@@ -1096,6 +1115,18 @@ impl OBJPixel {
     }
 }
 
+fn empty_bg_lines() -> [[u16; GPU::WIDTH]; 4] {
+    [[0; GPU::WIDTH]; 4]
+}
+
+fn empty_objs_line() -> [OBJPixel; GPU::WIDTH] {
+    [OBJPixel::none(); GPU::WIDTH]
+}
+
+fn empty_windows_lines() -> [[bool; GPU::WIDTH]; 3] {
+    [[false; GPU::WIDTH]; 3]
+}
+
 impl<E: EngineType> Engine2D<E> {
     pub fn read_register(&self, addr: u32) -> u8 {
         assert_eq!((addr >> 12) & !0x1, 0x04000);
@@ -1320,7 +1351,11 @@ impl<E: EngineType> Engine2D<E> {
     }
 
     fn set_pixel(&mut self, vcount: u16, dot_x: usize, color: u16) {
-        self.pixels[vcount as usize * GPU::WIDTH + dot_x] = self.master_bright.apply(color);
+        self.pixels[vcount as usize * GPU::WIDTH + dot_x] = if self.layers.master_bright {
+            self.master_bright.apply(color)
+        } else {
+            color
+        };
     }
 
     pub fn write_palette_ram(&mut self, addr: usize, value: u16) {
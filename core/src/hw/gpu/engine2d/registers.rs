@@ -1,11 +1,12 @@
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
 use super::EngineType;
 use crate::hw::{mem::IORegister, scheduler::Scheduler};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum BGMode {
     Mode0 = 0,
     Mode1 = 1,
@@ -32,7 +33,7 @@ impl BGMode {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum DisplayMode {
     Mode0 = 0,
     Mode1 = 1,
@@ -54,6 +55,7 @@ impl DisplayMode {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct DISPCNTFlags: u32 {
         const IS_3D = 1 << 3; // TODO: Only Engine A
         const TILE_OBJ_1D = 1 << 4;
@@ -75,6 +77,8 @@ bitflags! {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct DISPCNT<E: EngineType> {
     pub flags: DISPCNTFlags,
     pub bg_mode: BGMode,
@@ -181,7 +185,7 @@ impl<E: EngineType> IORegister for DISPCNT<E> {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct BGCNT {
     pub priority: u8,
     pub tile_block: u8,
@@ -238,7 +242,7 @@ impl IORegister for BGCNT {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct OFS {
     pub offset: u16,
 }
@@ -267,7 +271,7 @@ impl IORegister for OFS {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct RotationScalingParameter {
     value: i16,
 }
@@ -299,7 +303,7 @@ impl IORegister for RotationScalingParameter {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct ReferencePointCoord {
     value: i32,
 }
@@ -346,7 +350,7 @@ impl std::ops::AddAssign<RotationScalingParameter> for ReferencePointCoord {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct WindowDimensions {
     pub coord2: u8,
     pub coord1: u8,
@@ -379,7 +383,7 @@ impl IORegister for WindowDimensions {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct WindowControl {
     pub bg0_enable: bool,
     pub bg1_enable: bool,
@@ -443,6 +447,7 @@ impl IORegister for WindowControl {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct MosaicSize {
     pub h_size: u16,
     pub v_size: u16,
@@ -466,6 +471,7 @@ impl MosaicSize {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct MOSAIC {
     pub bg_size: MosaicSize,
     pub obj_size: MosaicSize,
@@ -498,6 +504,7 @@ impl IORegister for MOSAIC {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct BLDCNTTargetPixelSelection {
     pub enabled: [bool; 6],
 }
@@ -528,7 +535,7 @@ impl BLDCNTTargetPixelSelection {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum ColorSFX {
     None = 0,
     AlphaBlend = 1,
@@ -549,6 +556,7 @@ impl ColorSFX {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct BLDCNT {
     pub target_pixel1: BLDCNTTargetPixelSelection,
     pub effect: ColorSFX,
@@ -586,6 +594,7 @@ impl IORegister for BLDCNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct BLDALPHA {
     raw_eva: u8,
     raw_evb: u8,
@@ -628,6 +637,7 @@ impl IORegister for BLDALPHA {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct BLDY {
     pub evy: u8,
 }
@@ -652,7 +662,7 @@ impl IORegister for BLDY {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum MasterBrightMode {
     Disable = 0,
     Up = 1,
@@ -670,6 +680,7 @@ impl MasterBrightMode {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct MasterBright {
     factor_read: u8, // Used for memory reading which is different than factor
     factor: u8,
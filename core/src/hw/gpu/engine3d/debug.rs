@@ -0,0 +1,226 @@
+use super::{
+    super::VRAM,
+    geometry::{Polygon, Vertex},
+    math::Matrix,
+    registers::{PolygonMode, TextureFormat, TextureParams},
+    Engine3D,
+};
+
+// Copy of what the rendering engine was given for a frame, kept for debugging tools
+#[derive(Clone, Default)]
+pub struct SceneSnapshot {
+    pub polygons: Vec<PolygonSnapshot>,
+    pub matrices: MatrixSnapshot,
+    // Texture and texture palette slots when the frame rendered, see VRAM::textures_snapshot
+    texture_data: Vec<u8>,
+    palette_data: Vec<u8>,
+}
+
+impl SceneSnapshot {
+    // Every texture the polygons use, in the order they were first used
+    pub fn textures(&self) -> Vec<TextureInfo> {
+        let mut textures: Vec<TextureInfo> = Vec::new();
+        for polygon in self.polygons.iter() {
+            let texture = polygon.texture;
+            if texture.format != TextureFormat::NoTexture && !textures.contains(&texture) {
+                textures.push(texture);
+            }
+        }
+        textures
+    }
+
+    // Returns RGBA8 pixels along with the width and height
+    pub fn decode_texture(&self, texture: &TextureInfo) -> (Vec<[u8; 4]>, usize, usize) {
+        // The default snapshot has no polygons, so no textures to decode
+        if self.texture_data.is_empty() {
+            return (Vec::new(), 0, 0);
+        }
+        let vram = VRAM::from_textures(&self.texture_data, &self.palette_data);
+        let (width, height) = (texture.size[0], texture.size[1]);
+        let mut tex_params = TextureParams::new();
+        tex_params.vram_offset = texture.vram_offset;
+        tex_params.size_s = width;
+        tex_params.size_t = height;
+        tex_params.format = texture.format;
+        tex_params.color0_transparent = texture.color0_transparent;
+        let mut pixels = Vec::with_capacity(width * height);
+        for t in 0..height {
+            for s in 0..width {
+                let color = Engine3D::decode_texel(&vram, &tex_params, texture.palette_base, s, t);
+                pixels.push(color.map_or([0; 4], |color| color.as_rgba8()));
+            }
+        }
+        (pixels, width, height)
+    }
+}
+
+#[derive(Clone)]
+pub struct PolygonSnapshot {
+    pub mode: PolygonMode,
+    pub alpha: u8,
+    pub id: u8,
+    pub is_front: bool,
+    pub render_front: bool,
+    pub render_back: bool,
+    pub fog: bool,
+    pub lights_enabled: [bool; 4],
+    pub depth_test_eq: bool,
+    pub texture: TextureInfo,
+    pub tex_repeat: [bool; 2],
+    pub tex_flip: [bool; 2],
+    pub vertices: Vec<VertexSnapshot>,
+}
+
+// What's needed to decode a texture, offsets are into the texture and texture palette slots
+#[derive(Clone, Copy, PartialEq)]
+pub struct TextureInfo {
+    pub format: TextureFormat,
+    pub vram_offset: usize,
+    pub size: [usize; 2],
+    pub palette_base: usize,
+    pub color0_transparent: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct VertexSnapshot {
+    pub screen_coords: [u32; 2],
+    pub depth: u32, // 24 bit
+    pub w: i16,
+    pub color: [u8; 3],
+    pub tex_coord: [i16; 2], // 1 + 11 + 4 fixed point
+}
+
+// Matrices are row major and converted from 20.12 fixed point
+#[derive(Clone, Default)]
+pub struct MatrixSnapshot {
+    pub projection: [f32; 16],
+    pub position: [f32; 16],
+    pub vector: [f32; 16],
+    pub texture: [f32; 16],
+    pub projection_level: u8,
+    pub position_level: u8,
+    pub texture_level: u8,
+}
+
+impl Engine3D {
+    pub(super) fn take_snapshot(&mut self, vram: &VRAM) {
+        let vertices = &self.vertices;
+        let polygons = self
+            .polygons
+            .iter()
+            .map(|polygon| PolygonSnapshot::new(polygon, vertices))
+            .collect();
+        let (texture_data, palette_data) = vram.textures_snapshot();
+        self.snapshot = Some(SceneSnapshot {
+            polygons,
+            matrices: MatrixSnapshot {
+                projection: Self::matrix_snapshot(&self.cur_proj),
+                position: Self::matrix_snapshot(&self.cur_pos),
+                vector: Self::matrix_snapshot(&self.cur_vec),
+                texture: Self::matrix_snapshot(&self.cur_tex),
+                projection_level: self.proj_stack_sp,
+                position_level: self.pos_vec_stack_sp,
+                texture_level: self.tex_stack_sp,
+            },
+            texture_data,
+            palette_data,
+        });
+    }
+
+    fn matrix_snapshot(matrix: &Matrix) -> [f32; 16] {
+        let mut elems = [0.0; 16];
+        for (i, elem) in elems.iter_mut().enumerate() {
+            *elem = matrix[i].raw() as f32 / 4096.0;
+        }
+        elems
+    }
+
+    pub fn set_capture_scene(&mut self, capture: bool) {
+        self.capture_scene = capture;
+        if !capture {
+            self.snapshot = None;
+        }
+    }
+
+    pub fn scene_snapshot(&self) -> Option<&SceneSnapshot> {
+        self.snapshot.as_ref()
+    }
+}
+
+impl PolygonSnapshot {
+    fn new(polygon: &Polygon, vertices: &[Vertex]) -> Self {
+        let tex_params = &polygon.tex_params;
+        PolygonSnapshot {
+            mode: polygon.attrs.mode,
+            alpha: polygon.attrs.alpha,
+            id: polygon.attrs.polygon_id,
+            is_front: polygon.is_front,
+            render_front: polygon.attrs.render_front,
+            render_back: polygon.attrs.render_back,
+            fog: polygon.attrs.fog_enable,
+            lights_enabled: polygon.attrs.lights_enabled,
+            depth_test_eq: polygon.attrs.depth_test_eq,
+            texture: TextureInfo {
+                format: tex_params.format,
+                vram_offset: tex_params.vram_offset,
+                size: [tex_params.size_s, tex_params.size_t],
+                palette_base: polygon.palette_base,
+                color0_transparent: tex_params.color0_transparent,
+            },
+            tex_repeat: [tex_params.repeat_s, tex_params.repeat_t],
+            tex_flip: [tex_params.flip_s, tex_params.flip_t],
+            vertices: vertices[polygon.start_vert..polygon.end_vert]
+                .iter()
+                .map(|vert| VertexSnapshot {
+                    screen_coords: vert.screen_coords,
+                    depth: vert.z_depth,
+                    w: vert.normalized_w,
+                    color: [vert.color.r8(), vert.color.g8(), vert.color.b8()],
+                    tex_coord: vert.tex_coord,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Engine3D, SceneSnapshot, TextureFormat, TextureInfo, VRAM};
+
+    #[test]
+    fn decode_texture() {
+        let cases: &[(u16, [u8; 4])] = &[
+            (0x0000, [0, 0, 0, 0]),
+            (0x801F, [255, 0, 0, 255]),
+            (0x03E0, [0, 255, 0, 0]),
+            (0xFC00, [0, 0, 255, 255]),
+        ];
+        let mut textures = vec![0; VRAM::TEXTURES_LEN];
+        for (i, (color, _)) in cases.iter().enumerate() {
+            textures[2 * i..2 * i + 2].copy_from_slice(&color.to_le_bytes());
+        }
+        let texture = TextureInfo {
+            format: TextureFormat::DirectColor,
+            vram_offset: 0,
+            size: [8, 8],
+            palette_base: 0,
+            color0_transparent: false,
+        };
+
+        let mut engine = Engine3D::new();
+        {
+            let vram = VRAM::from_textures(&textures, &vec![0; VRAM::TEXTURES_PAL_LEN]);
+            engine.take_snapshot(&vram);
+        }
+        // VRAM is gone, so the texels have to come from the snapshot's own copy
+        let snapshot = engine.scene_snapshot().unwrap();
+        let (pixels, width, height) = snapshot.decode_texture(&texture);
+        assert_eq!((width, height), (8, 8));
+        for (i, (color, expected)) in cases.iter().enumerate() {
+            assert_eq!(pixels[i], *expected, "color {:04X}", color);
+        }
+
+        let (pixels, _, _) = SceneSnapshot::default().decode_texture(&texture);
+        assert!(pixels.is_empty());
+    }
+}
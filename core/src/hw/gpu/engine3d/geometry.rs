@@ -1,7 +1,10 @@
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+
 use super::{
     math::{FixedPoint, Matrix, Vec4},
     registers::*,
-    Engine3D,
+    Engine3D, GeometryWrite,
 };
 
 impl Engine3D {
@@ -259,6 +262,7 @@ impl Engine3D {
     }
 
     pub fn write_geometry_fifo(&mut self, value: u32) {
+        self.record_write(GeometryWrite::FIFO(value));
         if self.packed_commands == 0 {
             if value == 0 {
                 return;
@@ -299,6 +303,7 @@ impl Engine3D {
     }
 
     pub fn write_geometry_command(&mut self, addr: u32, value: u32) {
+        self.record_write(GeometryWrite::Command(addr, value));
         let command = GeometryCommand::from_addr(addr & 0xFFF);
         if command != GeometryCommand::Unimplemented {
             self.push_geometry_command(command, value);
@@ -757,7 +762,7 @@ impl Engine3D {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GeometryCommand {
     NOP = 0x00,
     MtxMode = 0x10,
@@ -933,7 +938,7 @@ impl GeometryCommand {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GeometryCommandEntry {
     command: GeometryCommand,
     param: u32,
@@ -945,6 +950,7 @@ impl GeometryCommandEntry {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum MatrixMode {
     Proj = 0,
     Pos = 1,
@@ -964,7 +970,7 @@ impl From<u8> for MatrixMode {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Light {
     direction: [FixedPoint; 3],
     color: [i32; 3],
@@ -979,11 +985,13 @@ impl Light {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Material {
     diffuse: [i32; 3],
     ambient: [i32; 3],
     specular: [i32; 3],
     emission: [i32; 3],
+    #[serde(with = "BigArray")]
     shininess: [i8; 128],
     use_shininess_table: bool,
 }
@@ -1029,7 +1037,7 @@ impl Material {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -1114,7 +1122,7 @@ impl Color {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Vertex {
     pub clip_coords: Vec4,
     pub screen_coords: [u32; 2],
@@ -1142,6 +1150,7 @@ impl Vertex {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Polygon {
     pub start_vert: usize,
     pub end_vert: usize,
@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Index, Mul, Neg, Sub};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Matrix {
     elems: [FixedPoint; 16],
 }
@@ -260,7 +261,7 @@ impl Mul<[FixedPoint; 3]> for Matrix {
 }
 
 // 12 bit fraction
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct FixedPoint(i32);
 
 impl Mul for FixedPoint {
@@ -360,7 +361,7 @@ impl std::fmt::Debug for FixedPoint {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Vec4 {
     elems: [FixedPoint; 4],
 }
@@ -1,18 +1,26 @@
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 use super::{InterruptRequest, Scheduler, GPU};
 use crate::hw::mem::IORegister;
 
+mod debug;
 mod geometry;
 mod math;
+mod recording;
 mod registers;
 mod rendering;
 
+pub use debug::{MatrixSnapshot, PolygonSnapshot, SceneSnapshot, TextureInfo, VertexSnapshot};
 use geometry::*;
 use math::{FixedPoint, Matrix};
+use recording::GeometryWrite;
+pub use recording::{GeometryDump, LoadDumpError};
 use registers::*;
+pub use registers::{PolygonMode, TextureFormat};
 use rendering::FrameBufferPixel;
 
+#[derive(Serialize, Deserialize)]
 pub struct Engine3D {
     pub bus_stalled: bool,
     // Registers
@@ -69,6 +77,17 @@ pub struct Engine3D {
     tex_coord: [i16; 2],     // 1 + 11 + 4 fixed point
     // Toon
     toon_table: [Color; 0x20],
+    // Debugging
+    #[serde(skip)]
+    capture_scene: bool,
+    #[serde(skip)]
+    snapshot: Option<SceneSnapshot>,
+    #[serde(skip)]
+    record_frame: bool,
+    #[serde(skip)]
+    recording: Option<GeometryDump>,
+    #[serde(skip)]
+    recorded: Option<GeometryDump>,
 }
 
 impl Engine3D {
@@ -131,6 +150,12 @@ impl Engine3D {
             tex_coord: [0; 2],     // 1 + 11 + 4 fixed point
             // Toon
             toon_table: [Color::new5(0, 0, 0); 0x20],
+            // Debugging
+            capture_scene: false,
+            snapshot: None,
+            record_frame: false,
+            recording: None,
+            recorded: None,
         }
     }
 
@@ -162,6 +187,7 @@ impl Engine3D {
 
     pub fn write_register(&mut self, scheduler: &mut Scheduler, addr: u32, value: u8) {
         assert_eq!(addr >> 12, 0x04000);
+        self.record_write(GeometryWrite::Register(addr, value));
         match addr & 0xFFF {
             0x350..=0x353 => self
                 .clear_color
@@ -176,4 +202,9 @@ impl Engine3D {
             _ => warn!("Ignoring Engine3D Write 0x{:08X} = {:02X}", addr, value),
         }
     }
+
+    pub fn write_disp3dcnt(&mut self, scheduler: &mut Scheduler, byte: usize, value: u8) {
+        self.record_write(GeometryWrite::DISP3DCNT(byte, value));
+        self.disp3dcnt.write(scheduler, byte, value)
+    }
 }
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use super::{super::VRAM, Engine3D, Scheduler, GPU};
+
+const MAGIC: &[u8; 4] = b"NDSG";
+// Bump whenever the layout below or any Engine3D state changes
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 8;
+// Far more than any frame needs, keeps a corrupted length from allocating without bound
+const MAX_LEN: u64 = 0x400_0000;
+
+// Same encoding as bincode::serialize, with a size limit since dumps are loaded from files
+fn options() -> impl Options {
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_LEN)
+}
+
+// Every write the ARM9 made to the 3D engine, in order
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(super) enum GeometryWrite {
+    FIFO(u32),
+    Command(u32, u32),
+    Register(u32, u8),
+    DISP3DCNT(usize, u8),
+}
+
+// One 3D frame as the engine received it, enough to render it again without the rest of the system.
+// The engine state is from right after the previous frame rendered, VRAM from when this one did.
+#[derive(Serialize, Deserialize)]
+pub struct GeometryDump {
+    engine: Vec<u8>,
+    writes: Vec<GeometryWrite>,
+    textures: Vec<u8>,
+    palettes: Vec<u8>,
+}
+
+impl GeometryDump {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        options().serialize_into(&mut data, self).unwrap();
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, LoadDumpError> {
+        if data.len() < HEADER_LEN || &data[0..4] != MAGIC {
+            return Err(LoadDumpError::InvalidHeader);
+        }
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        if version != VERSION {
+            return Err(LoadDumpError::UnsupportedVersion(version));
+        }
+        let dump: GeometryDump = options()
+            .deserialize(&data[HEADER_LEN..])
+            .map_err(|e| LoadDumpError::Corrupted(e.to_string()))?;
+        // The engine asserts these can't happen, so reject them before replay gets to it
+        for write in dump.writes.iter() {
+            match *write {
+                GeometryWrite::Register(addr, _) if addr >> 12 != 0x04000 => {
+                    return Err(LoadDumpError::Corrupted(format!(
+                        "register write to 0x{:08X}",
+                        addr
+                    )))
+                }
+                GeometryWrite::DISP3DCNT(byte, _) if byte >= 4 => {
+                    return Err(LoadDumpError::Corrupted(format!(
+                        "DISP3DCNT write to byte {}",
+                        byte
+                    )))
+                }
+                _ => (),
+            }
+        }
+        Ok(dump)
+    }
+
+    pub fn num_writes(&self) -> usize {
+        self.writes.len()
+    }
+
+    // Feeds every write to a fresh engine and returns the frame it renders
+    pub fn replay(&self) -> Result<Vec<u16>, LoadDumpError> {
+        let mut engine: Engine3D = options()
+            .deserialize(&self.engine)
+            .map_err(|e| LoadDumpError::Corrupted(e.to_string()))?;
+        if self.textures.len() != VRAM::TEXTURES_LEN
+            || self.palettes.len() != VRAM::TEXTURES_PAL_LEN
+        {
+            return Err(LoadDumpError::Corrupted("wrong VRAM length".to_string()));
+        }
+        let vram = VRAM::from_textures(&self.textures, &self.palettes);
+        // None of the registers schedule anything
+        let mut scheduler = Scheduler::new();
+
+        // Commands that were stalled behind the previous frame run right after it renders
+        engine.exec_commands();
+        for write in self.writes.iter() {
+            match *write {
+                GeometryWrite::FIFO(value) => engine.write_geometry_fifo(value),
+                GeometryWrite::Command(addr, value) => engine.write_geometry_command(addr, value),
+                GeometryWrite::Register(addr, value) => {
+                    engine.write_register(&mut scheduler, addr, value)
+                }
+                GeometryWrite::DISP3DCNT(byte, value) => {
+                    engine.write_disp3dcnt(&mut scheduler, byte, value)
+                }
+            }
+        }
+        engine.render(&vram);
+        Ok((0..GPU::WIDTH * GPU::HEIGHT)
+            .map(|i| engine.pixel_color(i))
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadDumpError {
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    Corrupted(String),
+}
+
+impl fmt::Display for LoadDumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadDumpError::InvalidHeader => write!(f, "Not a geometry dump"),
+            LoadDumpError::UnsupportedVersion(version) => write!(
+                f,
+                "Geometry dump version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            LoadDumpError::Corrupted(reason) => write!(f, "Corrupted geometry dump: {}", reason),
+        }
+    }
+}
+
+impl Engine3D {
+    // Records the next complete 3D frame, see take_geometry_dump
+    pub fn record_geometry_frame(&mut self) {
+        self.record_frame = true;
+        self.recording = None;
+        self.recorded = None;
+    }
+
+    pub fn is_recording_geometry(&self) -> bool {
+        self.record_frame || self.recording.is_some()
+    }
+
+    pub fn take_geometry_dump(&mut self) -> Option<GeometryDump> {
+        self.recorded.take()
+    }
+
+    // Called once the previous frame has rendered
+    pub(super) fn start_recording(&mut self) {
+        self.record_frame = false;
+        self.recording = Some(GeometryDump {
+            engine: options().serialize(self).unwrap(),
+            writes: Vec::new(),
+            textures: Vec::new(),
+            palettes: Vec::new(),
+        });
+    }
+
+    pub(super) fn finish_recording(&mut self, vram: &VRAM) {
+        if let Some(mut dump) = self.recording.take() {
+            let (textures, palettes) = vram.textures_snapshot();
+            dump.textures = textures;
+            dump.palettes = palettes;
+            self.recorded = Some(dump);
+        }
+    }
+
+    pub(super) fn record_write(&mut self, write: GeometryWrite) {
+        if let Some(dump) = &mut self.recording {
+            dump.writes.push(write);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{options, Engine3D, GeometryDump, GeometryWrite, LoadDumpError, Options, VRAM};
+
+    #[test]
+    fn from_bytes_validates_writes() {
+        let cases: &[(GeometryWrite, bool)] = &[
+            (GeometryWrite::FIFO(0), true),
+            (GeometryWrite::Command(0x0400_0440, 0), true),
+            (GeometryWrite::Register(0x0400_0350, 0x1F), true),
+            (GeometryWrite::Register(0x0400_0FFF, 0), true),
+            (GeometryWrite::Register(0x0400_1000, 0), false),
+            (GeometryWrite::Register(0x0500_0350, 0), false),
+            (GeometryWrite::DISP3DCNT(3, 0), true),
+            (GeometryWrite::DISP3DCNT(4, 0), false),
+        ];
+        let engine = options().serialize(&Engine3D::new()).unwrap();
+        for &(write, valid) in cases.iter() {
+            let dump = GeometryDump {
+                engine: engine.clone(),
+                writes: vec![write],
+                textures: vec![0; VRAM::TEXTURES_LEN],
+                palettes: vec![0; VRAM::TEXTURES_PAL_LEN],
+            };
+            match GeometryDump::from_bytes(&dump.to_bytes()) {
+                Ok(dump) => {
+                    assert!(valid, "{:?} loaded", write);
+                    assert!(dump.replay().is_ok());
+                }
+                Err(LoadDumpError::Corrupted(_)) => assert!(!valid, "{:?} rejected", write),
+                Err(err) => panic!("{:?}: {}", write, err),
+            }
+        }
+    }
+}
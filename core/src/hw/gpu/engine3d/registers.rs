@@ -1,5 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::{math::Vec4, Color, Engine3D, IORegister, Scheduler, GPU};
 
+#[derive(Serialize, Deserialize)]
 pub struct DISP3DCNT {
     pub texture_mapping: bool,
     pub highlight_shading: bool,
@@ -82,6 +85,7 @@ impl IORegister for DISP3DCNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct GXSTAT {
     pub test_busy: bool, // Box, Pos, Vector Test
     pub box_test_inside: bool,
@@ -91,7 +95,7 @@ pub struct GXSTAT {
     pub command_fifo_irq: CommandFifoIRQ,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum CommandFifoIRQ {
     Never = 0,
     LessHalf = 1,
@@ -184,6 +188,7 @@ impl Engine3D {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ClearColor {
     pub r: u8,
     pub g: u8,
@@ -229,6 +234,7 @@ impl IORegister for ClearColor {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ClearDepth {
     depth: u16,
 }
@@ -257,7 +263,7 @@ impl IORegister for ClearDepth {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TextureParams {
     pub vram_offset: usize,
     pub repeat_s: bool,
@@ -307,7 +313,7 @@ impl TextureParams {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TextureFormat {
     NoTexture = 0,
     A3I5 = 1,
@@ -335,7 +341,7 @@ impl From<u32> for TextureFormat {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TexCoordTransformationMode {
     None = 0,
     TexCoord = 1,
@@ -355,7 +361,7 @@ impl From<u32> for TexCoordTransformationMode {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct PolygonAttributes {
     pub lights_enabled: [bool; 4],
     pub mode: PolygonMode,
@@ -405,7 +411,7 @@ impl PolygonAttributes {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PolygonMode {
     Modulation = 0,
     Decal = 1,
@@ -425,7 +431,7 @@ impl From<u32> for PolygonMode {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct FrameParams {
     pub manual_sort_translucent: bool,
     pub w_buffer: bool,
@@ -445,6 +451,7 @@ impl FrameParams {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Viewport {
     x1: i32,
     y1: i32,
@@ -502,6 +509,7 @@ impl Viewport {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum VertexPrimitive {
     Triangles = 0,
     Quad = 1,
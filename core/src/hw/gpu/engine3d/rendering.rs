@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::{
     super::VRAM,
     geometry::{Polygon, Vertex},
-    registers::{PolygonMode, DISP3DCNT},
+    registers::{PolygonMode, TextureParams, DISP3DCNT},
     Color, Engine3D, TextureFormat, GPU,
 };
 
@@ -22,6 +24,10 @@ impl Engine3D {
         if !self.polygons_submitted {
             return;
         }
+        self.finish_recording(vram);
+        if self.capture_scene {
+            self.take_snapshot(vram);
+        }
         // TODO: Optimize
         for pixel in self.frame_buffer.iter_mut() {
             pixel.color = FrameBufferColor::new5(
@@ -90,6 +96,9 @@ impl Engine3D {
         self.vertices.clear();
         self.gxstat.geometry_engine_busy = false;
         self.polygons_submitted = false;
+        if self.record_frame {
+            self.start_recording();
+        }
     }
 
     fn render_polygon<B>(
@@ -276,8 +285,6 @@ impl Engine3D {
     }
 
     fn get_tex_color(vram: &VRAM, polygon: &Polygon, s: i32, t: i32) -> Option<FrameBufferColor> {
-        let vram_offset = polygon.tex_params.vram_offset;
-        let pal_offset = polygon.palette_base;
         let size = (
             polygon.tex_params.size_s as u32,
             polygon.tex_params.size_t as u32,
@@ -320,11 +327,23 @@ impl Engine3D {
         } else {
             t as u32
         } as usize;
-        let texel = t * polygon.tex_params.size_s + s;
-        let color0_transparent = polygon.tex_params.color0_transparent;
+        Self::decode_texel(vram, &polygon.tex_params, polygon.palette_base, s, t)
+    }
+
+    // Decodes the texel at s, t which must be within the texture
+    pub(super) fn decode_texel(
+        vram: &VRAM,
+        tex_params: &TextureParams,
+        pal_offset: usize,
+        s: usize,
+        t: usize,
+    ) -> Option<FrameBufferColor> {
+        let vram_offset = tex_params.vram_offset;
+        let texel = t * tex_params.size_s + s;
+        let color0_transparent = tex_params.color0_transparent;
 
         // TODO: Remove code duplication
-        match polygon.tex_params.format {
+        match tex_params.format {
             TextureFormat::NoTexture => None,
             TextureFormat::A3I5 => Some({
                 let byte = vram.get_textures::<u8>(vram_offset + texel);
@@ -362,7 +381,7 @@ impl Engine3D {
                 FrameBufferColor::new5(color, alpha)
             }),
             TextureFormat::Compressed => Some({
-                let num_blocks_row = polygon.tex_params.size_s / 4;
+                let num_blocks_row = tex_params.size_s / 4;
                 let block_start_addr = t / 4 * num_blocks_row + s / 4;
                 let base_addr = vram_offset + 4 * block_start_addr;
                 let te = vram.get_textures::<u8>(base_addr + t % 4);
@@ -762,7 +781,7 @@ impl<const N: u8, const M: u8> std::ops::SubAssign<Frac<M>> for Frac<N> {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct FrameBufferPixel {
     color: FrameBufferColor,
     depth: u32,
@@ -777,8 +796,8 @@ impl FrameBufferPixel {
     }
 }
 
-#[derive(Clone, Copy)]
-struct FrameBufferColor {
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub(super) struct FrameBufferColor {
     color: Color,
     a: u8,
 }
@@ -821,6 +840,10 @@ impl FrameBufferColor {
         self.a >> 2
     }
 
+    pub fn as_rgba8(&self) -> [u8; 4] {
+        [self.color.r8(), self.color.g8(), self.color.b8(), self.a]
+    }
+
     // TODO: Convert 2D engine to also use 8 bit color
     pub fn as_u16(&self) -> u16 {
         self.color.as_u16() | if self.a == 0 { 0 } else { 0x8000 }
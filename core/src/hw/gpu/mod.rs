@@ -4,6 +4,8 @@ mod engine3d;
 mod registers;
 mod vram;
 
+use serde::{Deserialize, Serialize};
+
 use crate::hw::{
     dma::DMAOccasion,
     interrupt_controller::{InterruptController, InterruptRequest},
@@ -11,14 +13,19 @@ use crate::hw::{
     HW,
 };
 
+pub use debug::{LayerToggles, OBJInfo, OBJMode};
 pub use engine2d::Engine2D;
-pub use engine3d::Engine3D;
+pub use engine3d::{
+    Engine3D, GeometryDump, LoadDumpError, MatrixSnapshot, PolygonMode, PolygonSnapshot,
+    SceneSnapshot, TextureFormat, TextureInfo, VertexSnapshot,
+};
 pub use registers::{DISPSTATFlags, DISPCAPCNT, DISPSTAT, POWCNT1};
 pub use vram::VRAM;
 
 use engine2d::DisplayMode;
 use registers::CaptureSource;
 
+#[derive(Serialize, Deserialize)]
 pub struct GPU {
     // Registers and Values Shared between Engines
     pub dispstats: [DISPSTAT; 2],
@@ -184,8 +191,12 @@ impl GPU {
         rendered_frame
     }
 
+    pub fn engine_a_on_top(&self) -> bool {
+        self.powcnt1.contains(POWCNT1::TOP_A)
+    }
+
     pub fn get_screens(&self) -> [&Vec<u16>; 2] {
-        if self.powcnt1.contains(POWCNT1::TOP_A) {
+        if self.engine_a_on_top() {
             [&self.engine_a.pixels(), &self.engine_b.pixels()]
         } else {
             [&self.engine_b.pixels(), &self.engine_a.pixels()]
@@ -194,7 +205,7 @@ impl GPU {
 }
 
 impl HW {
-    fn start_next_line(&mut self, _event: Event) {
+    pub(super) fn start_next_line(&mut self, _event: Event) {
         self.scheduler.schedule(
             Event::HBlank,
             HW::on_hblank,
@@ -233,7 +244,7 @@ impl HW {
         });
     }
 
-    fn on_hblank(&mut self, _event: Event) {
+    pub(super) fn on_hblank(&mut self, _event: Event) {
         self.scheduler.schedule(
             Event::StartNextLine,
             HW::start_next_line,
@@ -253,7 +264,7 @@ impl HW {
         });
     }
 
-    fn on_vblank(&mut self, _event: Event) {
+    pub(super) fn on_vblank(&mut self, _event: Event) {
         self.run_dmas(DMAOccasion::VBlank);
         // TODO: Render using multiple threads
         if self.gpu.powcnt1.contains(POWCNT1::ENABLE_3D_RENDERING) {
@@ -1,10 +1,12 @@
 use std::ops::{Deref, DerefMut};
 
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
 use crate::hw::{mem::IORegister, Scheduler, HW};
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct POWCNT1: u32 {
         const ENABLE_LCDS = 1 << 0;
         const ENABLE_ENGINE_A = 1 << 1;
@@ -30,6 +32,7 @@ impl IORegister for POWCNT1 {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct DISPSTATFlags: u16 {
         const VBLANK = 1 << 0;
         const HBLANK = 1 << 1;
@@ -40,6 +43,7 @@ bitflags! {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DISPSTAT {
     pub flags: DISPSTATFlags,
     pub vcount_setting: u16,
@@ -92,6 +96,7 @@ impl IORegister for DISPSTAT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DISPCAPCNT {
     pub eva: u8,
     pub evb: u8,
@@ -164,7 +169,7 @@ impl IORegister for DISPCAPCNT {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum CaptureOffset {
     O00000 = 0,
     O08000 = 1,
@@ -195,7 +200,7 @@ impl From<u8> for CaptureOffset {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum CaptureSize {
     S128x128 = 0,
     S256x64 = 1,
@@ -235,7 +240,7 @@ impl From<u8> for CaptureSize {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum CaptureSource {
     A = 0,
     B = 1,
@@ -1,10 +1,12 @@
 use num_traits as num;
+use serde::{Deserialize, Serialize};
 
 use super::{
     super::{MemoryValue, HW},
     EngineType,
 };
 
+#[derive(Serialize, Deserialize)]
 pub struct VRAM {
     cnts: [VRAMCNT; 9],
     pub(super) banks: [Vec<u8>; 9],
@@ -37,6 +39,8 @@ impl VRAM {
         16 * 0x400,
     ];
     const MAPPING_LEN: usize = 16 * 0x400;
+    pub(super) const TEXTURES_LEN: usize = 32 * VRAM::MAPPING_LEN;
+    pub(super) const TEXTURES_PAL_LEN: usize = 6 * VRAM::MAPPING_LEN;
 
     const LCDC_OFFSETS: [usize; 9] = [
         0x0_0000, 0x2_0000, 0x4_0000, 0x6_0000, 0x8_0000, 0x9_0000, 0x9_4000, 0x9_8000, 0xA_0000,
@@ -484,7 +488,7 @@ impl VRAM {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 struct VRAMCNT {
     mst: u8,
     offset: u8,
@@ -510,7 +514,7 @@ impl VRAMCNT {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 enum Bank {
     A = 0,
     B = 1,
@@ -587,5 +591,51 @@ mod debug {
             }
             (pixels, width * 32, height * 32)
         }
+
+        // Copies of the texture and texture palette slots as the 3D engine sees them
+        pub fn textures_snapshot(&self) -> (Vec<u8>, Vec<u8>) {
+            let textures = (0..VRAM::TEXTURES_LEN)
+                .map(|addr| self.get_textures::<u8>(addr))
+                .collect();
+            let palettes = (0..VRAM::TEXTURES_PAL_LEN)
+                .map(|addr| self.get_textures_pal::<u8>(addr))
+                .collect();
+            (textures, palettes)
+        }
+
+        // Banks A-D hold the texture slots and E-G the palette slots, nothing else is mapped
+        pub fn from_textures(textures: &[u8], palettes: &[u8]) -> VRAM {
+            assert_eq!(textures.len(), VRAM::TEXTURES_LEN);
+            assert_eq!(palettes.len(), VRAM::TEXTURES_PAL_LEN);
+            let mut vram = VRAM::new();
+            for (index, offset) in [
+                (VRAM::BANK_A, 0),
+                (VRAM::BANK_B, 1),
+                (VRAM::BANK_C, 2),
+                (VRAM::BANK_D, 3),
+                (VRAM::BANK_E, 0),
+                (VRAM::BANK_F, 2),
+                (VRAM::BANK_G, 3),
+            ]
+            .iter()
+            {
+                vram.write_vram_cnt(*index, 0x80 | offset << 3 | 3);
+            }
+            for (bank, data) in vram.banks[VRAM::BANK_A..=VRAM::BANK_D]
+                .iter_mut()
+                .zip(textures.chunks(VRAM::BANKS_LEN[VRAM::BANK_A]))
+            {
+                bank.copy_from_slice(data);
+            }
+            let (bank_e, bank_f_g) = palettes.split_at(VRAM::BANKS_LEN[VRAM::BANK_E]);
+            vram.banks[VRAM::BANK_E].copy_from_slice(bank_e);
+            for (bank, data) in vram.banks[VRAM::BANK_F..=VRAM::BANK_G]
+                .iter_mut()
+                .zip(bank_f_g.chunks(VRAM::BANKS_LEN[VRAM::BANK_F]))
+            {
+                bank.copy_from_slice(data);
+            }
+            vram
+        }
     }
 }
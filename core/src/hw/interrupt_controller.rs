@@ -1,6 +1,8 @@
 use super::{mem::IORegister, Scheduler};
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct InterruptController {
     pub enable: InterruptEnable,
     pub master_enable: InterruptMasterEnable,
@@ -22,6 +24,7 @@ impl InterruptController {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct InterruptEnable: u32 {
         const VBLANK = 1 << 0;
         const HBLANK = 1 << 1;
@@ -47,12 +50,14 @@ bitflags! {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct InterruptMasterEnable: u32 {
         const ENABLE = 1 << 0;
     }
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct InterruptRequest: u32 {
         const VBLANK = 1 << 0;
         const HBLANK = 1 << 1;
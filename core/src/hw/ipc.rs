@@ -1,7 +1,10 @@
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-use super::interrupt_controller::InterruptRequest;
+use super::{interrupt_controller::InterruptRequest, HW};
+use crate::nds::CPU;
 
+#[derive(Serialize, Deserialize)]
 pub struct IPC {
     fifocnt7: FIFOCNT,
     sync7: SYNC,
@@ -58,6 +61,22 @@ impl IPC {
         )
     }
 
+    // What the next receive returns without popping it
+    pub fn arm7_peek(&self) -> u32 {
+        IPC::peek(&self.fifocnt7, &self.output9, self.prev_value9)
+    }
+    pub fn arm9_peek(&self) -> u32 {
+        IPC::peek(&self.fifocnt9, &self.output7, self.prev_value7)
+    }
+
+    // Words sent by the CPU that haven't been received yet
+    pub fn pending_words(&self, cpu: CPU) -> usize {
+        match cpu {
+            CPU::ARM7 => self.output7.len(),
+            CPU::ARM9 => self.output9.len(),
+        }
+    }
+
     pub fn write_sync7(&mut self, byte: usize, value: u8) -> InterruptRequest {
         self.sync7.write(&mut self.sync9, byte, value)
     }
@@ -130,6 +149,13 @@ impl IPC {
         (*prev_value, interrupt)
     }
 
+    fn peek(recv_cnt: &FIFOCNT, recv_fifo: &VecDeque<u32>, prev_value: u32) -> u32 {
+        if !recv_cnt.enable {
+            return prev_value;
+        }
+        recv_fifo.front().copied().unwrap_or(prev_value)
+    }
+
     fn send(
         send_cnt: &mut FIFOCNT,
         recv_cnt: &FIFOCNT,
@@ -154,6 +180,169 @@ impl IPC {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IPCAccess {
+    Send,
+    Recv,
+    Sync,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IPCInterrupt {
+    Sync,
+    SendFIFOEmpty,
+    RecvFIFONotEmpty,
+}
+
+// An access as seen by the CPU making it, interrupts are raised on the other CPU
+#[derive(Clone, Copy, Debug)]
+pub struct IPCMessage {
+    pub cpu: CPU,
+    pub access: IPCAccess,
+    pub value: u32,
+    // A send that never reached the FIFO or a receive that didn't pop anything
+    pub lost: bool,
+    pub pending: usize,
+    pub interrupt: Option<IPCInterrupt>,
+    pub frame: usize,
+    pub vcount: u16,
+    pub cycle: usize,
+}
+
+impl HW {
+    const IPC_TRACE_LEN: usize = 0x1_0000;
+
+    pub fn ipc_trace(&self) -> &VecDeque<IPCMessage> {
+        &self.ipc_trace
+    }
+
+    pub fn set_trace_ipc(&mut self, trace: bool) {
+        self.trace_ipc = trace;
+        if !trace {
+            self.clear_ipc_trace();
+        }
+    }
+
+    pub fn clear_ipc_trace(&mut self) {
+        self.ipc_trace.clear();
+        self.new_ipc_messages.clear();
+    }
+
+    // Like tag_dma_trace
+    pub fn tag_ipc_trace(&mut self, frame: usize) {
+        for mut message in self.new_ipc_messages.drain(..) {
+            message.frame = frame;
+            if self.ipc_trace.len() == HW::IPC_TRACE_LEN {
+                self.ipc_trace.pop_front();
+            }
+            self.ipc_trace.push_back(message);
+        }
+    }
+
+    pub(super) fn ipc_send(&mut self, cpu: CPU, value: u32) {
+        let prev_pending = self.ipc.pending_words(cpu);
+        let (interrupt, other) = match cpu {
+            CPU::ARM7 => (self.ipc.arm7_send(value), CPU::ARM9),
+            CPU::ARM9 => (self.ipc.arm9_send(value), CPU::ARM7),
+        };
+        self.request_ipc_interrupt(other, interrupt);
+        if self.trace_ipc {
+            let pending = self.ipc.pending_words(cpu);
+            self.trace_ipc(
+                cpu,
+                IPCAccess::Send,
+                value,
+                pending == prev_pending,
+                pending,
+                interrupt,
+            );
+        }
+    }
+
+    pub(super) fn ipc_recv(&mut self, cpu: CPU) -> u32 {
+        let other = match cpu {
+            CPU::ARM7 => CPU::ARM9,
+            CPU::ARM9 => CPU::ARM7,
+        };
+        let prev_pending = self.ipc.pending_words(other);
+        let (value, interrupt) = match cpu {
+            CPU::ARM7 => self.ipc.arm7_recv(),
+            CPU::ARM9 => self.ipc.arm9_recv(),
+        };
+        self.request_ipc_interrupt(other, interrupt);
+        if self.trace_ipc {
+            let pending = self.ipc.pending_words(other);
+            self.trace_ipc(
+                cpu,
+                IPCAccess::Recv,
+                value,
+                pending == prev_pending,
+                pending,
+                interrupt,
+            );
+        }
+        value
+    }
+
+    pub(super) fn write_ipc_sync(&mut self, cpu: CPU, byte: usize, value: u8) {
+        let (interrupt, other) = match cpu {
+            CPU::ARM7 => (self.ipc.write_sync7(byte, value), CPU::ARM9),
+            CPU::ARM9 => (self.ipc.write_sync9(byte, value), CPU::ARM7),
+        };
+        self.request_ipc_interrupt(other, interrupt);
+        // Only the second byte does anything
+        if self.trace_ipc && byte == 1 {
+            self.trace_ipc(
+                cpu,
+                IPCAccess::Sync,
+                value as u32 & 0xF,
+                false,
+                0,
+                interrupt,
+            );
+        }
+    }
+
+    fn request_ipc_interrupt(&mut self, cpu: CPU, interrupt: InterruptRequest) {
+        match cpu {
+            CPU::ARM7 => self.interrupts[0].request |= interrupt,
+            CPU::ARM9 => self.interrupts[1].request |= interrupt,
+        }
+    }
+
+    fn trace_ipc(
+        &mut self,
+        cpu: CPU,
+        access: IPCAccess,
+        value: u32,
+        lost: bool,
+        pending: usize,
+        interrupt: InterruptRequest,
+    ) {
+        let interrupt = if interrupt.contains(InterruptRequest::IPC_SYNC) {
+            Some(IPCInterrupt::Sync)
+        } else if interrupt.contains(InterruptRequest::IPC_SEND_FIFO_EMPTY) {
+            Some(IPCInterrupt::SendFIFOEmpty)
+        } else if interrupt.contains(InterruptRequest::IPC_RECV_FIFO_NOT_EMPTY) {
+            Some(IPCInterrupt::RecvFIFONotEmpty)
+        } else {
+            None
+        };
+        self.new_ipc_messages.push(IPCMessage {
+            cpu,
+            access,
+            value,
+            lost,
+            pending,
+            interrupt,
+            frame: 0, // Set by tag_ipc_trace
+            vcount: self.gpu.vcount,
+            cycle: self.scheduler.cycle,
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 struct SYNC {
     input: u8,
     output: u8,
@@ -199,7 +388,7 @@ impl SYNC {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 struct FIFOCNT {
     send_fifo_empty_irq: bool,
     recv_fifo_not_empty_irq: bool,
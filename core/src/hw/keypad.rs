@@ -1,5 +1,6 @@
 use super::{mem::IORegister, Scheduler};
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Key {
@@ -17,6 +18,7 @@ pub enum Key {
     L = 9,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Keypad {
     pub keyinput: KEYINPUT,
     pub keycnt: KEYCNT,
@@ -71,6 +73,7 @@ impl Keypad {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct KEYINPUT: u16 {
         const A = 1 << 0;
         const B = 1 << 1;
@@ -86,6 +89,7 @@ bitflags! {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct KEYCNT: u16 {
         const A = 1 << 0;
         const B = 1 << 1;
@@ -103,6 +107,7 @@ bitflags! {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     pub struct EXTKEYIN: u8 {
         const X = 1 << 0;
         const Y = 1 << 1;
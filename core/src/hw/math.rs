@@ -1,6 +1,8 @@
 use super::{mem::IORegister, scheduler::Scheduler, HW};
 use num_integer::Roots;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct Div {
     pub cnt: DIVCNT,
     numer: MathParam,
@@ -81,6 +83,7 @@ impl Div {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Sqrt {
     pub cnt: SQRTCNT,
     param: MathParam,
@@ -114,6 +117,7 @@ impl Sqrt {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct MathParam {
     value: u64,
 }
@@ -136,6 +140,7 @@ impl IORegister for MathParam {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DIVCNT {
     mode: u8,
     div_by_0: bool,
@@ -171,6 +176,7 @@ impl IORegister for DIVCNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct SQRTCNT {
     is_64bit: bool,
     busy: bool,
@@ -1,9 +1,13 @@
 use super::{AccessType, IORegister, MemoryValue, HW};
+use crate::nds::CPU;
 
 type MemoryRegion = ARM7MemoryRegion;
 
 impl HW {
     pub fn arm7_read<T: MemoryValue>(&mut self, addr: u32) -> T {
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(CPU::ARM7, addr, std::mem::size_of::<T>(), false);
+        }
         match MemoryRegion::from_addr(addr) {
             MemoryRegion::BIOS => HW::read_mem(&self.bios7, addr),
             MemoryRegion::MainMem => HW::read_mem(&self.main_mem, addr & HW::MAIN_MEM_MASK),
@@ -30,6 +34,9 @@ impl HW {
     }
 
     pub fn arm7_write<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(CPU::ARM7, addr, std::mem::size_of::<T>(), true);
+        }
         match MemoryRegion::from_addr(addr) {
             MemoryRegion::BIOS => warn!("Writing to BIOS7 0x{:08x} = 0x{:X}", addr, value),
             MemoryRegion::MainMem => {
@@ -56,8 +63,88 @@ impl HW {
         }
     }
 
+    // Reads like the ARM7 without side effects. The IPC FIFO shows the value the next read
+    // returns and the game card data register reads as 0.
+    pub fn arm7_peek<T: MemoryValue>(&self, addr: u32) -> T {
+        HW::read_from_bytes(self, &HW::arm7_peek_byte, addr)
+    }
+
+    // Writes like the ARM7 to memory only, writes to IO registers, the BIOS and the ROM are ignored
+    pub fn arm7_poke<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        HW::write_from_bytes(self, &HW::arm7_poke_byte, addr, value)
+    }
+
+    fn arm7_peek_byte(&self, addr: u32) -> u8 {
+        // Unlike the ARM9, unknown regions aren't handled by MemoryRegion
+        if !matches!(addr >> 24, 0x0 | 0x2..=0x4 | 0x6 | 0x8..=0xA) {
+            return 0;
+        }
+        match MemoryRegion::from_addr(addr) {
+            MemoryRegion::BIOS => self.bios7.get(addr as usize).copied().unwrap_or(0),
+            MemoryRegion::MainMem => self.main_mem[(addr & HW::MAIN_MEM_MASK) as usize],
+            MemoryRegion::SharedWRAM if self.wramcnt.arm7_mask == 0 => {
+                self.iwram[(addr & HW::IWRAM_MASK) as usize]
+            }
+            MemoryRegion::SharedWRAM => {
+                self.shared_wram
+                    [(self.wramcnt.arm7_offset + (addr & self.wramcnt.arm7_mask)) as usize]
+            }
+            MemoryRegion::IWRAM => self.iwram[(addr & HW::IWRAM_MASK) as usize],
+            MemoryRegion::IO if (0x0410_0000..=0x0410_0003).contains(&addr) => {
+                (self.ipc.arm7_peek() >> (8 * (addr & 0x3))) as u8
+            }
+            MemoryRegion::IO if (0x0410_0010..=0x0410_0013).contains(&addr) => 0,
+            // Card registers read as 0 without access, minus the warning a normal read logs
+            MemoryRegion::IO
+                if (0x0400_01A0..=0x0400_01AF).contains(&addr) && !self.exmem.nds_arm7_access =>
+            {
+                0
+            }
+            // Write-only sound registers also read as 0 without the warning
+            MemoryRegion::IO
+                if (0x0400_0400..=0x0400_04FF).contains(&addr) && addr & 0xF >= 0x4
+                    || (0x0400_0510..=0x0400_051F).contains(&addr) && addr & 0x7 >= 0x4 =>
+            {
+                0
+            }
+            MemoryRegion::IO => self.arm7_io_register(addr).unwrap_or(0),
+            MemoryRegion::VRAM => self.gpu.vram.arm7_read(addr),
+            MemoryRegion::GBAROM => self.read_gba_rom(false, addr),
+            MemoryRegion::GBARAM => 0,
+        }
+    }
+
+    fn arm7_poke_byte(&mut self, addr: u32, value: u8) {
+        if !matches!(addr >> 24, 0x0 | 0x2..=0x4 | 0x6 | 0x8..=0xA) {
+            return;
+        }
+        match MemoryRegion::from_addr(addr) {
+            MemoryRegion::MainMem => self.main_mem[(addr & HW::MAIN_MEM_MASK) as usize] = value,
+            MemoryRegion::SharedWRAM if self.wramcnt.arm7_mask == 0 => {
+                self.iwram[(addr & HW::IWRAM_MASK) as usize] = value
+            }
+            MemoryRegion::SharedWRAM => {
+                self.shared_wram
+                    [(self.wramcnt.arm7_offset + (addr & self.wramcnt.arm7_mask)) as usize] = value
+            }
+            MemoryRegion::IWRAM => self.iwram[(addr & HW::IWRAM_MASK) as usize] = value,
+            MemoryRegion::VRAM => self.gpu.vram.arm7_write(addr, value),
+            MemoryRegion::BIOS | MemoryRegion::GBAROM | MemoryRegion::GBARAM => (),
+            // IO registers have side effects on write
+            MemoryRegion::IO => (),
+        }
+    }
+
     fn arm7_read_io_register(&self, addr: u32) -> u8 {
-        match addr {
+        self.arm7_io_register(addr).unwrap_or_else(|| {
+            warn!("Ignoring ARM7 IO Register Read at 0x{:08X}", addr);
+            0
+        })
+    }
+
+    // Returns None for unmapped registers
+    fn arm7_io_register(&self, addr: u32) -> Option<u8> {
+        Some(match addr {
             0x0400_0004 => self.gpu.dispstats[0].read(0),
             0x0400_0005 => self.gpu.dispstats[0].read(1),
             0x0400_0006 => (self.gpu.vcount >> 0) as u8,
@@ -119,14 +206,13 @@ impl HW {
             0x0400_0305 => self.powcnt2.read(1),
             0x0400_0306 => self.powcnt2.read(2),
             0x0400_0307 => self.powcnt2.read(3),
-            0x0400_0400..=0x0400_051F => self.spu.read(addr as usize & 0xFFF),
+            0x0400_0400..=0x0400_0509 | 0x0400_0510..=0x0400_051F => {
+                self.spu.read(addr as usize & 0xFFF)
+            }
             0x0480_4000..=0x0480_5FFF => 0, // TODO: WiFi RAM
             0x0480_8000..=0x0480_8FFF => 0, // TOOD: WiFi Registers
-            _ => {
-                warn!("Ignoring ARM7 IO Register Read at 0x{:08X}", addr);
-                0
-            }
-        }
+            _ => return None,
+        })
     }
 
     fn arm7_write_io_register(&mut self, addr: u32, value: u8) {
@@ -163,10 +249,7 @@ impl HW {
             0x0400_0136 => self.keypad.extkeyin.write(&mut self.scheduler, 0, value),
             0x0400_0137 => self.keypad.extkeyin.write(&mut self.scheduler, 1, value),
             0x0400_0138..=0x0400_0139 => (), // TODO: RTC
-            0x0400_0180 => self.interrupts[1].request |= self.ipc.write_sync7(0, value),
-            0x0400_0181 => self.interrupts[1].request |= self.ipc.write_sync7(1, value),
-            0x0400_0182 => self.interrupts[1].request |= self.ipc.write_sync7(2, value),
-            0x0400_0183 => self.interrupts[1].request |= self.ipc.write_sync7(3, value),
+            0x0400_0180..=0x0400_0183 => self.write_ipc_sync(CPU::ARM7, addr as usize % 4, value),
             0x0400_0184 => self.interrupts[0].request |= self.ipc.write_fifocnt7(0, value),
             0x0400_0185 => self.interrupts[0].request |= self.ipc.write_fifocnt7(1, value),
             0x0400_0186 => self.interrupts[0].request |= self.ipc.write_fifocnt7(2, value),
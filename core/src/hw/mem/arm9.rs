@@ -1,5 +1,6 @@
 use super::{AccessType, IORegister, MemoryValue, CP15, HW};
 use crate::hw::gpu::{Engine2D, EngineType, GPU};
+use crate::nds::CPU;
 use crate::num;
 
 type MemoryRegion = ARM9MemoryRegion;
@@ -9,6 +10,9 @@ impl HW {
     const DTCM_MASK: u32 = HW::DTCM_SIZE as u32 - 1;
 
     pub fn arm9_read<T: MemoryValue>(&mut self, addr: u32) -> T {
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(CPU::ARM9, addr, std::mem::size_of::<T>(), false);
+        }
         match MemoryRegion::from_addr(addr, &self.cp15) {
             MemoryRegion::ITCM => HW::read_mem(&self.itcm, addr & HW::ITCM_MASK),
             MemoryRegion::DTCM => HW::read_mem(&self.dtcm, addr & HW::DTCM_MASK),
@@ -41,7 +45,7 @@ impl HW {
             MemoryRegion::OAM => HW::read_mem(&self.gpu.engine_b.oam, addr & GPU::OAM_MASK as u32),
             MemoryRegion::GBAROM => self.read_gba_rom(true, addr),
             MemoryRegion::GBARAM => todo!(),
-            MemoryRegion::BIOS => HW::read_mem(&self.bios9, addr & 0xFFFF),
+            MemoryRegion::BIOS => HW::read_mem(&self.bios9, addr & 0xFFF),
             MemoryRegion::Unknown => {
                 warn!("Reading from Unknown 0x{:08X}", addr);
                 num::zero()
@@ -50,6 +54,9 @@ impl HW {
     }
 
     pub fn arm9_write<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(CPU::ARM9, addr, std::mem::size_of::<T>(), true);
+        }
         match MemoryRegion::from_addr(addr, &self.cp15) {
             MemoryRegion::ITCM => HW::write_mem(&mut self.itcm, addr & HW::ITCM_MASK, value),
             MemoryRegion::DTCM => HW::write_mem(&mut self.dtcm, addr & HW::DTCM_MASK, value),
@@ -98,6 +105,85 @@ impl HW {
         }
     }
 
+    // Reads like the ARM9 without side effects. The IPC FIFO shows the value the next read
+    // returns and the game card data register reads as 0.
+    pub fn arm9_peek<T: MemoryValue>(&self, addr: u32) -> T {
+        HW::read_from_bytes(self, &HW::arm9_peek_byte, addr)
+    }
+
+    // Writes like the ARM9 to memory only, writes to IO registers, the BIOS and the ROM are ignored
+    pub fn arm9_poke<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        HW::write_from_bytes(self, &HW::arm9_poke_byte, addr, value)
+    }
+
+    fn arm9_peek_byte(&self, addr: u32) -> u8 {
+        match MemoryRegion::from_addr(addr, &self.cp15) {
+            MemoryRegion::ITCM => self.itcm[(addr & HW::ITCM_MASK) as usize],
+            MemoryRegion::DTCM => self.dtcm[(addr & HW::DTCM_MASK) as usize],
+            MemoryRegion::MainMem => self.main_mem[(addr & HW::MAIN_MEM_MASK) as usize],
+            MemoryRegion::SharedWRAM if self.wramcnt.arm9_mask == 0 => 0,
+            MemoryRegion::SharedWRAM => {
+                self.shared_wram
+                    [(self.wramcnt.arm9_offset + (addr & self.wramcnt.arm9_mask)) as usize]
+            }
+            MemoryRegion::IO if (0x0410_0000..=0x0410_0003).contains(&addr) => {
+                (self.ipc.arm9_peek() >> (8 * (addr & 0x3))) as u8
+            }
+            MemoryRegion::IO if (0x0410_0010..=0x0410_0013).contains(&addr) => 0,
+            // Card registers read as 0 without access, minus the warning a normal read logs
+            MemoryRegion::IO
+                if (0x0400_01A0..=0x0400_01AF).contains(&addr) && self.exmem.nds_arm7_access =>
+            {
+                0
+            }
+            MemoryRegion::IO => self.arm9_io_register(addr).unwrap_or(0),
+            MemoryRegion::Palette if addr & 0x7FFF < 0x400 => {
+                self.gpu.engine_a.read_palette_ram(addr)
+            }
+            MemoryRegion::Palette => self.gpu.engine_b.read_palette_ram(addr),
+            MemoryRegion::VRAM if addr & 0x00E0_0000 <= 0x0080_0000 => {
+                self.gpu.vram.arm9_read(addr)
+            }
+            MemoryRegion::OAM if addr & 0x7FFF < 0x400 => {
+                self.gpu.engine_a.oam[addr as usize & GPU::OAM_MASK]
+            }
+            MemoryRegion::OAM => self.gpu.engine_b.oam[addr as usize & GPU::OAM_MASK],
+            MemoryRegion::GBAROM => self.read_gba_rom(true, addr),
+            MemoryRegion::BIOS => self.bios9[(addr & 0xFFF) as usize],
+            MemoryRegion::VRAM | MemoryRegion::GBARAM | MemoryRegion::Unknown => 0,
+        }
+    }
+
+    fn arm9_poke_byte(&mut self, addr: u32, value: u8) {
+        match MemoryRegion::from_addr(addr, &self.cp15) {
+            MemoryRegion::ITCM => self.itcm[(addr & HW::ITCM_MASK) as usize] = value,
+            MemoryRegion::DTCM => self.dtcm[(addr & HW::DTCM_MASK) as usize] = value,
+            MemoryRegion::MainMem => self.main_mem[(addr & HW::MAIN_MEM_MASK) as usize] = value,
+            MemoryRegion::SharedWRAM if self.wramcnt.arm9_mask == 0 => (),
+            MemoryRegion::SharedWRAM => {
+                self.shared_wram
+                    [(self.wramcnt.arm9_offset + (addr & self.wramcnt.arm9_mask)) as usize] = value
+            }
+            MemoryRegion::Palette if addr & 0x7FFF < 0x400 => {
+                HW::poke_palette_ram(&mut self.gpu.engine_a, addr, value)
+            }
+            MemoryRegion::Palette => HW::poke_palette_ram(&mut self.gpu.engine_b, addr, value),
+            MemoryRegion::VRAM if addr & 0x00E0_0000 <= 0x0080_0000 => {
+                self.gpu.vram.arm9_write(addr, value)
+            }
+            MemoryRegion::OAM if addr & 0x7FFF < 0x400 => {
+                self.gpu.engine_a.oam[addr as usize & GPU::OAM_MASK] = value
+            }
+            MemoryRegion::OAM => self.gpu.engine_b.oam[addr as usize & GPU::OAM_MASK] = value,
+            MemoryRegion::IO
+            | MemoryRegion::VRAM
+            | MemoryRegion::GBAROM
+            | MemoryRegion::GBARAM
+            | MemoryRegion::BIOS
+            | MemoryRegion::Unknown => (),
+        }
+    }
+
     pub fn arm9_get_access_time<T: MemoryValue>(
         &mut self,
         _access_type: AccessType,
@@ -119,7 +205,15 @@ impl HW {
     }
 
     fn arm9_read_io_register(&self, addr: u32) -> u8 {
-        match addr {
+        self.arm9_io_register(addr).unwrap_or_else(|| {
+            warn!("Ignoring ARM9 IO Register Read at 0x{:08X}", addr);
+            0
+        })
+    }
+
+    // Returns None for unmapped registers
+    fn arm9_io_register(&self, addr: u32) -> Option<u8> {
+        Some(match addr {
             0x0400_0000..=0x0400_0003 => self.gpu.engine_a.read_register(addr),
             0x0400_0004 => self.gpu.dispstats[1].read(0),
             0x0400_0005 => self.gpu.dispstats[1].read(1),
@@ -205,7 +299,10 @@ impl HW {
             0x0400_0305 => self.gpu.powcnt1.read(1),
             0x0400_0306 => self.gpu.powcnt1.read(2),
             0x0400_0307 => self.gpu.powcnt1.read(3),
-            0x0400_0320..=0x0400_06A3 => self.gpu.engine3d.read_register(addr),
+            // The rest of the 3D registers are write-only
+            0x0400_04A4..=0x0400_04A7 | 0x0400_0600..=0x0400_0607 | 0x0400_0640..=0x0400_067F => {
+                self.gpu.engine3d.read_register(addr)
+            }
             0x0400_1000..=0x0400_1003 => self.gpu.engine_b.read_register(addr),
             0x0400_1004..=0x0400_1007 => 0,
             0x0400_1008..=0x0400_105F => self.gpu.engine_b.read_register(addr),
@@ -215,11 +312,8 @@ impl HW {
             0x0400_106E => self.gpu.engine_b.master_bright.read(2),
             0x0400_106F => self.gpu.engine_b.master_bright.read(3),
             0x0400_4010..=0x0400_4011 => 0, // DSi register that's unused for NDS
-            _ => {
-                warn!("Ignoring ARM9 IO Register Read at 0x{:08X}", addr);
-                0
-            }
-        }
+            _ => return None,
+        })
     }
 
     fn arm9_write_io_register(&mut self, addr: u32, value: u8) {
@@ -241,8 +335,7 @@ impl HW {
             0x0400_0060..=0x0400_0063 => {
                 self.gpu
                     .engine3d
-                    .disp3dcnt
-                    .write(&mut self.scheduler, addr as usize % 4, value)
+                    .write_disp3dcnt(&mut self.scheduler, addr as usize % 4, value)
             }
             0x0400_0064..=0x0400_0067 => {
                 self.gpu
@@ -311,10 +404,7 @@ impl HW {
             0x0400_0133 => self.keypad.keycnt.write(&mut self.scheduler, 1, value),
             0x0400_0136 => self.keypad.extkeyin.write(&mut self.scheduler, 0, value),
             0x0400_0137 => self.keypad.extkeyin.write(&mut self.scheduler, 1, value),
-            0x0400_0180 => self.interrupts[0].request |= self.ipc.write_sync9(0, value),
-            0x0400_0181 => self.interrupts[0].request |= self.ipc.write_sync9(1, value),
-            0x0400_0182 => self.interrupts[0].request |= self.ipc.write_sync9(2, value),
-            0x0400_0183 => self.interrupts[0].request |= self.ipc.write_sync9(3, value),
+            0x0400_0180..=0x0400_0183 => self.write_ipc_sync(CPU::ARM9, addr as usize % 4, value),
             0x0400_0184 => self.interrupts[1].request |= self.ipc.write_fifocnt9(0, value),
             0x0400_0185 => self.interrupts[1].request |= self.ipc.write_fifocnt9(1, value),
             0x0400_0186 => self.interrupts[1].request |= self.ipc.write_fifocnt9(2, value),
@@ -452,7 +542,7 @@ impl HW {
                 self.sqrt
                     .write_param(&mut self.scheduler, addr as usize & 0x7, value)
             }
-            0x0400_0300 => self.postflg9 = (self.postflg9 & !0x02 | value & 0x02) | (value & 0x1), // Only bit 1 is writable
+            0x0400_0300 => self.postflg9 = self.postflg9 & 0x1 | value & 0x3, // Bit 0 cannot be cleared once set
             0x0400_0301..=0x0400_0303 => (), // Other Parts of POSTFLG
             0x0400_0304 => self.gpu.powcnt1.write(&mut self.scheduler, 0, value),
             0x0400_0305 => self.gpu.powcnt1.write(&mut self.scheduler, 1, value),
@@ -534,6 +624,16 @@ impl HW {
             _ => unreachable!(),
         }
     }
+
+    // Palette RAM is only written in halfwords
+    fn poke_palette_ram<E: EngineType>(engine: &mut Engine2D<E>, addr: u32, value: u8) {
+        let shift = 8 * (addr & 0x1);
+        let color = (engine.read_palette_ram(addr & !0x1) as u16
+            | (engine.read_palette_ram(addr | 0x1) as u16) << 8)
+            & !(0xFF << shift)
+            | (value as u16) << shift;
+        engine.write_palette_ram((addr & !0x1) as usize, color);
+    }
 }
 
 #[derive(PartialEq)]
@@ -571,10 +671,8 @@ impl ARM9MemoryRegion {
             0x8 | 0x9 => GBAROM,
             0xA => GBARAM,
             0xFF if addr >> 16 == 0xFFFF => BIOS,
-            _ => {
-                warn!("Uknown Memory Access: {:X}", addr);
-                Unknown
-            }
+            // Reads and writes log the access, peeks and pokes stay silent
+            _ => Unknown,
         }
     }
 }
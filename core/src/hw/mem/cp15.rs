@@ -1,7 +1,9 @@
 use bitflags::*;
+use serde::{Deserialize, Serialize};
 
 use super::HW;
 
+#[derive(Serialize, Deserialize)]
 pub struct CP15 {
     control: Control,
     interrupt_base: u32,
@@ -193,6 +195,7 @@ impl CP15 {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct TCMControl {
     pub base: u32,
     pub virtual_size: u32,
@@ -228,6 +231,7 @@ impl TCMControl {
 }
 
 bitflags! {
+    #[derive(Serialize, Deserialize)]
     struct Control: u32 {
         const ITCM_WRITE_ONLY = 1 << 19;
         const ITCM_ENABLE = 1 << 18;
@@ -272,4 +276,8 @@ impl CP15 {
     pub fn interrupt_base(&self) -> u32 {
         self.interrupt_base
     }
+
+    pub fn dtcm_base(&self) -> u32 {
+        self.dtcm_control.base
+    }
 }
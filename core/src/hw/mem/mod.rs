@@ -3,8 +3,10 @@ pub mod arm9;
 pub mod cp15;
 
 use super::{Scheduler, HW};
+use crate::nds::CPU;
 use crate::num::{self, cast::FromPrimitive, NumCast, PrimInt, Unsigned};
 pub use cp15::CP15;
+use serde::{Deserialize, Serialize};
 use std::mem::size_of;
 use std::ops::BitOrAssign;
 
@@ -17,15 +19,8 @@ impl HW {
         if addr != 0x0410_0000 || size_of::<T>() != 4 {
             todo!()
         }
-        if is_arm9 {
-            let (value, interrupt) = self.ipc.arm9_recv();
-            self.interrupts[0].request |= interrupt;
-            num::cast::<u32, T>(value).unwrap()
-        } else {
-            let (value, interrupt) = self.ipc.arm7_recv();
-            self.interrupts[1].request |= interrupt;
-            num::cast::<u32, T>(value).unwrap()
-        }
+        let value = self.ipc_recv(if is_arm9 { CPU::ARM9 } else { CPU::ARM7 });
+        num::cast::<u32, T>(value).unwrap()
     }
 
     fn ipc_fifo_send<T: MemoryValue>(&mut self, is_arm9: bool, addr: u32, value: T) {
@@ -33,11 +28,8 @@ impl HW {
             todo!()
         }
         let value = num::cast::<T, u32>(value).unwrap();
-        if is_arm9 {
-            self.interrupts[1].request |= self.ipc.arm7_send(value);
-        } else {
-            self.interrupts[0].request |= self.ipc.arm9_send(value);
-        }
+        // is_arm9 is the receiving CPU here
+        self.ipc_send(if is_arm9 { CPU::ARM7 } else { CPU::ARM9 }, value);
     }
 
     fn read_game_card<T: MemoryValue>(&mut self, is_arm9: bool, addr: u32) -> T {
@@ -135,7 +127,7 @@ impl MemoryValue for u16 {}
 impl MemoryValue for u32 {}
 impl MemoryValue for u64 {}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum AccessType {
     N,
     S,
@@ -146,6 +138,7 @@ pub trait IORegister {
     fn write(&mut self, scheduler: &mut Scheduler, byte: usize, value: u8);
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct EXMEM {
     gba: [ExMemGBA; 2],
     gba_arm7_access: bool,
@@ -191,6 +184,7 @@ impl EXMEM {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ExMemGBA {
     sram_access_time: u8,
     rom_n_access_time: u8,
@@ -223,6 +217,7 @@ impl ExMemGBA {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct WRAMCNT {
     value: u8,
 
@@ -288,6 +283,7 @@ impl IORegister for WRAMCNT {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct POWCNT2 {
     enable_sound: bool,
     enable_wifi: bool,
@@ -323,7 +319,7 @@ impl IORegister for POWCNT2 {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum HaltMode {
     None = 0,
     GBA = 1,
@@ -343,6 +339,7 @@ impl HaltMode {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct HALTCNT {
     mode: HaltMode,
 }
@@ -354,6 +351,9 @@ impl HALTCNT {
         }
     }
 
+    pub fn halt(&mut self) {
+        self.mode = HaltMode::Halt;
+    }
     pub fn unhalt(&mut self) {
         self.mode = HaltMode::None;
     }
@@ -1,3 +1,4 @@
+mod bios;
 mod cartridge;
 mod dma;
 mod gpu;
@@ -11,29 +12,46 @@ mod spi;
 mod spu;
 mod timers;
 
+use std::collections::VecDeque;
 use std::convert::TryInto;
-use std::path::PathBuf;
 
+pub use bios::SWIResult;
 use cartridge::Cartridge;
+pub use cartridge::{Save, SaveType};
 use dma::DMAController;
-pub use gpu::{EngineA, EngineB, GPU};
+pub use dma::{DMAOccasion, DMATransfer};
+pub use gpu::{
+    EngineA, EngineB, GeometryDump, LayerToggles, LoadDumpError, MatrixSnapshot, OBJInfo, OBJMode,
+    PolygonMode, PolygonSnapshot, SceneSnapshot, TextureFormat, TextureInfo, VertexSnapshot, GPU,
+};
 use interrupt_controller::{InterruptController, InterruptRequest};
 use ipc::IPC;
+pub use ipc::{IPCAccess, IPCInterrupt, IPCMessage};
 pub use keypad::Key;
 use keypad::Keypad;
 use math::{Div, Sqrt};
 pub use mem::{AccessType, MemoryValue};
 use mem::{CP15, EXMEM, HALTCNT, POWCNT2, WRAMCNT};
 use scheduler::Scheduler;
+pub use scheduler::{Event, FiredEvent, ScheduledEvent};
+pub use spi::firmware::{generate_firmware, Language, UserSettings};
 use spi::SPI;
+pub use spu::audio::{AudioSink, BufferSink, NullSink, AUDIO_SAMPLE_RATE};
 use spu::SPU;
+pub use spu::{ChannelFormat, ChannelInfo, RepeatMode};
 use timers::Timers;
 
+use crate::builder::{BootMode, NDSConfig};
+use crate::debugger::{StopReason, Watchpoint};
+use crate::nds::CPU;
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
+
 pub struct HW {
     // Memory
     pub cp15: CP15,
     bios7: Vec<u8>,
     bios9: Vec<u8>,
+    hle_bios: bool,
     cartridge: Cartridge,
     itcm: Vec<u8>,
     dtcm: Vec<u8>,
@@ -61,8 +79,19 @@ pub struct HW {
     // Math
     div: Div,
     sqrt: Sqrt,
+    // HLE BIOS
+    intr_waiting: [bool; 2],
     // Misc
     scheduler: Scheduler,
+    // Debugging, not part of save states
+    watchpoints: Vec<Watchpoint>,
+    watchpoint_hit: Option<StopReason>,
+    trace_dmas: bool,
+    dma_trace: VecDeque<DMATransfer>,
+    new_dmas: Vec<DMATransfer>,
+    trace_ipc: bool,
+    ipc_trace: VecDeque<IPCMessage>,
+    new_ipc_messages: Vec<IPCMessage>,
 }
 
 impl HW {
@@ -72,21 +101,31 @@ impl HW {
     const IWRAM_SIZE: usize = 0x1_0000;
     const SHARED_WRAM_SIZE: usize = 0x8000;
 
+    // The movie in the config is ignored, NDS handles playback
     pub fn new(
         bios7: Vec<u8>,
         bios9: Vec<u8>,
         firmware: Vec<u8>,
         rom: Vec<u8>,
-        save_file: PathBuf,
-        direct_boot: bool,
+        config: NDSConfig,
+        save_type: SaveType,
     ) -> Self {
+        let hle_bios = config.hle_bios;
+        let direct_boot = config.boot_mode == BootMode::Direct;
+        let (bios7, bios9) = if hle_bios {
+            (HW::hle_bios7(), HW::hle_bios9())
+        } else {
+            (bios7, bios9)
+        };
         let mut scheduler = Scheduler::new();
+        let cartridge = Cartridge::new(rom, &bios7, config.save, save_type, direct_boot);
         let hw = HW {
             // Memory
             cp15: CP15::new(),
             bios7,
             bios9,
-            cartridge: Cartridge::new(rom, save_file),
+            hle_bios,
+            cartridge,
             itcm: vec![0; HW::ITCM_SIZE],
             dtcm: vec![0; HW::DTCM_SIZE],
             main_mem: vec![0; HW::MAIN_MEM_SIZE],
@@ -94,7 +133,7 @@ impl HW {
             shared_wram: vec![0; HW::SHARED_WRAM_SIZE],
             // Devices
             gpu: GPU::new(&mut scheduler),
-            spu: SPU::new(&mut scheduler),
+            spu: SPU::new(&mut scheduler, config.audio),
             keypad: Keypad::new(),
             interrupts: [InterruptController::new(), InterruptController::new()],
             in_dma: false,
@@ -102,7 +141,7 @@ impl HW {
             dma_fill: [0; 4],
             timers: [Timers::new(false), Timers::new(true)],
             ipc: IPC::new(),
-            spi: SPI::new(firmware),
+            spi: SPI::new(firmware, config.user_settings.as_ref()),
             // Registesr
             wramcnt: WRAMCNT::new(3),
             powcnt2: POWCNT2::new(),
@@ -113,8 +152,19 @@ impl HW {
             // Math
             div: Div::new(),
             sqrt: Sqrt::new(),
+            // HLE BIOS
+            intr_waiting: [false; 2],
             // Misc
             scheduler,
+            // Debugging
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            trace_dmas: false,
+            dma_trace: VecDeque::new(),
+            new_dmas: Vec::new(),
+            trace_ipc: false,
+            ipc_trace: VecDeque::new(),
+            new_ipc_messages: Vec::new(),
         };
         if direct_boot {
             hw.init_mem()
@@ -152,6 +202,119 @@ impl HW {
         self.cartridge.save_backup();
     }
 
+    pub fn backup_mem(&self) -> &[u8] {
+        self.cartridge.backup_mem()
+    }
+
+    pub fn firmware_user_settings(&self) -> &[u8] {
+        self.spi.firmware_user_settings()
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn watchpoints_mut(&mut self) -> &mut Vec<Watchpoint> {
+        &mut self.watchpoints
+    }
+
+    pub fn take_watchpoint_hit(&mut self) -> Option<StopReason> {
+        self.watchpoint_hit.take()
+    }
+
+    // Only the first access of an instruction is reported
+    fn check_watchpoints(&mut self, cpu: CPU, addr: u32, len: usize, write: bool) {
+        if self.watchpoint_hit.is_none()
+            && self
+                .watchpoints
+                .iter()
+                .any(|watchpoint| watchpoint.matches(cpu, addr, len as u32, write))
+        {
+            self.watchpoint_hit = Some(StopReason::Watchpoint { cpu, addr, write });
+        }
+    }
+
+    pub fn hle_bios(&self) -> bool {
+        self.hle_bios
+    }
+
+    pub fn game_code(&self) -> [u8; 4] {
+        self.cartridge.header().game_code
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        // Memory
+        state.write(&self.cp15);
+        self.cartridge.save_state(state);
+        state.write(&self.itcm);
+        state.write(&self.dtcm);
+        state.write(&self.main_mem);
+        state.write(&self.iwram);
+        state.write(&self.shared_wram);
+        // Devices
+        state.write(&self.gpu);
+        self.spu.save_state(state);
+        state.write(&self.keypad);
+        state.write(&self.interrupts);
+        state.write(&self.in_dma);
+        state.write(&self.dmas);
+        state.write(&self.dma_fill);
+        state.write(&self.timers);
+        state.write(&self.ipc);
+        state.write(&self.spi);
+        // Registers
+        state.write(&self.wramcnt);
+        state.write(&self.powcnt2);
+        state.write(&self.haltcnt);
+        state.write(&self.postflg7);
+        state.write(&self.postflg9);
+        state.write(&self.exmem);
+        // Math
+        state.write(&self.div);
+        state.write(&self.sqrt);
+        // HLE BIOS
+        state.write(&self.intr_waiting);
+        // Misc
+        state.write(&self.scheduler);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        // Memory
+        self.cp15 = state.read()?;
+        self.cartridge.load_state(state)?;
+        self.itcm = state.read()?;
+        self.dtcm = state.read()?;
+        self.main_mem = state.read()?;
+        self.iwram = state.read()?;
+        self.shared_wram = state.read()?;
+        // Devices
+        self.gpu = state.read()?;
+        self.spu.load_state(state)?;
+        self.keypad = state.read()?;
+        self.interrupts = state.read()?;
+        self.in_dma = state.read()?;
+        self.dmas = state.read()?;
+        self.dma_fill = state.read()?;
+        self.timers = state.read()?;
+        self.ipc = state.read()?;
+        self.spi = state.read()?;
+        // Registers
+        self.wramcnt = state.read()?;
+        self.powcnt2 = state.read()?;
+        self.haltcnt = state.read()?;
+        self.postflg7 = state.read()?;
+        self.postflg9 = state.read()?;
+        self.exmem = state.read()?;
+        // Math
+        self.div = state.read()?;
+        self.sqrt = state.read()?;
+        // HLE BIOS
+        self.intr_waiting = state.read()?;
+        // Misc
+        self.scheduler = state.read()?;
+        Ok(())
+    }
+
     pub fn press_key(&mut self, key: Key) {
         self.keypad.press_key(key);
     }
@@ -230,6 +393,34 @@ impl HW {
         }
     }
 
+    pub fn layer_toggles(&self, engine: Engine) -> LayerToggles {
+        match engine {
+            Engine::A => self.gpu.engine_a.layer_toggles(),
+            Engine::B => self.gpu.engine_b.layer_toggles(),
+        }
+    }
+
+    pub fn set_layer_toggles(&mut self, engine: Engine, layers: LayerToggles) {
+        match engine {
+            Engine::A => self.gpu.engine_a.set_layer_toggles(layers),
+            Engine::B => self.gpu.engine_b.set_layer_toggles(layers),
+        }
+    }
+
+    pub fn objs(&self, engine: Engine) -> Vec<OBJInfo> {
+        match engine {
+            Engine::A => self.gpu.engine_a.objs(),
+            Engine::B => self.gpu.engine_b.objs(),
+        }
+    }
+
+    pub fn render_obj(&self, engine: Engine, obj: &OBJInfo) -> (Vec<u16>, usize, usize) {
+        match engine {
+            Engine::A => self.gpu.engine_a.render_obj(&self.gpu.vram, obj),
+            Engine::B => self.gpu.engine_b.render_obj(&self.gpu.vram, obj),
+        }
+    }
+
     pub fn render_tiles(
         &self,
         engine: Engine,
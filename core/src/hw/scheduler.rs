@@ -1,7 +1,9 @@
 use std::cmp::{Eq, PartialEq, Reverse};
+use std::collections::VecDeque;
 use std::hash::Hash;
 
 use priority_queue::PriorityQueue;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{spu, HW};
 
@@ -22,6 +24,7 @@ impl HW {
         }
         let (wrapper, Reverse(cycle)) = self.scheduler.event_queue.pop().unwrap();
         self.scheduler.cycle = cycle;
+        self.scheduler.record_event(wrapper.event, cycle);
         (wrapper.handler)(self, wrapper.event);
     }
 
@@ -37,19 +40,45 @@ impl HW {
     fn dummy_handler(&mut self, _event: Event) {
         unreachable!()
     }
+
+    pub fn scheduler_cycle(&self) -> usize {
+        self.scheduler.cycle
+    }
+
+    pub fn scheduled_events(&self) -> impl Iterator<Item = ScheduledEvent> + '_ {
+        self.scheduler.scheduled_events()
+    }
+
+    pub fn fired_events(&self) -> &VecDeque<FiredEvent> {
+        &self.scheduler.history
+    }
+
+    pub fn set_record_events(&mut self, record: bool) {
+        self.scheduler.record_history = record;
+        if !record {
+            self.scheduler.history.clear();
+        }
+    }
 }
 
 pub struct Scheduler {
     pub cycle: usize,
     event_queue: PriorityQueue<EventWrapper, Reverse<usize>>,
+    // Debugging
+    record_history: bool,
+    history: VecDeque<FiredEvent>,
 }
 
 impl Scheduler {
+    const HISTORY_LEN: usize = 256;
+
     pub fn new() -> Scheduler {
         let queue = PriorityQueue::new();
         Scheduler {
             cycle: 0,
             event_queue: queue,
+            record_history: false,
+            history: VecDeque::new(),
         }
     }
 
@@ -57,12 +86,40 @@ impl Scheduler {
         // There should always be at least one event in the queue
         let (_event_type, Reverse(cycle)) = self.event_queue.peek().unwrap();
         if self.cycle >= *cycle {
-            Some(self.event_queue.pop().unwrap().0)
+            let (wrapper, Reverse(cycle)) = self.event_queue.pop().unwrap();
+            self.record_event(wrapper.event, cycle);
+            Some(wrapper)
         } else {
             None
         }
     }
 
+    fn record_event(&mut self, event: Event, cycle: usize) {
+        if !self.record_history {
+            return;
+        }
+        if self.history.len() == Scheduler::HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(FiredEvent {
+            event,
+            cycle,
+            fired_cycle: self.cycle,
+        });
+    }
+
+    // Pending events in no particular order
+    pub fn scheduled_events(&self) -> impl Iterator<Item = ScheduledEvent> + '_ {
+        let cur_cycle = self.cycle;
+        self.event_queue
+            .iter()
+            .map(move |(wrapper, Reverse(cycle))| ScheduledEvent {
+                event: wrapper.event,
+                cycle: *cycle,
+                relative_cycle: *cycle as isize - cur_cycle as isize,
+            })
+    }
+
     pub fn schedule(&mut self, event: Event, handler: EventHandler, delay: usize) {
         let wrapper = EventWrapper::new(event, handler);
         self.event_queue.push(wrapper, Reverse(self.cycle + delay));
@@ -78,7 +135,53 @@ impl Scheduler {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+// Handlers can't be serialized, so only the events are saved and their handlers are looked up on load
+impl Serialize for Scheduler {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut events = self
+            .event_queue
+            .iter()
+            .map(|(wrapper, Reverse(cycle))| (wrapper.event, *cycle))
+            .collect::<Vec<_>>();
+        events.sort_by_key(|(_event, cycle)| *cycle);
+        (self.cycle, events).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scheduler {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (cycle, events) = <(usize, Vec<(Event, usize)>)>::deserialize(deserializer)?;
+        let mut scheduler = Scheduler {
+            cycle,
+            event_queue: PriorityQueue::new(),
+            record_history: false,
+            history: VecDeque::new(),
+        };
+        for (event, cycle) in events {
+            let wrapper = EventWrapper::new(event, event.handler());
+            scheduler.event_queue.push(wrapper, Reverse(cycle));
+        }
+        Ok(scheduler)
+    }
+}
+
+// Cycles are in ARM7 cycles, relative_cycle is negative if the event is overdue
+#[derive(Clone, Copy, Debug)]
+pub struct ScheduledEvent {
+    pub event: Event,
+    pub cycle: usize,
+    pub relative_cycle: isize,
+}
+
+// fired_cycle can be later than cycle since events are handled in between instructions
+#[derive(Clone, Copy, Debug)]
+pub struct FiredEvent {
+    pub event: Event,
+    pub cycle: usize,
+    pub fired_cycle: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Event {
     DMA(bool, usize),
     StartNextLine,
@@ -93,6 +196,24 @@ pub enum Event {
     ResetAudioChannel(spu::ChannelSpec),
 }
 
+impl Event {
+    fn handler(&self) -> EventHandler {
+        match self {
+            Event::DMA(_, _) => HW::on_dma,
+            Event::StartNextLine => HW::start_next_line,
+            Event::HBlank => HW::on_hblank,
+            Event::VBlank => HW::on_vblank,
+            Event::CheckGeometryCommandFIFO => HW::check_geometry_command_fifo_handler,
+            Event::TimerOverflow(_, _) => HW::on_timer_overflow,
+            Event::ROMWordTransfered => HW::on_rom_word_transfered,
+            Event::ROMBlockEnded(_) => HW::on_rom_block_ended,
+            Event::GenerateAudioSample => HW::generate_audio_sample,
+            Event::StepAudioChannel(_) => HW::step_audio_channel,
+            Event::ResetAudioChannel(_) => HW::reset_audio_channel,
+        }
+    }
+}
+
 struct EventWrapper {
     event: Event,
     handler: EventHandler,
@@ -0,0 +1,234 @@
+use super::HW;
+
+pub const SIZE: usize = 0x4_0000;
+pub const USER_SETTINGS_ADDRS: [u32; 2] = [0x3FE00, 0x3FF00];
+const ACCESS_POINT_ADDRS: [u32; 3] = [0x3FA00, 0x3FB00, 0x3FC00];
+const WIFI_CONFIG_ADDR: u32 = 0x2C;
+const WIFI_CONFIG_LEN: u16 = 0x138;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    Japanese = 0,
+    English = 1,
+    French = 2,
+    German = 3,
+    Italian = 4,
+    Spanish = 5,
+    Chinese = 6,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserSettings {
+    pub nickname: String, // Max 10 characters
+    pub message: String,  // Max 26 characters
+    pub favorite_color: u8,
+    pub birthday_month: u8,
+    pub birthday_day: u8,
+    pub language: Language,
+}
+
+impl UserSettings {
+    const NICKNAME_LEN: usize = 10;
+    const MESSAGE_LEN: usize = 26;
+
+    const VERSION: u16 = 5;
+
+    // Writes the settings into one of the two user settings copies, CRC is updated separately
+    pub(super) fn write(&self, firmware: &mut [u8], addr: u32) {
+        let addr = addr as usize;
+        firmware[addr + 0x02] = self.favorite_color & 0xF;
+        firmware[addr + 0x03] = self.birthday_month;
+        firmware[addr + 0x04] = self.birthday_day;
+        let nickname_len = UserSettings::write_string(
+            &mut firmware[addr + 0x06..addr + 0x1A],
+            &self.nickname,
+            UserSettings::NICKNAME_LEN,
+        );
+        HW::write_mem(firmware, addr as u32 + 0x1A, nickname_len);
+        let message_len = UserSettings::write_string(
+            &mut firmware[addr + 0x1C..addr + 0x50],
+            &self.message,
+            UserSettings::MESSAGE_LEN,
+        );
+        HW::write_mem(firmware, addr as u32 + 0x50, message_len);
+        firmware[addr + 0x64] = firmware[addr + 0x64] & !0x7 | self.language as u8;
+    }
+
+    fn write_string(dest: &mut [u8], string: &str, max_len: usize) -> u16 {
+        for byte in dest.iter_mut() {
+            *byte = 0;
+        }
+        let mut len = 0;
+        for (i, c) in string.encode_utf16().take(max_len).enumerate() {
+            dest[i * 2..i * 2 + 2].copy_from_slice(&c.to_le_bytes());
+            len += 1;
+        }
+        len
+    }
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            nickname: "NDS".to_string(),
+            message: String::new(),
+            favorite_color: 0,
+            birthday_month: 1,
+            birthday_day: 1,
+            language: Language::English,
+        }
+    }
+}
+
+// Builds a firmware image without any boot code, enough for direct boot
+pub fn generate_firmware(user_settings: &UserSettings) -> Vec<u8> {
+    // Unused areas read as erased flash
+    let mut firmware = vec![0xFF; SIZE];
+    write_header(&mut firmware);
+    write_wifi_config(&mut firmware);
+    for addr in ACCESS_POINT_ADDRS.iter().copied() {
+        let range = addr as usize..addr as usize + 0x100;
+        for byte in firmware[range.clone()].iter_mut() {
+            *byte = 0;
+        }
+        firmware[addr as usize + 0xE7] = 0xFF; // Not Configured
+        let crc = crc16(0x0000, &firmware[range.start..range.start + 0xFE]);
+        HW::write_mem(&mut firmware, addr + 0xFE, crc);
+    }
+    for addr in USER_SETTINGS_ADDRS.iter().copied() {
+        let start = addr as usize;
+        for byte in firmware[start..start + 0x74].iter_mut() {
+            *byte = 0;
+        }
+        HW::write_mem(&mut firmware, addr, UserSettings::VERSION);
+        // Mark every setting as configured so the firmware menu doesn't ask for them again
+        firmware[start + 0x65] = 0xFC;
+        HW::write_mem(&mut firmware, addr + 0x6C, 0xFFFF_FFFFu32);
+        user_settings.write(&mut firmware, addr);
+        let crc = crc16(0xFFFF, &firmware[start..start + 0x70]);
+        HW::write_mem(&mut firmware, addr + 0x72, crc);
+    }
+    firmware
+}
+
+fn write_header(firmware: &mut [u8]) {
+    // No boot code, so the part addresses and CRCs are left empty
+    for byte in firmware[0x00..0x08].iter_mut() {
+        *byte = 0;
+    }
+    firmware[0x08..0x0C].copy_from_slice(b"MACP");
+    for byte in firmware[0x0C..0x1D].iter_mut() {
+        *byte = 0;
+    }
+    firmware[0x1D] = 0x20; // Console Type: Nintendo DS Lite
+    HW::write_mem(firmware, 0x20, (USER_SETTINGS_ADDRS[0] / 8) as u16);
+    HW::write_mem(firmware, 0x22, 0x0B51u16);
+    HW::write_mem(firmware, 0x24, 0x0DB3u16);
+}
+
+fn write_wifi_config(firmware: &mut [u8]) {
+    let start = WIFI_CONFIG_ADDR as usize;
+    let end = start + WIFI_CONFIG_LEN as usize;
+    for byte in firmware[start..end].iter_mut() {
+        *byte = 0;
+    }
+    HW::write_mem(firmware, WIFI_CONFIG_ADDR, WIFI_CONFIG_LEN);
+    firmware[0x2F] = 5; // Wifi Version
+    for byte in firmware[0x30..0x36].iter_mut() {
+        *byte = 0xFF;
+    }
+    firmware[0x36..0x3C].copy_from_slice(&[0x00, 0x09, 0xBF, 0x12, 0x34, 0x56]); // MAC Address
+    HW::write_mem(firmware, 0x3C, 0x3FFEu16); // Enabled Channels 1 - 13
+    HW::write_mem(firmware, 0x3E, 0xFFFFu16);
+    firmware[0x40] = 0x02; // RF Chip Type
+    firmware[0x41] = 0x18; // Bits per RF Entry
+    firmware[0x42] = 0x0C; // Number of RF Entries
+    firmware[0x43] = 0x01;
+    // Initial values for the Wifi config registers, typical of retail units
+    let config_regs: [u16; 16] = [
+        0x0002, 0x0017, 0x0026, 0x1818, 0x0048, 0x4840, 0x0058, 0x0042, 0x0146, 0x8064, 0xE6E6,
+        0x2443, 0x000E, 0x0001, 0x0001, 0x0402,
+    ];
+    for (i, value) in config_regs.iter().enumerate() {
+        HW::write_mem(firmware, 0x44 + i as u32 * 2, *value);
+    }
+    let crc = crc16(0x0000, &firmware[start..end]);
+    HW::write_mem(firmware, 0x2A, crc);
+}
+
+pub fn crc16(initial: u16, data: &[u8]) -> u16 {
+    let mut crc = initial as u32;
+    let vals = [
+        0xC0C1, 0xC181, 0xC301, 0xC601, 0xCC01, 0xD801, 0xF001, 0xA001,
+    ];
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for (i, val) in vals.iter().enumerate() {
+            let new_crc = crc >> 1;
+            crc = if crc & 0x1 != 0 {
+                // Carry Occurred
+                new_crc ^ (val << (7 - i))
+            } else {
+                new_crc
+            };
+        }
+    }
+    crc as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u16(firmware: &[u8], addr: usize) -> u16 {
+        u16::from_le_bytes([firmware[addr], firmware[addr + 1]])
+    }
+
+    #[test]
+    fn crc16_check_values() {
+        // CRC-16/ARC with an initial value of 0 and CRC-16/MODBUS with 0xFFFF
+        let cases: [(u16, &[u8], u16); 6] = [
+            (0x0000, b"", 0x0000),
+            (0xFFFF, b"", 0xFFFF),
+            (0x0000, b"123456789", 0xBB3D),
+            (0xFFFF, b"123456789", 0x4B37),
+            (0x0000, &[0x00], 0x0000),
+            (0xFFFF, &[0x01, 0x02], 0xE181),
+        ];
+        for (initial, data, expected) in cases.iter() {
+            assert_eq!(
+                crc16(*initial, data),
+                *expected,
+                "{:04X} {:02X?}",
+                initial,
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn generated_crcs() {
+        let firmware = generate_firmware(&UserSettings::default());
+        // (CRC address, CRC initial value, covered range)
+        let wifi_start = WIFI_CONFIG_ADDR as usize;
+        let mut cases = vec![(
+            0x2A,
+            0x0000,
+            wifi_start..wifi_start + WIFI_CONFIG_LEN as usize,
+        )];
+        for addr in ACCESS_POINT_ADDRS.iter().map(|addr| *addr as usize) {
+            cases.push((addr + 0xFE, 0x0000, addr..addr + 0xFE));
+        }
+        for addr in USER_SETTINGS_ADDRS.iter().map(|addr| *addr as usize) {
+            cases.push((addr + 0x72, 0xFFFF, addr..addr + 0x70));
+        }
+        for (crc_addr, initial, range) in cases.into_iter() {
+            assert_eq!(
+                read_u16(&firmware, crc_addr),
+                crc16(initial, &firmware[range]),
+                "CRC at 0x{:05X}",
+                crc_addr
+            );
+        }
+    }
+}
@@ -1,9 +1,14 @@
+pub mod firmware;
 mod tsc;
 
+use serde::{Deserialize, Serialize};
+
 use super::{mem::IORegister, Scheduler, GPU, HW};
 use crate::hw::cartridge::{Backup, Flash};
+use firmware::UserSettings;
 use tsc::TSC;
 
+#[derive(Serialize, Deserialize)]
 pub struct SPI {
     cnt: CNT,
     firmware: Flash,
@@ -11,10 +16,10 @@ pub struct SPI {
 }
 
 impl SPI {
-    pub fn new(firmware: Vec<u8>) -> Self {
+    pub fn new(firmware: Vec<u8>, user_settings: Option<&UserSettings>) -> Self {
         SPI {
             cnt: CNT::new(),
-            firmware: Flash::new_firmware(SPI::init_firmware(firmware)),
+            firmware: Flash::new_firmware(SPI::init_firmware(firmware, user_settings)),
             tsc: TSC::new(),
         }
     }
@@ -59,63 +64,55 @@ impl SPI {
         }
     }
 
+    // Both copies of the user settings
+    pub fn firmware_user_settings(&self) -> &[u8] {
+        &self.firmware.mem()[firmware::USER_SETTINGS_ADDRS[0] as usize..firmware::SIZE]
+    }
+
     pub fn press_screen(&mut self, x: usize, y: usize) {
         self.tsc.press_screen(x, y)
     }
     pub fn release_screen(&mut self) {
         self.tsc.release_screen()
     }
-    pub fn init_firmware(firmware: Vec<u8>) -> Vec<u8> {
+    pub fn init_firmware(firmware: Vec<u8>, user_settings: Option<&UserSettings>) -> Vec<u8> {
         let mut firmware = firmware;
-        let user_settings_addr = 0x3FE00;
-
-        // Set Touch Screen Calibration
-        let max_x = GPU::WIDTH - 1;
-        let max_y = GPU::HEIGHT - 1;
-        // Top Left Corner
-        HW::write_mem(&mut firmware, user_settings_addr + 0x58, 0u16);
-        HW::write_mem(&mut firmware, user_settings_addr + 0x5A, 0u16);
-        firmware[user_settings_addr as usize + 0x5C] = 0;
-        firmware[user_settings_addr as usize + 0x5D] = 0;
-        // Bottom Right Corner
-        HW::write_mem(
-            &mut firmware,
-            user_settings_addr + 0x5E,
-            (max_x as u16) << 4,
-        );
-        HW::write_mem(
-            &mut firmware,
-            user_settings_addr + 0x60,
-            (max_y as u16) << 4,
-        );
-        firmware[user_settings_addr as usize + 0x62] = max_x as u8;
-        firmware[user_settings_addr as usize + 0x63] = max_y as u8;
-        let crc16 = {
-            let mut crc = 0xFFFF;
-            let vals = [
-                0xC0C1, 0xC181, 0xC301, 0xC601, 0xCC01, 0xD801, 0xF001, 0xA001,
-            ];
-            for byte in
-                firmware[user_settings_addr as usize..user_settings_addr as usize + 0x70].iter()
-            {
-                crc ^= *byte as u32;
-                for (i, val) in vals.iter().enumerate() {
-                    let new_crc = crc >> 1;
-                    crc = if crc & 0x1 != 0 {
-                        // Carry Occurred
-                        new_crc ^ (val << (7 - i))
-                    } else {
-                        new_crc
-                    };
-                }
+        for user_settings_addr in firmware::USER_SETTINGS_ADDRS.iter().copied() {
+            // Set Touch Screen Calibration
+            let max_x = GPU::WIDTH - 1;
+            let max_y = GPU::HEIGHT - 1;
+            // Top Left Corner
+            HW::write_mem(&mut firmware, user_settings_addr + 0x58, 0u16);
+            HW::write_mem(&mut firmware, user_settings_addr + 0x5A, 0u16);
+            firmware[user_settings_addr as usize + 0x5C] = 0;
+            firmware[user_settings_addr as usize + 0x5D] = 0;
+            // Bottom Right Corner
+            HW::write_mem(
+                &mut firmware,
+                user_settings_addr + 0x5E,
+                (max_x as u16) << 4,
+            );
+            HW::write_mem(
+                &mut firmware,
+                user_settings_addr + 0x60,
+                (max_y as u16) << 4,
+            );
+            firmware[user_settings_addr as usize + 0x62] = max_x as u8;
+            firmware[user_settings_addr as usize + 0x63] = max_y as u8;
+            if let Some(user_settings) = user_settings {
+                user_settings.write(&mut firmware, user_settings_addr);
             }
-            crc as u16
-        };
-        HW::write_mem(&mut firmware, user_settings_addr + 0x72, crc16);
+            let crc16 = firmware::crc16(
+                0xFFFF,
+                &firmware[user_settings_addr as usize..user_settings_addr as usize + 0x70],
+            );
+            HW::write_mem(&mut firmware, user_settings_addr + 0x72, crc16);
+        }
         firmware
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct CNT {
     baudrate: u8,
     busy: bool,
@@ -175,7 +172,7 @@ impl IORegister for CNT {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum Device {
     Powerman = 0,
     Firmware = 1,
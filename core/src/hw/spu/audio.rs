@@ -1,89 +1,59 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use ringbuf::RingBuffer;
+use std::sync::{Arc, Mutex};
 
-pub struct Audio {
-    config: cpal::StreamConfig,
-    _stream: cpal::Stream,
-    prod: ringbuf::Producer<[f32; 2]>,
+// Samples are always generated at this rate, sinks resample them for their device
+pub const AUDIO_SAMPLE_RATE: usize = 32768;
+
+pub trait AudioSink {
+    fn push_sample(&mut self, left_sample: f32, right_sample: f32);
 }
 
-impl Audio {
-    const BUFFER_LEN: usize = 2048;
+// Discards all samples, for running without a sound device
+pub struct NullSink {}
 
+impl NullSink {
     pub fn new() -> Self {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .expect("No audio output device available!");
-        let config = device
-            .default_output_config()
-            .expect("No audio output config available!");
+        NullSink {}
+    }
+}
 
-        match config.sample_format() {
-            cpal::SampleFormat::F32 => Audio::init::<f32>(device, config.into()),
-            cpal::SampleFormat::I16 => Audio::init::<i16>(device, config.into()),
-            cpal::SampleFormat::U16 => Audio::init::<u16>(device, config.into()),
-        }
+impl Default for NullSink {
+    fn default() -> Self {
+        NullSink::new()
     }
+}
 
-    fn init<T: cpal::Sample>(device: cpal::Device, config: cpal::StreamConfig) -> Self {
-        let buffer = RingBuffer::<[f32; 2]>::new(Audio::BUFFER_LEN);
-        let (prod, mut cons) = buffer.split();
+impl AudioSink for NullSink {
+    fn push_sample(&mut self, _left_sample: f32, _right_sample: f32) {}
+}
 
-        let output_config = OutputConfig::from(config.channels);
-        let stream = device
-            .build_output_stream(
-                &config,
-                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                    for frame in data.chunks_mut(output_config as usize) {
-                        let samples = cons.pop().unwrap_or_else(|| [0.0, 0.0]);
-                        match output_config {
-                            OutputConfig::Mono => {
-                                let sample = samples.iter().sum::<f32>() / 2.0;
-                                frame[0] = cpal::Sample::from::<f32>(&sample);
-                            }
-                            OutputConfig::Stereo => {
-                                frame[0] = cpal::Sample::from::<f32>(&(samples[0]));
-                                frame[1] = cpal::Sample::from::<f32>(&(samples[1]));
-                            }
-                        }
-                    }
-                },
-                |err| error!("Audio Stream Error: {}", err),
-            )
-            .unwrap();
-        stream.play().unwrap();
+// Collects all samples in memory, the buffer can be shared before the sink is handed to the NDS
+pub struct BufferSink {
+    samples: Arc<Mutex<Vec<[f32; 2]>>>,
+}
 
-        Audio {
-            config,
-            _stream: stream,
-            prod,
+impl BufferSink {
+    pub fn new() -> Self {
+        BufferSink {
+            samples: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
-    pub fn push_sample(&mut self, left_sample: f32, right_sample: f32) {
-        while self.prod.is_full() {} // TODO: Block thread instead of using CPU
-        self.prod.push([left_sample, right_sample]).unwrap();
-    }
-
-    pub fn sample_rate(&self) -> usize {
-        self.config.sample_rate.0 as usize
+    pub fn samples(&self) -> Arc<Mutex<Vec<[f32; 2]>>> {
+        Arc::clone(&self.samples)
     }
 }
 
-#[derive(Clone, Copy)]
-enum OutputConfig {
-    Mono = 1,
-    Stereo = 2,
+impl AudioSink for BufferSink {
+    fn push_sample(&mut self, left_sample: f32, right_sample: f32) {
+        self.samples
+            .lock()
+            .unwrap()
+            .push([left_sample, right_sample]);
+    }
 }
 
-impl From<u16> for OutputConfig {
-    fn from(value: u16) -> Self {
-        use OutputConfig::*;
-        match value {
-            1 => Mono,
-            2 => Stereo,
-            _ => panic!("Only Mono and Stereo audio devices supported!"),
-        }
+impl Default for BufferSink {
+    fn default() -> Self {
+        BufferSink::new()
     }
 }
@@ -0,0 +1,163 @@
+use super::{
+    registers::{Format, RepeatMode},
+    Channel, ChannelType, HW, SPU,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelFormat {
+    PCM8,
+    PCM16,
+    ADPCM,
+    PSG,
+    Noise,
+    Invalid, // PSG format on a channel that can't generate it
+}
+
+// Lengths and loop starts are in words, volume and pan range from 0 to 127 with 64 as center pan
+#[derive(Clone, Copy)]
+pub struct ChannelInfo {
+    pub format: ChannelFormat,
+    pub repeat_mode: RepeatMode,
+    pub src_addr: u32,
+    pub loop_start: u16,
+    pub len: u32,
+    pub timer_val: u16,
+    pub frequency: f64,
+    pub volume: u8,
+    pub volume_shift: usize,
+    pub pan: u8,
+    pub wave_duty: u8,
+    pub hold: bool,
+    pub busy: bool,
+    pub muted: bool,
+    pub soloed: bool,
+}
+
+impl<T: ChannelType> Channel<T> {
+    fn info(&self) -> ChannelInfo {
+        let cnt = &self.cnt;
+        ChannelInfo {
+            format: match cnt.format {
+                Format::PCM8 => ChannelFormat::PCM8,
+                Format::PCM16 => ChannelFormat::PCM16,
+                Format::ADPCM => ChannelFormat::ADPCM,
+                Format::Special if T::supports_psg() => ChannelFormat::PSG,
+                Format::Special if T::supports_noise() => ChannelFormat::Noise,
+                Format::Special => ChannelFormat::Invalid,
+            },
+            repeat_mode: cnt.repeat_mode,
+            src_addr: self.src_addr,
+            loop_start: self.loop_start,
+            len: self.len,
+            timer_val: self.timer_val,
+            frequency: Self::frequency(self.timer_val),
+            volume: cnt.volume_mul,
+            volume_shift: cnt.volume_shift(),
+            pan: cnt.panning,
+            wave_duty: cnt.wave_duty,
+            hold: cnt.hold,
+            busy: cnt.busy,
+            muted: false,
+            soloed: false,
+        }
+    }
+
+    // The timer ticks at half the bus clock and restarts at timer_val, so 0 is the lowest rate
+    fn frequency(timer_val: u16) -> f64 {
+        crate::nds::NDS::CLOCK_RATE as f64 / 2.0 / (0x1_0000 - timer_val as u32) as f64
+    }
+
+    fn output(&self) -> (i32, i32) {
+        let mut sample = (0, 0);
+        self.generate_sample(&mut sample);
+        sample
+    }
+}
+
+impl SPU {
+    pub const NUM_CHANNELS: usize = 16;
+
+    pub(super) fn channel_audible(&self, num: usize) -> bool {
+        !self.muted[num] && (!self.soloed.contains(&true) || self.soloed[num])
+    }
+
+    fn channel_output(&self, num: usize) -> (i32, i32) {
+        match num {
+            0x0..=0x7 => self.base_channels[num].output(),
+            0x8..=0xD => self.psg_channels[num - 0x8].output(),
+            0xE..=0xF => self.noise_channels[num - 0xE].output(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub(super) fn update_peaks(&mut self) {
+        for num in 0..SPU::NUM_CHANNELS {
+            let (left, right) = self.channel_output(num);
+            let peak = std::cmp::max(left.abs(), right.abs()) as u32;
+            self.peaks[num] = std::cmp::max(self.peaks[num], peak);
+        }
+    }
+}
+
+impl HW {
+    pub fn audio_channels(&self) -> Vec<ChannelInfo> {
+        let spu = &self.spu;
+        let base_channels = spu.base_channels.iter().map(Channel::info);
+        let psg_channels = spu.psg_channels.iter().map(Channel::info);
+        let noise_channels = spu.noise_channels.iter().map(Channel::info);
+        base_channels
+            .chain(psg_channels)
+            .chain(noise_channels)
+            .enumerate()
+            .map(|(num, info)| ChannelInfo {
+                muted: spu.muted[num],
+                soloed: spu.soloed[num],
+                ..info
+            })
+            .collect()
+    }
+
+    pub fn set_audio_channel_muted(&mut self, num: usize, muted: bool) {
+        self.spu.muted[num] = muted;
+    }
+
+    pub fn set_audio_channel_soloed(&mut self, num: usize, soloed: bool) {
+        self.spu.soloed[num] = soloed;
+    }
+
+    pub fn set_track_audio_peaks(&mut self, track: bool) {
+        self.spu.track_peaks = track;
+        if !track {
+            self.spu.peaks = [0; SPU::NUM_CHANNELS];
+        }
+    }
+
+    // Resets the peaks so every call returns the peaks since the previous one
+    pub fn take_audio_peaks(&mut self) -> [u32; SPU::NUM_CHANNELS] {
+        std::mem::replace(&mut self.spu.peaks, [0; SPU::NUM_CHANNELS])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::BaseChannel, Channel};
+
+    #[test]
+    fn frequency() {
+        let cases: &[(u16, f64)] = &[
+            (0x0000, 255.69),
+            (0x8000, 511.38),
+            (0xFE00, 32728.50),
+            (0xFFFF, 16756991.0),
+        ];
+        for &(timer_val, expected) in cases.iter() {
+            let frequency = Channel::<BaseChannel>::frequency(timer_val);
+            assert!(
+                (frequency - expected).abs() < 0.01,
+                "timer {:04X}: {} Hz",
+                timer_val,
+                frequency
+            );
+        }
+    }
+}
@@ -1,13 +1,19 @@
-mod audio;
+pub mod audio;
+mod debug;
 mod registers;
 
+use serde::{Deserialize, Serialize};
+
 use super::{
     mem::IORegister,
     scheduler::{Event, Scheduler},
     HW,
 };
 
-use audio::Audio;
+use crate::savestate::{LoadStateError, StateReader, StateWriter};
+use audio::{AudioSink, AUDIO_SAMPLE_RATE};
+pub use debug::{ChannelFormat, ChannelInfo};
+pub use registers::RepeatMode;
 use registers::*;
 
 pub struct SPU {
@@ -15,12 +21,16 @@ pub struct SPU {
     sound_bias: u16,
     captures: [Capture; 2],
     // Sound Generation
-    audio: Audio,
-    clocks_per_sample: usize,
+    audio: Box<dyn AudioSink>,
     // Channels
     pub base_channels: [Channel<BaseChannel>; 8],
     pub psg_channels: [Channel<PSGChannel>; 6],
     pub noise_channels: [Channel<NoiseChannel>; 2],
+    // Debugging
+    muted: [bool; SPU::NUM_CHANNELS],
+    soloed: [bool; SPU::NUM_CHANNELS],
+    track_peaks: bool,
+    peaks: [u32; SPU::NUM_CHANNELS],
 }
 
 macro_rules! create_channels {
@@ -34,6 +44,8 @@ macro_rules! create_channels {
 }
 
 impl SPU {
+    // Fixed so event timing doesn't depend on the sound device
+    const CLOCKS_PER_SAMPLE: usize = crate::nds::NDS::CLOCK_RATE / AUDIO_SAMPLE_RATE;
     pub const ADPCM_INDEX_TABLE: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
     pub const ADPCM_TABLE: [u16; 89] = [
         0x0007, 0x0008, 0x0009, 0x000A, 0x000B, 0x000C, 0x000D, 0x000E, 0x0010, 0x0011, 0x0013,
@@ -47,14 +59,11 @@ impl SPU {
         0x7FFF,
     ];
 
-    pub fn new(scheduler: &mut Scheduler) -> Self {
-        let audio = Audio::new();
-        // TODO: Sample at 32.768 kHz and resample to device sample rate
-        let clocks_per_sample = crate::nds::NDS::CLOCK_RATE / audio.sample_rate();
+    pub fn new(scheduler: &mut Scheduler, audio: Box<dyn AudioSink>) -> Self {
         scheduler.schedule(
             Event::GenerateAudioSample,
             HW::generate_audio_sample,
-            clocks_per_sample,
+            SPU::CLOCKS_PER_SAMPLE,
         );
         SPU {
             cnt: SoundControl::new(),
@@ -62,28 +71,63 @@ impl SPU {
             captures: [Capture::new(), Capture::new()],
             // Sound Generation
             audio,
-            clocks_per_sample,
             // Channels
             base_channels: create_channels!(BaseChannel, Base, 0, 1, 2, 3, 4, 5, 6, 7),
             psg_channels: create_channels!(PSGChannel, PSG, 0, 1, 2, 3, 4, 5),
             noise_channels: create_channels!(NoiseChannel, Noise, 0, 1),
+            // Debugging
+            muted: [false; SPU::NUM_CHANNELS],
+            soloed: [false; SPU::NUM_CHANNELS],
+            track_peaks: false,
+            peaks: [0; SPU::NUM_CHANNELS],
         }
     }
 
-    fn generate_mixer(&self) -> ((i32, i32), (i32, i32), (i32, i32)) {
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write(&self.cnt);
+        state.write(&self.sound_bias);
+        state.write(&self.captures);
+        state.write(&self.base_channels);
+        state.write(&self.psg_channels);
+        state.write(&self.noise_channels);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        self.cnt = state.read()?;
+        self.sound_bias = state.read()?;
+        self.captures = state.read()?;
+        self.base_channels = state.read()?;
+        self.psg_channels = state.read()?;
+        self.noise_channels = state.read()?;
+        Ok(())
+    }
+
+    // Muting and soloing channels is only applied to what's played, not to what's captured
+    fn generate_mixer(&self, apply_mutes: bool) -> ((i32, i32), (i32, i32), (i32, i32)) {
+        let audible = |num: usize| !apply_mutes || self.channel_audible(num);
         let mut mixer = (0, 0);
         for i in (0..1).chain(2..3).chain(4..self.base_channels.len()) {
-            self.base_channels[i].generate_sample(&mut mixer)
+            if audible(i) {
+                self.base_channels[i].generate_sample(&mut mixer)
+            }
         }
-        for channel in self.psg_channels.iter() {
-            channel.generate_sample(&mut mixer)
+        for (i, channel) in self.psg_channels.iter().enumerate() {
+            if audible(0x8 + i) {
+                channel.generate_sample(&mut mixer)
+            }
         }
-        for channel in self.noise_channels.iter() {
-            channel.generate_sample(&mut mixer)
+        for (i, channel) in self.noise_channels.iter().enumerate() {
+            if audible(0xE + i) {
+                channel.generate_sample(&mut mixer)
+            }
         }
         let (mut ch1, mut ch3) = ((0, 0), (0, 0));
-        self.base_channels[1].generate_sample(&mut ch1);
-        self.base_channels[3].generate_sample(&mut ch3);
+        if audible(1) {
+            self.base_channels[1].generate_sample(&mut ch1);
+        }
+        if audible(3) {
+            self.base_channels[3].generate_sample(&mut ch3);
+        }
         if self.cnt.output_1 {
             mixer.0 += ch1.0;
             mixer.1 += ch1.1
@@ -96,7 +140,10 @@ impl SPU {
     }
 
     pub fn generate_sample(&mut self) {
-        let (mixer, ch1, ch3) = self.generate_mixer();
+        if self.track_peaks {
+            self.update_peaks();
+        }
+        let (mixer, ch1, ch3) = self.generate_mixer(true);
         let left_sample = match self.cnt.left_output {
             ChannelOutput::Mixer => mixer.0,
             ChannelOutput::Ch1 => ch1.0,
@@ -114,11 +161,19 @@ impl SPU {
             ((right_sample * self.cnt.master_volume()) >> 7) as i16,
         );
         self.audio.push_sample(
-            cpal::Sample::from::<i16>(&final_sample.0),
-            cpal::Sample::from::<i16>(&final_sample.1),
+            SPU::sample_to_f32(final_sample.0),
+            SPU::sample_to_f32(final_sample.1),
         );
     }
 
+    fn sample_to_f32(sample: i16) -> f32 {
+        if sample < 0 {
+            sample as f32 / -(i16::MIN as f32)
+        } else {
+            sample as f32 / i16::MAX as f32
+        }
+    }
+
     pub fn capture_addr(&mut self, num: usize) -> Option<(u32, usize, bool)> {
         let capture_i = match num {
             1 => 0,
@@ -141,7 +196,7 @@ impl SPU {
             // TODO: Implement bugged behavior
             todo!()
         } else {
-            let (mixer, _, _) = self.generate_mixer();
+            let (mixer, _, _) = self.generate_mixer(false);
             let mixer_value = (if capture_i == 0 { mixer.0 } else { mixer.1 } >> 16) as u16;
             if std::mem::size_of::<T>() == 1 {
                 mixer_value >> 8
@@ -187,9 +242,10 @@ impl IORegister for SPU {
         match addr {
             0x400..=0x4FF => self.read_channels(addr),
             0x500..=0x503 => self.cnt.read(addr & 0x3),
-            0x504..=0x507 => HW::read_byte_from_value(&self.sound_bias, addr & 0x3),
+            0x504..=0x505 => HW::read_byte_from_value(&self.sound_bias, addr & 0x1),
+            0x506..=0x507 => 0,
             0x508..=0x509 => self.captures[addr & 0x1].cnt.read(),
-            0x510..=0x51F => self.captures[addr >> 3 & 0x1].read(addr & 0xF),
+            0x510..=0x51F => self.captures[addr >> 3 & 0x1].read(addr & 0x7),
             _ => {
                 warn!("Ignoring SPU Register Read at 0x04000{:03X}", addr);
                 0
@@ -213,16 +269,16 @@ impl IORegister for SPU {
 }
 
 impl HW {
-    fn generate_audio_sample(&mut self, _event: Event) {
+    pub(super) fn generate_audio_sample(&mut self, _event: Event) {
         self.scheduler.schedule(
             Event::GenerateAudioSample,
             HW::generate_audio_sample,
-            self.spu.clocks_per_sample,
+            SPU::CLOCKS_PER_SAMPLE,
         );
         self.spu.generate_sample();
     }
 
-    fn step_audio_channel(&mut self, event: Event) {
+    pub(super) fn step_audio_channel(&mut self, event: Event) {
         let channel_spec = match event {
             Event::StepAudioChannel(channel_spec) => channel_spec,
             _ => unreachable!(),
@@ -338,7 +394,7 @@ impl HW {
         }
     }
 
-    fn reset_audio_channel(&mut self, event: Event) {
+    pub(super) fn reset_audio_channel(&mut self, event: Event) {
         let channel_spec = match event {
             Event::ResetAudioChannel(channel_spec) => channel_spec,
             _ => unreachable!(),
@@ -351,6 +407,8 @@ impl HW {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Channel<T: ChannelType> {
     // Registers
     cnt: ChannelControl<T>,
@@ -617,6 +675,7 @@ impl<T: ChannelType> Channel<T> {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct Capture {
     // Registers
     cnt: CaptureControl,
@@ -688,7 +747,7 @@ impl Capture {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ChannelSpec {
     Base(usize),
     PSG(usize),
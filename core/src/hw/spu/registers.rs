@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 use super::{ChannelType, IORegister, Scheduler};
 
+#[derive(Serialize, Deserialize)]
 pub struct SoundControl {
     master_volume: u8,
     pub left_output: ChannelOutput,
@@ -64,7 +66,7 @@ impl SoundControl {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum ChannelOutput {
     Mixer = 0,
     Ch1 = 1,
@@ -85,11 +87,13 @@ impl From<u8> for ChannelOutput {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ChannelControl<T: ChannelType> {
-    volume_mul: u8,
+    pub(super) volume_mul: u8,
     volume_div: u8,
     pub hold: bool,
-    panning: u8,
+    pub(super) panning: u8,
     pub wave_duty: u8,
     pub repeat_mode: RepeatMode,
     pub format: Format,
@@ -168,7 +172,7 @@ impl<T: ChannelType> ChannelControl<T> {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum RepeatMode {
     Manual = 0,
     Loop = 1,
@@ -187,7 +191,7 @@ impl From<u8> for RepeatMode {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Format {
     PCM8 = 0,
     PCM16 = 1,
@@ -208,6 +212,7 @@ impl From<u8> for Format {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct CaptureControl {
     pub add: bool,
     pub use_channel: bool,
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use super::{
     interrupt_controller::InterruptRequest,
     mem::IORegister,
@@ -5,6 +7,7 @@ use super::{
     HW,
 };
 
+#[derive(Serialize, Deserialize)]
 pub struct Timers {
     timers: [Timer; Timers::NUM_TIMERS],
 }
@@ -39,7 +42,7 @@ impl std::ops::IndexMut<usize> for Timers {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Timer {
     is_nds9: bool,
     pub reload: u16,
@@ -188,7 +191,7 @@ impl Timer {
 }
 
 impl HW {
-    fn on_timer_overflow(&mut self, event: Event) {
+    pub(super) fn on_timer_overflow(&mut self, event: Event) {
         let (is_nds9, num) = match event {
             Event::TimerOverflow(is_nds9, num) => (is_nds9, num),
             _ => unreachable!(),
@@ -211,7 +214,7 @@ impl HW {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TMCNT {
     pub prescaler: u8,
     pub count_up: bool,
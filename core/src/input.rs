@@ -0,0 +1,279 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hw::{Key, HW};
+use crate::nds::{HEIGHT, NDS, WIDTH};
+
+pub(crate) const KEY_NAMES: [(Key, &str); 12] = [
+    (Key::A, "A"),
+    (Key::B, "B"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Select, "Select"),
+    (Key::Start, "Start"),
+    (Key::Right, "Right"),
+    (Key::Left, "Left"),
+    (Key::Up, "Up"),
+    (Key::Down, "Down"),
+    (Key::R, "R"),
+    (Key::L, "L"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Press(Key),
+    Release(Key),
+    Touch(usize, usize),
+    Untouch,
+}
+
+impl InputEvent {
+    pub fn apply(&self, nds: &mut NDS) {
+        match *self {
+            InputEvent::Press(key) => nds.press_key(key),
+            InputEvent::Release(key) => nds.release_key(key),
+            InputEvent::Touch(x, y) => nds.press_screen(x, y),
+            InputEvent::Untouch => nds.release_screen(),
+        }
+    }
+
+    pub(crate) fn apply_hw(&self, hw: &mut HW) {
+        match *self {
+            InputEvent::Press(key) => hw.press_key(key),
+            InputEvent::Release(key) => hw.release_key(key),
+            InputEvent::Touch(x, y) => hw.press_screen(x, y),
+            InputEvent::Untouch => hw.release_screen(),
+        }
+    }
+
+    fn key_name(key: Key) -> &'static str {
+        KEY_NAMES.iter().find(|(k, _)| *k == key).unwrap().1
+    }
+
+    fn parse_key(name: &str) -> Result<Key, String> {
+        KEY_NAMES
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(key, _)| *key)
+            .ok_or_else(|| format!("Unknown key {}", name))
+    }
+
+    fn parse_coord(value: Option<&str>, max: usize) -> Result<usize, String> {
+        let value = value.ok_or_else(|| "Missing touch coordinate".to_string())?;
+        match value.parse::<usize>() {
+            Ok(coord) if coord < max => Ok(coord),
+            _ => Err(format!("Invalid touch coordinate {}", value)),
+        }
+    }
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InputEvent::Press(key) => write!(f, "press {}", InputEvent::key_name(key)),
+            InputEvent::Release(key) => write!(f, "release {}", InputEvent::key_name(key)),
+            InputEvent::Touch(x, y) => write!(f, "touch {} {}", x, y),
+            InputEvent::Untouch => write!(f, "untouch"),
+        }
+    }
+}
+
+impl FromStr for InputEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let event = match words.next() {
+            Some("press") => InputEvent::Press(InputEvent::parse_key(words.next().unwrap_or(""))?),
+            Some("release") => {
+                InputEvent::Release(InputEvent::parse_key(words.next().unwrap_or(""))?)
+            }
+            Some("touch") => InputEvent::Touch(
+                InputEvent::parse_coord(words.next(), WIDTH)?,
+                InputEvent::parse_coord(words.next(), HEIGHT)?,
+            ),
+            Some("untouch") => InputEvent::Untouch,
+            Some(action) => return Err(format!("Unknown action {}", action)),
+            None => return Err("Missing action".to_string()),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("Unexpected {}", extra));
+        }
+        Ok(event)
+    }
+}
+
+// Input events tagged with the frame they happen on, one per line:
+//   <frame> press <key>
+//   <frame> release <key>
+//   <frame> touch <x> <y>
+//   <frame> untouch
+// Keys are A, B, X, Y, L, R, Start, Select, Up, Down, Left and Right. Events are applied
+// before the frame they are tagged with is emulated. Lines starting with # are ignored.
+pub struct InputScript {
+    events: Vec<(usize, InputEvent)>,
+    next_event: usize,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        InputScript {
+            events: Vec::new(),
+            next_event: 0,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseInputError> {
+        let mut script = InputScript::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message| ParseInputError {
+                line: i + 1,
+                message,
+            };
+            let (frame, event) = match line.find(char::is_whitespace) {
+                Some(index) => line.split_at(index),
+                None => (line, ""),
+            };
+            let frame = frame
+                .parse::<usize>()
+                .map_err(|_| error(format!("Invalid frame {}", frame)))?;
+            if event.trim().is_empty() {
+                return Err(error("Missing event".to_string()));
+            }
+            script.push(frame, event.parse().map_err(error)?);
+        }
+        // Events on the same frame keep the order they were written in
+        script.events.sort_by_key(|(frame, _)| *frame);
+        Ok(script)
+    }
+
+    pub fn push(&mut self, frame: usize, event: InputEvent) {
+        self.events.push((frame, event));
+    }
+
+    pub fn events(&self) -> &[(usize, InputEvent)] {
+        &self.events
+    }
+
+    pub fn last_frame(&self) -> Option<usize> {
+        self.events.last().map(|(frame, _)| *frame)
+    }
+
+    // Applies all events for the given frame, frames must be passed in increasing order
+    pub fn apply(&mut self, nds: &mut NDS, frame: usize) {
+        for (_, event) in self.take_events(frame) {
+            event.apply(nds);
+        }
+    }
+
+    // Returns the events up to the given frame that haven't been returned yet
+    pub fn take_events(&mut self, frame: usize) -> &[(usize, InputEvent)] {
+        let start = self.next_event;
+        while let Some((event_frame, _)) = self.events.get(self.next_event) {
+            if *event_frame > frame {
+                break;
+            }
+            self.next_event += 1;
+        }
+        &self.events[start..self.next_event]
+    }
+}
+
+impl Default for InputScript {
+    fn default() -> Self {
+        InputScript::new()
+    }
+}
+
+impl fmt::Display for InputScript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (frame, event) in self.events.iter() {
+            writeln!(f, "{} {}", frame, event)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseInputError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseInputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseInputError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let text = "\
+# Comment
+0 press A
+0 release a
+3 touch 0 0
+2 touch 255 191
+10 untouch
+7   press   start
+";
+        let expected = [
+            (0, InputEvent::Press(Key::A)),
+            (0, InputEvent::Release(Key::A)),
+            (2, InputEvent::Touch(255, 191)),
+            (3, InputEvent::Touch(0, 0)),
+            (7, InputEvent::Press(Key::Start)),
+            (10, InputEvent::Untouch),
+        ];
+        let script = InputScript::parse(text).unwrap();
+        assert!(script.events() == expected, "{}", script);
+        let reparsed = InputScript::parse(&script.to_string()).unwrap();
+        assert!(reparsed.events() == expected, "{}", reparsed);
+        assert_eq!(script.last_frame(), Some(10));
+    }
+
+    #[test]
+    fn take_events() {
+        let mut script = InputScript::parse("1 press A\n1 press B\n4 release A").unwrap();
+        let cases: [(usize, usize); 5] = [(0, 0), (1, 2), (2, 0), (5, 1), (6, 0)];
+        for (frame, count) in cases.iter() {
+            assert_eq!(script.take_events(*frame).len(), *count, "frame {}", frame);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("10", 1, "Missing event"),
+            ("1 press A\n\n10   ", 3, "Missing event"),
+            ("x press A", 1, "Invalid frame x"),
+            ("-1 press A", 1, "Invalid frame -1"),
+            ("1 jump", 1, "Unknown action jump"),
+            ("1 press", 1, "Unknown key "),
+            ("1 press Z", 1, "Unknown key Z"),
+            ("1 touch 256 0", 1, "Invalid touch coordinate 256"),
+            ("1 touch 0 192", 1, "Invalid touch coordinate 192"),
+            ("1 touch 5", 1, "Missing touch coordinate"),
+            ("1 untouch now", 1, "Unexpected now"),
+        ];
+        for (text, line, message) in cases.iter() {
+            let err = InputScript::parse(text).err().unwrap();
+            assert_eq!(
+                (err.line, err.message.as_str()),
+                (*line, *message),
+                "{:?}",
+                text
+            );
+        }
+    }
+}
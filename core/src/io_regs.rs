@@ -0,0 +1,580 @@
+use crate::nds::CPU;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IOAccess {
+    ReadWrite,
+    // Writes are ignored
+    ReadOnly,
+    // Writing 1 acknowledges the bit, writing 0 leaves it alone
+    WriteOneToClear,
+    // Writing 1 starts an operation instead of storing the value
+    Trigger,
+}
+
+// A bitfield of an IO register, covering len bits starting at lsb
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IOField {
+    pub name: &'static str,
+    pub lsb: u32,
+    pub len: u32,
+    pub access: IOAccess,
+}
+
+impl IOField {
+    const fn new(name: &'static str, lsb: u32, len: u32, access: IOAccess) -> Self {
+        IOField {
+            name,
+            lsb,
+            len,
+            access,
+        }
+    }
+
+    fn mask(&self) -> u32 {
+        (u32::MAX >> (32 - self.len)) << self.lsb
+    }
+
+    // Read only and trigger fields can't be edited without side effects
+    pub fn editable(&self) -> bool {
+        match self.access {
+            IOAccess::ReadWrite | IOAccess::WriteOneToClear => true,
+            IOAccess::ReadOnly | IOAccess::Trigger => false,
+        }
+    }
+
+    pub fn get(&self, reg_value: u32) -> u32 {
+        (reg_value & self.mask()) >> self.lsb
+    }
+
+    pub fn set(&self, reg_value: u32, value: u32) -> u32 {
+        reg_value & !self.mask() | (value << self.lsb) & self.mask()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IORegister {
+    pub name: &'static str,
+    pub addr: u32,
+    // In bytes, registers are accessed with their own size
+    pub size: usize,
+    pub fields: &'static [IOField],
+}
+
+impl IORegister {
+    const fn new(name: &'static str, addr: u32, size: usize, fields: &'static [IOField]) -> Self {
+        IORegister {
+            name,
+            addr,
+            size,
+            fields,
+        }
+    }
+
+    // The value to write to change only the given field. Write 1 to clear and trigger bits of
+    // the other fields are written as 0, so for a write 1 to clear field only its own bits are.
+    pub fn write_field(&self, reg_value: u32, field: &IOField, value: u32) -> u32 {
+        let kept = self
+            .fields
+            .iter()
+            .filter(|field| {
+                field.access == IOAccess::WriteOneToClear || field.access == IOAccess::Trigger
+            })
+            .fold(reg_value, |reg_value, field| field.set(reg_value, 0));
+        field.set(kept, value)
+    }
+}
+
+// Registers of each CPU's IO region in address order
+pub fn io_registers(cpu: CPU) -> &'static [IORegister] {
+    match cpu {
+        CPU::ARM9 => ARM9_REGISTERS,
+        CPU::ARM7 => ARM7_REGISTERS,
+    }
+}
+
+const fn f(name: &'static str, lsb: u32, len: u32) -> IOField {
+    IOField::new(name, lsb, len, IOAccess::ReadWrite)
+}
+
+const fn ro(name: &'static str, lsb: u32, len: u32) -> IOField {
+    IOField::new(name, lsb, len, IOAccess::ReadOnly)
+}
+
+const fn w1c(name: &'static str, lsb: u32, len: u32) -> IOField {
+    IOField::new(name, lsb, len, IOAccess::WriteOneToClear)
+}
+
+const fn trigger(name: &'static str, lsb: u32, len: u32) -> IOField {
+    IOField::new(name, lsb, len, IOAccess::Trigger)
+}
+
+const fn r(name: &'static str, addr: u32, size: usize, fields: &'static [IOField]) -> IORegister {
+    IORegister::new(name, addr, size, fields)
+}
+
+const DISPCNT: &[IOField] = &[
+    f("BG Mode", 0, 3),
+    f("BG0 2D/3D", 3, 1),
+    f("Tile OBJ Mapping", 4, 1),
+    f("Bitmap OBJ 2D Dimension", 5, 1),
+    f("Bitmap OBJ Mapping", 6, 1),
+    f("Forced Blank", 7, 1),
+    f("BG0 Enable", 8, 1),
+    f("BG1 Enable", 9, 1),
+    f("BG2 Enable", 10, 1),
+    f("BG3 Enable", 11, 1),
+    f("OBJ Enable", 12, 1),
+    f("Window 0 Enable", 13, 1),
+    f("Window 1 Enable", 14, 1),
+    f("OBJ Window Enable", 15, 1),
+    f("Display Mode", 16, 2),
+    f("VRAM Block", 18, 2),
+    f("Tile OBJ 1D Boundary", 20, 2),
+    f("Bitmap OBJ 1D Boundary", 22, 1),
+    f("OBJ During HBlank", 23, 1),
+    f("Character Base", 24, 3),
+    f("Screen Base", 27, 3),
+    f("BG Extended Palettes", 30, 1),
+    f("OBJ Extended Palettes", 31, 1),
+];
+
+const DISPSTAT: &[IOField] = &[
+    ro("VBlank", 0, 1),
+    ro("HBlank", 1, 1),
+    ro("VCounter Match", 2, 1),
+    f("VBlank IRQ", 3, 1),
+    f("HBlank IRQ", 4, 1),
+    f("VCounter IRQ", 5, 1),
+    f("VCount Setting Bit 8", 7, 1),
+    f("VCount Setting", 8, 8),
+];
+
+const VCOUNT: &[IOField] = &[ro("Line", 0, 9)];
+
+const BGCNT: &[IOField] = &[
+    f("Priority", 0, 2),
+    f("Character Base", 2, 4),
+    f("Mosaic", 6, 1),
+    f("256 Colors", 7, 1),
+    f("Screen Base", 8, 5),
+    f("Ext Palette Slot/Wraparound", 13, 1),
+    f("Screen Size", 14, 2),
+];
+
+const DISP3DCNT: &[IOField] = &[
+    f("Texture Mapping", 0, 1),
+    f("Highlight Shading", 1, 1),
+    f("Alpha Test", 2, 1),
+    f("Alpha Blending", 3, 1),
+    f("Anti-Aliasing", 4, 1),
+    f("Edge Marking", 5, 1),
+    f("Fog Alpha Only", 6, 1),
+    f("Fog Enable", 7, 1),
+    f("Fog Depth Shift", 8, 4),
+    w1c("Color Buffer Underflow", 12, 1),
+    w1c("Polygon/Vertex RAM Overflow", 13, 1),
+    f("Rear-Plane Bitmap", 14, 1),
+];
+
+const DISPCAPCNT: &[IOField] = &[
+    f("EVA", 0, 5),
+    f("EVB", 8, 5),
+    f("VRAM Write Block", 16, 2),
+    f("VRAM Write Offset", 18, 2),
+    f("Capture Size", 20, 2),
+    f("Source A", 24, 1),
+    f("Source B", 25, 1),
+    f("VRAM Read Offset", 26, 2),
+    f("Capture Source", 29, 2),
+    f("Capture Enable", 31, 1),
+];
+
+const MASTER_BRIGHT: &[IOField] = &[f("Factor", 0, 5), f("Mode", 14, 2)];
+
+const ADDRESS: &[IOField] = &[f("Address", 0, 28)];
+
+const VALUE: &[IOField] = &[f("Value", 0, 32)];
+
+const DMA9CNT: &[IOField] = &[
+    f("Word Count", 0, 21),
+    f("Dest Addr Control", 21, 2),
+    f("Source Addr Control", 23, 2),
+    f("Repeat", 25, 1),
+    f("32 Bit", 26, 1),
+    f("Start Timing", 27, 3),
+    f("IRQ", 30, 1),
+    f("Enable", 31, 1),
+];
+
+const DMA7CNT: &[IOField] = &[
+    f("Word Count", 0, 16),
+    f("Dest Addr Control", 21, 2),
+    f("Source Addr Control", 23, 2),
+    f("Repeat", 25, 1),
+    f("32 Bit", 26, 1),
+    f("Start Timing", 28, 2),
+    f("IRQ", 30, 1),
+    f("Enable", 31, 1),
+];
+
+// Reads return the counter but writes set the reload value, so only the raw value is editable
+const TMCNT_L: &[IOField] = &[ro("Counter", 0, 16)];
+
+const TMCNT_H: &[IOField] = &[
+    f("Prescaler", 0, 2),
+    f("Count-Up", 2, 1),
+    f("IRQ", 6, 1),
+    f("Start", 7, 1),
+];
+
+const KEYS: &[IOField] = &[
+    ro("A", 0, 1),
+    ro("B", 1, 1),
+    ro("Select", 2, 1),
+    ro("Start", 3, 1),
+    ro("Right", 4, 1),
+    ro("Left", 5, 1),
+    ro("Up", 6, 1),
+    ro("Down", 7, 1),
+    ro("R", 8, 1),
+    ro("L", 9, 1),
+];
+
+const KEYCNT: &[IOField] = &[
+    f("Keys", 0, 10),
+    f("IRQ Enable", 14, 1),
+    f("IRQ Condition", 15, 1),
+];
+
+const EXTKEYIN: &[IOField] = &[
+    ro("X", 0, 1),
+    ro("Y", 1, 1),
+    ro("Debug", 3, 1),
+    ro("Pen Up", 6, 1),
+    ro("Hinge Open", 7, 1),
+];
+
+const IPCSYNC: &[IOField] = &[
+    ro("Data In", 0, 4),
+    f("Data Out", 8, 4),
+    trigger("Send IRQ", 13, 1),
+    f("IRQ Enable", 14, 1),
+];
+
+const IPCFIFOCNT: &[IOField] = &[
+    ro("Send Empty", 0, 1),
+    ro("Send Full", 1, 1),
+    f("Send Empty IRQ", 2, 1),
+    trigger("Send Clear", 3, 1),
+    ro("Receive Empty", 8, 1),
+    ro("Receive Full", 9, 1),
+    f("Receive Not Empty IRQ", 10, 1),
+    w1c("Error", 14, 1),
+    f("Enable", 15, 1),
+];
+
+const AUXSPICNT: &[IOField] = &[
+    f("Baudrate", 0, 2),
+    f("Chip Select Hold", 6, 1),
+    ro("Busy", 7, 1),
+    f("Slot Mode SPI", 13, 1),
+    f("Transfer IRQ", 14, 1),
+    f("Slot Enable", 15, 1),
+];
+
+const ROMCTRL: &[IOField] = &[
+    f("KEY1 Gap1 Length", 0, 13),
+    f("KEY2 Encrypt Data", 13, 1),
+    trigger("KEY2 Apply Seed", 15, 1),
+    f("KEY1 Gap2 Length", 16, 6),
+    f("KEY2 Encrypt Command", 22, 1),
+    ro("Data Word Ready", 23, 1),
+    f("Data Block Size", 24, 3),
+    f("Transfer Clock Rate", 27, 1),
+    f("KEY1 Gap Clocks", 28, 1),
+    f("Release Reset", 29, 1),
+    f("Write", 30, 1),
+    trigger("Block Busy", 31, 1),
+];
+
+const EXMEMCNT: &[IOField] = &[
+    f("GBA SRAM Access Time", 0, 2),
+    f("GBA ROM 1st Access Time", 2, 2),
+    f("GBA ROM 2nd Access Time", 4, 1),
+    f("PHI Output", 5, 2),
+    f("GBA Slot ARM7 Access", 7, 1),
+    f("NDS Slot ARM7 Access", 11, 1),
+    f("Main Memory Interface Mode", 14, 1),
+    f("Main Memory ARM7 Priority", 15, 1),
+];
+
+const IME: &[IOField] = &[f("Enable", 0, 1)];
+
+const IE9: &[IOField] = &[
+    f("VBlank", 0, 1),
+    f("HBlank", 1, 1),
+    f("VCounter Match", 2, 1),
+    f("Timer 0", 3, 1),
+    f("Timer 1", 4, 1),
+    f("Timer 2", 5, 1),
+    f("Timer 3", 6, 1),
+    f("DMA 0", 8, 1),
+    f("DMA 1", 9, 1),
+    f("DMA 2", 10, 1),
+    f("DMA 3", 11, 1),
+    f("Keypad", 12, 1),
+    f("GBA Slot", 13, 1),
+    f("IPC Sync", 16, 1),
+    f("IPC Send FIFO Empty", 17, 1),
+    f("IPC Receive FIFO Not Empty", 18, 1),
+    f("Card Transfer Complete", 19, 1),
+    f("Card IREQ", 20, 1),
+    f("Geometry FIFO", 21, 1),
+];
+
+const IE7: &[IOField] = &[
+    f("VBlank", 0, 1),
+    f("HBlank", 1, 1),
+    f("VCounter Match", 2, 1),
+    f("Timer 0", 3, 1),
+    f("Timer 1", 4, 1),
+    f("Timer 2", 5, 1),
+    f("Timer 3", 6, 1),
+    f("SIO/RCNT/RTC", 7, 1),
+    f("DMA 0", 8, 1),
+    f("DMA 1", 9, 1),
+    f("DMA 2", 10, 1),
+    f("DMA 3", 11, 1),
+    f("Keypad", 12, 1),
+    f("GBA Slot", 13, 1),
+    f("IPC Sync", 16, 1),
+    f("IPC Send FIFO Empty", 17, 1),
+    f("IPC Receive FIFO Not Empty", 18, 1),
+    f("Card Transfer Complete", 19, 1),
+    f("Card IREQ", 20, 1),
+    f("Screens Unfolding", 22, 1),
+    f("SPI", 23, 1),
+    f("Wifi", 24, 1),
+];
+
+const IF9: &[IOField] = &[
+    w1c("VBlank", 0, 1),
+    w1c("HBlank", 1, 1),
+    w1c("VCounter Match", 2, 1),
+    w1c("Timer 0", 3, 1),
+    w1c("Timer 1", 4, 1),
+    w1c("Timer 2", 5, 1),
+    w1c("Timer 3", 6, 1),
+    w1c("DMA 0", 8, 1),
+    w1c("DMA 1", 9, 1),
+    w1c("DMA 2", 10, 1),
+    w1c("DMA 3", 11, 1),
+    w1c("Keypad", 12, 1),
+    w1c("GBA Slot", 13, 1),
+    w1c("IPC Sync", 16, 1),
+    w1c("IPC Send FIFO Empty", 17, 1),
+    w1c("IPC Receive FIFO Not Empty", 18, 1),
+    w1c("Card Transfer Complete", 19, 1),
+    w1c("Card IREQ", 20, 1),
+    w1c("Geometry FIFO", 21, 1),
+];
+
+const IF7: &[IOField] = &[
+    w1c("VBlank", 0, 1),
+    w1c("HBlank", 1, 1),
+    w1c("VCounter Match", 2, 1),
+    w1c("Timer 0", 3, 1),
+    w1c("Timer 1", 4, 1),
+    w1c("Timer 2", 5, 1),
+    w1c("Timer 3", 6, 1),
+    w1c("SIO/RCNT/RTC", 7, 1),
+    w1c("DMA 0", 8, 1),
+    w1c("DMA 1", 9, 1),
+    w1c("DMA 2", 10, 1),
+    w1c("DMA 3", 11, 1),
+    w1c("Keypad", 12, 1),
+    w1c("GBA Slot", 13, 1),
+    w1c("IPC Sync", 16, 1),
+    w1c("IPC Send FIFO Empty", 17, 1),
+    w1c("IPC Receive FIFO Not Empty", 18, 1),
+    w1c("Card Transfer Complete", 19, 1),
+    w1c("Card IREQ", 20, 1),
+    w1c("Screens Unfolding", 22, 1),
+    w1c("SPI", 23, 1),
+    w1c("Wifi", 24, 1),
+];
+
+const VRAMCNT: &[IOField] = &[f("MST", 0, 3), f("Offset", 3, 2), f("Enable", 7, 1)];
+
+const WRAMCNT: &[IOField] = &[f("Mode", 0, 2)];
+
+const WRAMSTAT: &[IOField] = &[ro("Mode", 0, 2)];
+
+const DIVCNT: &[IOField] = &[
+    f("Mode", 0, 2),
+    ro("Division By Zero", 14, 1),
+    ro("Busy", 15, 1),
+];
+
+const SQRTCNT: &[IOField] = &[f("64 Bit", 0, 1), ro("Busy", 15, 1)];
+
+const POSTFLG: &[IOField] = &[f("Booted", 0, 1), f("RAM", 1, 1)];
+
+const POWCNT1: &[IOField] = &[
+    f("LCDs", 0, 1),
+    f("2D Engine A", 1, 1),
+    f("3D Rendering", 2, 1),
+    f("3D Geometry", 3, 1),
+    f("2D Engine B", 9, 1),
+    f("Display Swap", 15, 1),
+];
+
+const GXSTAT: &[IOField] = &[
+    ro("Test Busy", 0, 1),
+    ro("Box Test Result", 1, 1),
+    ro("Position Stack Level", 8, 5),
+    ro("Projection Stack Level", 13, 1),
+    ro("Matrix Stack Busy", 14, 1),
+    w1c("Matrix Stack Error", 15, 1),
+    ro("FIFO Entries", 16, 9),
+    ro("FIFO Less Than Half Full", 25, 1),
+    ro("FIFO Empty", 26, 1),
+    ro("Geometry Busy", 27, 1),
+    f("FIFO IRQ", 30, 2),
+];
+
+const SPICNT: &[IOField] = &[
+    f("Baudrate", 0, 2),
+    ro("Busy", 7, 1),
+    f("Device", 8, 2),
+    f("Transfer Size", 10, 1),
+    f("Chip Select Hold", 11, 1),
+    f("IRQ", 14, 1),
+    f("Enable", 15, 1),
+];
+
+const HALTCNT: &[IOField] = &[trigger("Power Down Mode", 6, 2)];
+
+const POWCNT2: &[IOField] = &[f("Speakers", 0, 1), f("Wifi", 1, 1)];
+
+const SOUNDCNT: &[IOField] = &[
+    f("Master Volume", 0, 7),
+    f("Left Output", 8, 2),
+    f("Right Output", 10, 2),
+    f("Channel 1 To Mixer", 12, 1),
+    f("Channel 3 To Mixer", 13, 1),
+    f("Master Enable", 15, 1),
+];
+
+const SOUNDBIAS: &[IOField] = &[f("Bias", 0, 10)];
+
+const ARM9_REGISTERS: &[IORegister] = &[
+    r("DISPCNT_A", 0x0400_0000, 4, DISPCNT),
+    r("DISPSTAT", 0x0400_0004, 2, DISPSTAT),
+    r("VCOUNT", 0x0400_0006, 2, VCOUNT),
+    r("BG0CNT_A", 0x0400_0008, 2, BGCNT),
+    r("BG1CNT_A", 0x0400_000A, 2, BGCNT),
+    r("BG2CNT_A", 0x0400_000C, 2, BGCNT),
+    r("BG3CNT_A", 0x0400_000E, 2, BGCNT),
+    r("DISP3DCNT", 0x0400_0060, 2, DISP3DCNT),
+    r("DISPCAPCNT", 0x0400_0064, 4, DISPCAPCNT),
+    r("MASTER_BRIGHT_A", 0x0400_006C, 2, MASTER_BRIGHT),
+    r("DMA0SAD", 0x0400_00B0, 4, ADDRESS),
+    r("DMA0DAD", 0x0400_00B4, 4, ADDRESS),
+    r("DMA0CNT", 0x0400_00B8, 4, DMA9CNT),
+    r("DMA1SAD", 0x0400_00BC, 4, ADDRESS),
+    r("DMA1DAD", 0x0400_00C0, 4, ADDRESS),
+    r("DMA1CNT", 0x0400_00C4, 4, DMA9CNT),
+    r("DMA2SAD", 0x0400_00C8, 4, ADDRESS),
+    r("DMA2DAD", 0x0400_00CC, 4, ADDRESS),
+    r("DMA2CNT", 0x0400_00D0, 4, DMA9CNT),
+    r("DMA3SAD", 0x0400_00D4, 4, ADDRESS),
+    r("DMA3DAD", 0x0400_00D8, 4, ADDRESS),
+    r("DMA3CNT", 0x0400_00DC, 4, DMA9CNT),
+    r("DMA0FILL", 0x0400_00E0, 4, VALUE),
+    r("DMA1FILL", 0x0400_00E4, 4, VALUE),
+    r("DMA2FILL", 0x0400_00E8, 4, VALUE),
+    r("DMA3FILL", 0x0400_00EC, 4, VALUE),
+    r("TM0CNT_L", 0x0400_0100, 2, TMCNT_L),
+    r("TM0CNT_H", 0x0400_0102, 2, TMCNT_H),
+    r("TM1CNT_L", 0x0400_0104, 2, TMCNT_L),
+    r("TM1CNT_H", 0x0400_0106, 2, TMCNT_H),
+    r("TM2CNT_L", 0x0400_0108, 2, TMCNT_L),
+    r("TM2CNT_H", 0x0400_010A, 2, TMCNT_H),
+    r("TM3CNT_L", 0x0400_010C, 2, TMCNT_L),
+    r("TM3CNT_H", 0x0400_010E, 2, TMCNT_H),
+    r("KEYINPUT", 0x0400_0130, 2, KEYS),
+    r("KEYCNT", 0x0400_0132, 2, KEYCNT),
+    r("IPCSYNC", 0x0400_0180, 2, IPCSYNC),
+    r("IPCFIFOCNT", 0x0400_0184, 2, IPCFIFOCNT),
+    r("AUXSPICNT", 0x0400_01A0, 2, AUXSPICNT),
+    r("ROMCTRL", 0x0400_01A4, 4, ROMCTRL),
+    r("EXMEMCNT", 0x0400_0204, 2, EXMEMCNT),
+    r("IME", 0x0400_0208, 4, IME),
+    r("IE", 0x0400_0210, 4, IE9),
+    r("IF", 0x0400_0214, 4, IF9),
+    r("VRAMCNT_A", 0x0400_0240, 1, VRAMCNT),
+    r("VRAMCNT_B", 0x0400_0241, 1, VRAMCNT),
+    r("VRAMCNT_C", 0x0400_0242, 1, VRAMCNT),
+    r("VRAMCNT_D", 0x0400_0243, 1, VRAMCNT),
+    r("VRAMCNT_E", 0x0400_0244, 1, VRAMCNT),
+    r("VRAMCNT_F", 0x0400_0245, 1, VRAMCNT),
+    r("VRAMCNT_G", 0x0400_0246, 1, VRAMCNT),
+    r("WRAMCNT", 0x0400_0247, 1, WRAMCNT),
+    r("VRAMCNT_H", 0x0400_0248, 1, VRAMCNT),
+    r("VRAMCNT_I", 0x0400_0249, 1, VRAMCNT),
+    r("DIVCNT", 0x0400_0280, 2, DIVCNT),
+    r("SQRTCNT", 0x0400_02B0, 2, SQRTCNT),
+    r("POSTFLG", 0x0400_0300, 1, POSTFLG),
+    r("POWCNT1", 0x0400_0304, 2, POWCNT1),
+    r("GXSTAT", 0x0400_0600, 4, GXSTAT),
+    r("DISPCNT_B", 0x0400_1000, 4, DISPCNT),
+    r("BG0CNT_B", 0x0400_1008, 2, BGCNT),
+    r("BG1CNT_B", 0x0400_100A, 2, BGCNT),
+    r("BG2CNT_B", 0x0400_100C, 2, BGCNT),
+    r("BG3CNT_B", 0x0400_100E, 2, BGCNT),
+    r("MASTER_BRIGHT_B", 0x0400_106C, 2, MASTER_BRIGHT),
+];
+
+const ARM7_REGISTERS: &[IORegister] = &[
+    r("DISPSTAT", 0x0400_0004, 2, DISPSTAT),
+    r("VCOUNT", 0x0400_0006, 2, VCOUNT),
+    r("DMA0SAD", 0x0400_00B0, 4, ADDRESS),
+    r("DMA0DAD", 0x0400_00B4, 4, ADDRESS),
+    r("DMA0CNT", 0x0400_00B8, 4, DMA7CNT),
+    r("DMA1SAD", 0x0400_00BC, 4, ADDRESS),
+    r("DMA1DAD", 0x0400_00C0, 4, ADDRESS),
+    r("DMA1CNT", 0x0400_00C4, 4, DMA7CNT),
+    r("DMA2SAD", 0x0400_00C8, 4, ADDRESS),
+    r("DMA2DAD", 0x0400_00CC, 4, ADDRESS),
+    r("DMA2CNT", 0x0400_00D0, 4, DMA7CNT),
+    r("DMA3SAD", 0x0400_00D4, 4, ADDRESS),
+    r("DMA3DAD", 0x0400_00D8, 4, ADDRESS),
+    r("DMA3CNT", 0x0400_00DC, 4, DMA7CNT),
+    r("TM0CNT_L", 0x0400_0100, 2, TMCNT_L),
+    r("TM0CNT_H", 0x0400_0102, 2, TMCNT_H),
+    r("TM1CNT_L", 0x0400_0104, 2, TMCNT_L),
+    r("TM1CNT_H", 0x0400_0106, 2, TMCNT_H),
+    r("TM2CNT_L", 0x0400_0108, 2, TMCNT_L),
+    r("TM2CNT_H", 0x0400_010A, 2, TMCNT_H),
+    r("TM3CNT_L", 0x0400_010C, 2, TMCNT_L),
+    r("TM3CNT_H", 0x0400_010E, 2, TMCNT_H),
+    r("KEYINPUT", 0x0400_0130, 2, KEYS),
+    r("KEYCNT", 0x0400_0132, 2, KEYCNT),
+    r("EXTKEYIN", 0x0400_0136, 2, EXTKEYIN),
+    r("IPCSYNC", 0x0400_0180, 2, IPCSYNC),
+    r("IPCFIFOCNT", 0x0400_0184, 2, IPCFIFOCNT),
+    r("AUXSPICNT", 0x0400_01A0, 2, AUXSPICNT),
+    r("ROMCTRL", 0x0400_01A4, 4, ROMCTRL),
+    r("SPICNT", 0x0400_01C0, 2, SPICNT),
+    r("EXMEMSTAT", 0x0400_0204, 2, EXMEMCNT),
+    r("IME", 0x0400_0208, 4, IME),
+    r("IE", 0x0400_0210, 4, IE7),
+    r("IF", 0x0400_0214, 4, IF7),
+    r("WRAMSTAT", 0x0400_0241, 1, WRAMSTAT),
+    r("POSTFLG", 0x0400_0300, 1, POSTFLG),
+    r("HALTCNT", 0x0400_0301, 1, HALTCNT),
+    r("POWCNT2", 0x0400_0304, 2, POWCNT2),
+    r("SOUNDCNT", 0x0400_0500, 2, SOUNDCNT),
+    r("SOUNDBIAS", 0x0400_0504, 2, SOUNDBIAS),
+];
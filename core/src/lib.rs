@@ -5,8 +5,16 @@ pub use simplelog;
 
 mod arm7;
 mod arm9;
+mod builder;
+mod debugger;
+pub mod disassembler;
+pub mod gdb;
 mod hw;
+mod io_regs;
+mod movie;
+mod savestate;
 
+pub mod input;
 pub mod nds;
 
 pub use nds::NDS;
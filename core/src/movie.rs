@@ -0,0 +1,332 @@
+use std::convert::TryInto;
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::builder::BootMode;
+use crate::hw::{Key, SaveType};
+use crate::input::{InputEvent, InputScript, KEY_NAMES};
+use crate::nds::HEIGHT;
+
+const MAGIC: &[u8; 4] = b"NDSM";
+// Bump whenever the layout below changes
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 0x44;
+const USER_SETTINGS_LEN: usize = 0x200;
+const EVENT_LEN: usize = 8;
+
+// Inputs recorded from power on along with everything needed to reproduce them.
+// All values are little endian.
+//
+// Offset  Size  Description
+// 0x00    4     Magic "NDSM"
+// 0x04    4     Version
+// 0x08    4     Game code
+// 0x0C    32    SHA-256 of the ROM
+// 0x2C    1     Boot mode (0 = direct, 1 = firmware)
+// 0x2D    1     HLE BIOS (0 = off, 1 = on)
+// 0x2E    1     Save type (0 = none, 1 = EEPROM, 2 = flash)
+// 0x2F    1     Reserved
+// 0x30    4     Length in frames
+// 0x34    4     Save size (S)
+// 0x38    4     Number of input events (E)
+// 0x3C    8     Reserved
+// 0x44    0x200 Both firmware user settings copies, from 0x3FE00
+// 0x244   S     Save contents
+// 0x244+S E*8   Input events
+//
+// An input event is:
+// 0x0     4     Frame, the event is applied before this frame is emulated
+// 0x4     1     Type (0 = press, 1 = release, 2 = touch, 3 = untouch)
+// 0x5     1     Key for press and release, X for touch
+// 0x6     1     Y for touch
+// 0x7     1     Reserved
+pub struct Movie {
+    pub game_code: [u8; 4],
+    pub rom_hash: [u8; 32],
+    pub boot_mode: BootMode,
+    pub hle_bios: bool,
+    pub save_type: SaveType,
+    pub frames: usize,
+    pub save: Vec<u8>,
+    pub firmware_user_settings: Vec<u8>,
+    pub inputs: InputScript,
+}
+
+impl Movie {
+    pub fn hash_rom(rom: &[u8]) -> [u8; 32] {
+        Sha256::digest(rom).into()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let events = self.inputs.events();
+        let mut data = Vec::with_capacity(
+            HEADER_LEN + USER_SETTINGS_LEN + self.save.len() + events.len() * EVENT_LEN,
+        );
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&self.game_code);
+        data.extend_from_slice(&self.rom_hash);
+        data.push(match self.boot_mode {
+            BootMode::Direct => 0,
+            BootMode::Firmware => 1,
+        });
+        data.push(self.hle_bios as u8);
+        data.push(match self.save_type {
+            SaveType::None => 0,
+            SaveType::EEPROM(_) => 1,
+            SaveType::Flash(_) => 2,
+        });
+        data.push(0);
+        data.extend_from_slice(&(self.frames as u32).to_le_bytes());
+        data.extend_from_slice(&(self.save.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(events.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&self.firmware_user_settings);
+        data.extend_from_slice(&self.save);
+        for (frame, event) in events.iter() {
+            let (event_type, a, b) = match *event {
+                InputEvent::Press(key) => (0, Movie::key_index(key), 0),
+                InputEvent::Release(key) => (1, Movie::key_index(key), 0),
+                InputEvent::Touch(x, y) => (2, x as u8, y as u8),
+                InputEvent::Untouch => (3, 0, 0),
+            };
+            data.extend_from_slice(&(*frame as u32).to_le_bytes());
+            data.extend_from_slice(&[event_type, a, b, 0]);
+        }
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, LoadMovieError> {
+        if data.len() < HEADER_LEN || &data[0x00..0x04] != MAGIC {
+            return Err(LoadMovieError::InvalidHeader);
+        }
+        let read_u32 = |addr: usize| u32::from_le_bytes(data[addr..addr + 4].try_into().unwrap());
+        let version = read_u32(0x04);
+        if version != VERSION {
+            return Err(LoadMovieError::UnsupportedVersion(version));
+        }
+        let boot_mode = match data[0x2C] {
+            0 => BootMode::Direct,
+            1 => BootMode::Firmware,
+            value => {
+                return Err(LoadMovieError::Corrupted(format!(
+                    "invalid boot mode {}",
+                    value
+                )))
+            }
+        };
+        let frames = read_u32(0x30) as usize;
+        let save_len = read_u32(0x34) as usize;
+        let save_type = match data[0x2E] {
+            0 => SaveType::None,
+            1 => SaveType::EEPROM(save_len),
+            2 => SaveType::Flash(save_len),
+            value => {
+                return Err(LoadMovieError::Corrupted(format!(
+                    "invalid save type {}",
+                    value
+                )))
+            }
+        };
+        let events_len = read_u32(0x38) as usize;
+
+        let save_addr = HEADER_LEN + USER_SETTINGS_LEN;
+        let events_addr = save_addr + save_len;
+        if data.len() != events_addr + events_len * EVENT_LEN {
+            return Err(LoadMovieError::Corrupted("wrong length".to_string()));
+        }
+        let mut inputs = InputScript::new();
+        for addr in (events_addr..data.len()).step_by(EVENT_LEN) {
+            let frame = read_u32(addr) as usize;
+            let (a, b) = (data[addr + 5], data[addr + 6]);
+            let event = match data[addr + 4] {
+                0 => InputEvent::Press(Movie::key(a)?),
+                1 => InputEvent::Release(Movie::key(a)?),
+                2 if (b as usize) < HEIGHT => InputEvent::Touch(a as usize, b as usize),
+                2 => {
+                    return Err(LoadMovieError::Corrupted(format!(
+                        "invalid touch position {} {}",
+                        a, b
+                    )))
+                }
+                3 => InputEvent::Untouch,
+                value => {
+                    return Err(LoadMovieError::Corrupted(format!(
+                        "invalid event type {}",
+                        value
+                    )))
+                }
+            };
+            inputs.push(frame, event);
+        }
+
+        Ok(Movie {
+            game_code: data[0x08..0x0C].try_into().unwrap(),
+            rom_hash: data[0x0C..0x2C].try_into().unwrap(),
+            boot_mode,
+            hle_bios: data[0x2D] != 0,
+            save_type,
+            frames,
+            save: data[save_addr..events_addr].to_vec(),
+            firmware_user_settings: data[HEADER_LEN..save_addr].to_vec(),
+            inputs,
+        })
+    }
+
+    fn key_index(key: Key) -> u8 {
+        KEY_NAMES.iter().position(|(k, _)| *k == key).unwrap() as u8
+    }
+
+    fn key(index: u8) -> Result<Key, LoadMovieError> {
+        KEY_NAMES
+            .get(index as usize)
+            .map(|(key, _)| *key)
+            .ok_or_else(|| LoadMovieError::Corrupted(format!("invalid key {}", index)))
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadMovieError {
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    Corrupted(String),
+}
+
+impl fmt::Display for LoadMovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadMovieError::InvalidHeader => write!(f, "Not a movie"),
+            LoadMovieError::UnsupportedVersion(version) => write!(
+                f,
+                "Movie version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            LoadMovieError::Corrupted(reason) => write!(f, "Movie is corrupted: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadMovieError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nds::tests::test_rom;
+    use crate::nds::NDSBuilder;
+
+    fn test_movie(save_type: SaveType, save: Vec<u8>, events: &[(usize, InputEvent)]) -> Movie {
+        let mut inputs = InputScript::new();
+        for (frame, event) in events.iter() {
+            inputs.push(*frame, *event);
+        }
+        Movie {
+            game_code: *b"TEST",
+            rom_hash: Movie::hash_rom(&test_rom()),
+            boot_mode: BootMode::Direct,
+            hle_bios: true,
+            save_type,
+            frames: 20,
+            save,
+            firmware_user_settings: (0..USER_SETTINGS_LEN).map(|i| i as u8).collect(),
+            inputs,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = [
+            (0, InputEvent::Press(Key::A)),
+            (3, InputEvent::Touch(255, 191)),
+            (3, InputEvent::Release(Key::A)),
+            (9, InputEvent::Untouch),
+            (12, InputEvent::Press(Key::L)),
+        ];
+        let cases = [
+            test_movie(SaveType::None, Vec::new(), &[]),
+            test_movie(SaveType::EEPROM(0x200), vec![0x5A; 0x200], &events),
+            test_movie(
+                SaveType::Flash(256 * 0x400),
+                vec![0xFF; 256 * 0x400],
+                &events[..2],
+            ),
+        ];
+        for movie in cases.iter() {
+            let data = movie.to_bytes();
+            let loaded = Movie::from_bytes(&data).unwrap();
+            assert_eq!(loaded.save_type, movie.save_type);
+            assert_eq!(loaded.frames, movie.frames);
+            assert_eq!(loaded.save, movie.save);
+            assert_eq!(loaded.firmware_user_settings, movie.firmware_user_settings);
+            assert_eq!(loaded.inputs.to_string(), movie.inputs.to_string());
+            assert_eq!(loaded.to_bytes(), data);
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let data = test_movie(SaveType::None, Vec::new(), &[(1, InputEvent::Untouch)]).to_bytes();
+        let event_addr = HEADER_LEN + USER_SETTINGS_LEN;
+        let cases: [(usize, u8, &str); 6] = [
+            (0x00, b'X', "Not a movie"),
+            (0x04, 2, "Movie version 2 is not supported (expected 1)"),
+            (0x2C, 2, "Movie is corrupted: invalid boot mode 2"),
+            (0x2E, 3, "Movie is corrupted: invalid save type 3"),
+            (0x38, 2, "Movie is corrupted: wrong length"),
+            (
+                event_addr + 4,
+                4,
+                "Movie is corrupted: invalid event type 4",
+            ),
+        ];
+        for (addr, value, message) in cases.iter() {
+            let mut data = data.clone();
+            data[*addr] = *value;
+            let err = Movie::from_bytes(&data).err().unwrap();
+            assert_eq!(err.to_string(), *message, "byte 0x{:X}", addr);
+        }
+        let bad_events: [([u8; 3], &str); 3] = [
+            ([0, 12, 0], "invalid key 12"),
+            ([1, 0xFF, 0], "invalid key 255"),
+            ([2, 0, 192], "invalid touch position 0 192"),
+        ];
+        for (event, reason) in bad_events.iter() {
+            let mut data = data.clone();
+            data[event_addr + 4..event_addr + 7].copy_from_slice(event);
+            let err = Movie::from_bytes(&data).err().unwrap();
+            assert_eq!(err.to_string(), format!("Movie is corrupted: {}", reason));
+        }
+    }
+
+    #[test]
+    fn playback() {
+        let mut nds = NDSBuilder::new(vec![], vec![], vec![], test_rom())
+            .hle_bios(true)
+            .save_type(SaveType::None)
+            .record_movie(true)
+            .build()
+            .unwrap();
+        let inputs = [
+            (1, InputEvent::Press(Key::A)),
+            (2, InputEvent::Touch(100, 50)),
+            (4, InputEvent::Release(Key::A)),
+        ];
+        for frame in 0..6 {
+            for (_, event) in inputs.iter().filter(|(f, _)| *f == frame) {
+                event.apply(&mut nds);
+            }
+            nds.emulate_frame();
+        }
+        let recorded = nds.save_state();
+        let movie = Movie::from_bytes(&nds.stop_recording().unwrap().to_bytes()).unwrap();
+        assert_eq!(movie.frames, 6);
+        assert!(movie.inputs.events() == inputs);
+
+        let mut nds = NDSBuilder::new(vec![], vec![], vec![], test_rom())
+            .play_movie(movie)
+            .build()
+            .unwrap();
+        nds.run_until(6);
+        assert_eq!(nds.save_state(), recorded);
+    }
+}
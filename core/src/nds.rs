@@ -1,63 +1,360 @@
-use std::path::PathBuf;
+use std::collections::VecDeque;
 
 use crate::arm7::ARM7;
 use crate::arm9::ARM9;
 use crate::hw::HW;
+use crate::input::{InputEvent, InputScript};
+use crate::savestate::{StateReader, StateWriter};
 
-pub use crate::hw::{Engine, GraphicsType, Key};
+pub use crate::builder::{BootMode, LoadError, NDSBuilder, NDSConfig};
+pub use crate::debugger::{StopReason, WatchKind, Watchpoint};
+pub use crate::hw::{
+    generate_firmware, AudioSink, BufferSink, ChannelFormat, ChannelInfo, DMAOccasion, DMATransfer,
+    Engine, Event, FiredEvent, GeometryDump, GraphicsType, IPCAccess, IPCInterrupt, IPCMessage,
+    Key, Language, LayerToggles, LoadDumpError, MatrixSnapshot, MemoryValue, NullSink, OBJInfo,
+    OBJMode, PolygonMode, PolygonSnapshot, RepeatMode, Save, SaveType, SceneSnapshot,
+    ScheduledEvent, TextureFormat, TextureInfo, UserSettings, VertexSnapshot, AUDIO_SAMPLE_RATE,
+};
+pub use crate::io_regs::{io_registers, IOAccess, IOField, IORegister};
+pub use crate::movie::{LoadMovieError, Movie};
+pub use crate::savestate::LoadStateError;
 
 pub struct NDS {
     arm9_cycles_ahead: i32, // Measured in 66 MHz ARM9 cycles
     arm7: ARM7,
     arm9: ARM9,
     hw: HW,
+    // Number of frames emulated since power on
+    frame: usize,
+    movie: MovieMode,
+    // Emulation can stop in the middle of a frame when debugging
+    in_frame: bool,
+    arm7_catching_up: bool,
+    // Indexed by CPU
+    breakpoints: [Vec<u32>; 2],
+    // Where the last stop before an instruction happened so resuming doesn't stop there again
+    stopped_at: Option<(CPU, u32)>,
+    // VCOUNT when a scanline step started
+    step_vcount: Option<u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CPU {
+    ARM9,
+    ARM7,
+}
+
+enum MovieMode {
+    Off,
+    Recording(Movie),
+    Playing(Movie),
 }
 
 impl NDS {
     pub const CLOCK_RATE: usize = 33513982;
 
-    pub fn new(
+    // Inputs are validated by NDSBuilder
+    pub(crate) fn new(
         bios7: Vec<u8>,
         bios9: Vec<u8>,
         firmware: Vec<u8>,
         rom: Vec<u8>,
-        save_file: PathBuf,
+        mut config: NDSConfig,
+        save_type: SaveType,
     ) -> Self {
-        let direct_boot = true;
-        let mut hw = HW::new(bios7, bios9, firmware, rom, save_file, direct_boot);
+        let direct_boot = config.boot_mode == BootMode::Direct;
+        let boot_mode = config.boot_mode;
+        let hle_bios = config.hle_bios;
+        let movie = config.movie.take();
+        let rom_hash = if config.record_movie && movie.is_none() {
+            Some(Movie::hash_rom(&rom))
+        } else {
+            None
+        };
+        let mut hw = HW::new(bios7, bios9, firmware, rom, config, save_type);
+        let movie = match (movie, rom_hash) {
+            (Some(movie), _) => MovieMode::Playing(movie),
+            (None, Some(rom_hash)) => MovieMode::Recording(Movie {
+                game_code: hw.game_code(),
+                rom_hash,
+                boot_mode,
+                hle_bios,
+                save_type,
+                frames: 0,
+                save: hw.backup_mem().to_vec(),
+                firmware_user_settings: hw.firmware_user_settings().to_vec(),
+                inputs: InputScript::new(),
+            }),
+            (None, None) => MovieMode::Off,
+        };
         NDS {
             arm9_cycles_ahead: 0,
             arm7: ARM7::new(&mut hw, direct_boot),
             arm9: ARM9::new(&mut hw, direct_boot),
             hw,
+            frame: 0,
+            movie,
+            in_frame: false,
+            arm7_catching_up: false,
+            breakpoints: [Vec::new(), Vec::new()],
+            stopped_at: None,
+            step_vcount: None,
         }
     }
 
+    // Breakpoints and watchpoints are ignored, use run_until to stop at them
     pub fn emulate_frame(&mut self) {
+        while self.emulate_frame_until(|_, _| false).is_some() {}
+    }
+
+    // Emulates until the given number of frames have been emulated since power on
+    pub fn run_until(&mut self, frame: usize) -> StopReason {
+        while self.frame < frame {
+            if let Some(reason) = self.emulate_frame_until(|_, _| false) {
+                return reason;
+            }
+        }
+        StopReason::Frame
+    }
+
+    // Emulates until the ARM9 has run one instruction, the ARM7 keeps running alongside it.
+    // Stops at the end of the frame if the ARM9 doesn't run any, such as while halted.
+    pub fn step_arm9(&mut self) -> StopReason {
+        self.step(CPU::ARM9)
+    }
+
+    // Emulates until the ARM7 has run one instruction, the ARM9 keeps running alongside it.
+    // Stops at the end of the frame if the ARM7 doesn't run any, such as while halted.
+    pub fn step_arm7(&mut self) -> StopReason {
+        self.step(CPU::ARM7)
+    }
+
+    // Emulates until VCOUNT changes
+    pub fn step_scanline(&mut self) -> StopReason {
+        self.step_vcount = Some(self.hw.gpu.vcount);
+        loop {
+            if let Some(reason) = self.emulate_frame_until(|_, _| false) {
+                self.step_vcount = None;
+                return reason;
+            }
+        }
+    }
+
+    fn step(&mut self, step_cpu: CPU) -> StopReason {
+        let mut stepped = false;
+        self.emulate_frame_until(|cpu, _| {
+            if cpu != step_cpu {
+                false
+            } else if stepped {
+                true
+            } else {
+                stepped = true;
+                false
+            }
+        })
+        .unwrap_or(StopReason::Frame)
+    }
+
+    // Stops before running an instruction if stop returns true for the CPU about to run it and
+    // the instruction's address, or at breakpoints and watchpoints. The rest of the frame is
+    // emulated by the next call.
+    pub(crate) fn emulate_frame_until(
+        &mut self,
+        stop: impl FnMut(CPU, u32) -> bool,
+    ) -> Option<StopReason> {
+        let frame = self.frame;
+        let stopped = self.run_frame_until(stop);
+        self.hw.tag_dma_trace(frame);
+        self.hw.tag_ipc_trace(frame);
+        stopped
+    }
+
+    fn run_frame_until(&mut self, mut stop: impl FnMut(CPU, u32) -> bool) -> Option<StopReason> {
+        if !self.in_frame {
+            self.in_frame = true;
+            if let MovieMode::Playing(movie) = &mut self.movie {
+                for (_, event) in movie.inputs.take_events(self.frame) {
+                    event.apply_hw(&mut self.hw);
+                }
+            }
+        }
+        // Accesses made while stopped, such as a debugger reading memory, don't count
+        self.hw.take_watchpoint_hit();
+        if self.arm7_catching_up {
+            if let Some(reason) = self.run_arm7(&mut stop) {
+                return Some(reason);
+            }
+        }
         while !self.hw.rendered_frame() {
+            if let Some(vcount) = self.step_vcount {
+                if vcount != self.hw.gpu.vcount {
+                    return Some(StopReason::Scanline);
+                }
+            }
             if !self.hw.gpu.bus_stalled() {
                 self.arm9.handle_irq(&mut self.hw);
                 self.arm9_cycles_ahead += if self.hw.cp15.arm9_halted {
                     self.hw.cycles_until_event()
                 } else {
+                    if let Some(reason) = self.check_stop(CPU::ARM9, self.arm9.pc(), &mut stop) {
+                        return Some(reason);
+                    }
                     self.arm9.emulate_instr(&mut self.hw)
                 } as i32;
-
-                while self.arm9_cycles_ahead >= 0 {
-                    self.arm7.handle_irq(&mut self.hw);
-                    let arm7_cycles_ran = if self.hw.haltcnt.halted() {
-                        1
-                    } else {
-                        self.arm7.emulate_instr(&mut self.hw)
-                    };
-                    self.hw.clock(arm7_cycles_ran);
-                    self.arm9_cycles_ahead -= 2 * arm7_cycles_ran as i32
+                if let Some(reason) = self.hw.take_watchpoint_hit() {
+                    self.arm7_catching_up = true;
+                    return Some(reason);
+                }
+                if let Some(reason) = self.run_arm7(&mut stop) {
+                    return Some(reason);
                 }
             } else {
-                self.hw.clock_until_event()
+                self.hw.clock_until_event();
+                if let Some(reason) = self.hw.take_watchpoint_hit() {
+                    return Some(reason);
+                }
             }
         }
+        self.in_frame = false;
         self.hw.save_backup();
+        self.frame += 1;
+        if let MovieMode::Playing(movie) = &self.movie {
+            if self.frame >= movie.frames {
+                // Hand input back to the user
+                self.movie = MovieMode::Off;
+            }
+        }
+        None
+    }
+
+    // Lets the ARM7 catch up to the ARM9
+    fn run_arm7(&mut self, stop: &mut impl FnMut(CPU, u32) -> bool) -> Option<StopReason> {
+        while self.arm9_cycles_ahead >= 0 {
+            self.arm7.handle_irq(&mut self.hw);
+            let arm7_cycles_ran = if self.hw.haltcnt.halted() {
+                1
+            } else {
+                if let Some(reason) = self.check_stop(CPU::ARM7, self.arm7.pc(), stop) {
+                    self.arm7_catching_up = true;
+                    return Some(reason);
+                }
+                self.arm7.emulate_instr(&mut self.hw)
+            };
+            self.hw.clock(arm7_cycles_ran);
+            self.arm9_cycles_ahead -= 2 * arm7_cycles_ran as i32;
+            if let Some(reason) = self.hw.take_watchpoint_hit() {
+                self.arm7_catching_up = true;
+                return Some(reason);
+            }
+        }
+        self.arm7_catching_up = false;
+        None
+    }
+
+    fn check_stop(
+        &mut self,
+        cpu: CPU,
+        pc: u32,
+        stop: &mut impl FnMut(CPU, u32) -> bool,
+    ) -> Option<StopReason> {
+        let resuming = self.stopped_at == Some((cpu, pc));
+        if resuming {
+            self.stopped_at = None;
+        }
+        let reason = if !resuming && self.breakpoints[cpu as usize].contains(&pc) {
+            StopReason::Breakpoint(cpu, pc)
+        } else if stop(cpu, pc) {
+            StopReason::Step(cpu)
+        } else {
+            return None;
+        };
+        self.stopped_at = Some((cpu, pc));
+        Some(reason)
+    }
+
+    pub fn breakpoints(&self, cpu: CPU) -> &[u32] {
+        &self.breakpoints[cpu as usize]
+    }
+
+    pub fn add_breakpoint(&mut self, cpu: CPU, addr: u32) {
+        if !self.breakpoints[cpu as usize].contains(&addr) {
+            self.breakpoints[cpu as usize].push(addr);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, cpu: CPU, addr: u32) {
+        self.breakpoints[cpu as usize].retain(|breakpoint| *breakpoint != addr);
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        self.hw.watchpoints()
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.hw.watchpoints().contains(&watchpoint) {
+            self.hw.watchpoints_mut().push(watchpoint);
+        }
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.hw
+            .watchpoints_mut()
+            .retain(|other| *other != watchpoint);
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn recording_movie(&self) -> bool {
+        matches!(self.movie, MovieMode::Recording(_))
+    }
+
+    pub fn playing_movie(&self) -> bool {
+        matches!(self.movie, MovieMode::Playing(_))
+    }
+
+    // Returns the movie recorded since power on, the length is the number of frames emulated
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        match std::mem::replace(&mut self.movie, MovieMode::Off) {
+            MovieMode::Recording(mut movie) => {
+                movie.frames = self.frame;
+                Some(movie)
+            }
+            movie => {
+                self.movie = movie;
+                None
+            }
+        }
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new(self.hw.game_code());
+        state.write(&self.arm9_cycles_ahead);
+        state.write(&self.arm7);
+        state.write(&self.arm9);
+        self.hw.save_state(&mut state);
+        state.finish()
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), LoadStateError> {
+        let mut state = StateReader::new(data, self.hw.game_code())?;
+        if !matches!(self.movie, MovieMode::Off) {
+            warn!("Loading a state desyncs the movie");
+        }
+        // Keep a copy of the current state so a corrupted file doesn't leave the machine half loaded
+        let backup = self.save_state();
+        self.read_state(&mut state).inspect_err(|_| {
+            let mut backup = StateReader::new(&backup, self.hw.game_code()).unwrap();
+            self.read_state(&mut backup).unwrap();
+        })
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), LoadStateError> {
+        self.arm9_cycles_ahead = state.read()?;
+        self.arm7 = state.read()?;
+        self.arm9 = state.read()?;
+        self.hw.load_state(state)
     }
 
     pub fn get_screens(&self) -> [&Vec<u16>; 2] {
@@ -65,19 +362,98 @@ impl NDS {
     }
 
     pub fn press_key(&mut self, key: Key) {
-        self.hw.press_key(key);
+        self.input(InputEvent::Press(key));
     }
 
     pub fn release_key(&mut self, key: Key) {
-        self.hw.release_key(key);
+        self.input(InputEvent::Release(key));
     }
 
     pub fn press_screen(&mut self, x: usize, y: usize) {
-        self.hw.press_screen(x, y);
+        self.input(InputEvent::Touch(x, y));
     }
 
     pub fn release_screen(&mut self) {
-        self.hw.release_screen();
+        self.input(InputEvent::Untouch);
+    }
+
+    // Registers are numbered like GDB does, 15 is the address of the next instruction, 16 is the
+    // CPSR and 17 is the SPSR
+    pub fn reg(&self, cpu: CPU, i: usize) -> u32 {
+        match cpu {
+            CPU::ARM9 => self.arm9.reg(i),
+            CPU::ARM7 => self.arm7.reg(i),
+        }
+    }
+
+    // Registers of every mode along with their names, such as r13_svc
+    pub fn banked_regs(&self, cpu: CPU) -> Vec<(&'static str, u32)> {
+        match cpu {
+            CPU::ARM9 => self.arm9.banked_regs(),
+            CPU::ARM7 => self.arm7.banked_regs(),
+        }
+    }
+
+    pub(crate) fn set_reg(&mut self, cpu: CPU, i: usize, value: u32) -> bool {
+        match cpu {
+            CPU::ARM9 => self.arm9.set_reg(&mut self.hw, i, value),
+            CPU::ARM7 => self.arm7.set_reg(&mut self.hw, i, value),
+        }
+    }
+
+    // Memory accesses for tools which don't disturb the emulation, see HW::arm9_peek
+    pub fn peek9<T: MemoryValue>(&self, addr: u32) -> T {
+        self.hw.arm9_peek(addr)
+    }
+
+    pub fn peek7<T: MemoryValue>(&self, addr: u32) -> T {
+        self.hw.arm7_peek(addr)
+    }
+
+    pub fn poke9<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        self.hw.arm9_poke(addr, value)
+    }
+
+    pub fn poke7<T: MemoryValue>(&mut self, addr: u32, value: T) {
+        self.hw.arm7_poke(addr, value)
+    }
+
+    // Reads the register without side effects like peek9 and peek7
+    pub fn read_io(&self, cpu: CPU, reg: &IORegister) -> u32 {
+        match (cpu, reg.size) {
+            (CPU::ARM9, 1) => self.peek9::<u8>(reg.addr) as u32,
+            (CPU::ARM9, 2) => self.peek9::<u16>(reg.addr) as u32,
+            (CPU::ARM9, _) => self.peek9::<u32>(reg.addr),
+            (CPU::ARM7, 1) => self.peek7::<u8>(reg.addr) as u32,
+            (CPU::ARM7, 2) => self.peek7::<u16>(reg.addr) as u32,
+            (CPU::ARM7, _) => self.peek7::<u32>(reg.addr),
+        }
+    }
+
+    // Writes the register as if the CPU did, with all of the write's side effects
+    pub fn write_io(&mut self, cpu: CPU, reg: &IORegister, value: u32) {
+        match (cpu, reg.size) {
+            (CPU::ARM9, 1) => self.hw.arm9_write(reg.addr, value as u8),
+            (CPU::ARM9, 2) => self.hw.arm9_write(reg.addr, value as u16),
+            (CPU::ARM9, _) => self.hw.arm9_write(reg.addr, value),
+            (CPU::ARM7, 1) => self.hw.arm7_write(reg.addr, value as u8),
+            (CPU::ARM7, 2) => self.hw.arm7_write(reg.addr, value as u16),
+            (CPU::ARM7, _) => self.hw.arm7_write(reg.addr, value),
+        }
+    }
+
+    fn input(&mut self, event: InputEvent) {
+        match &mut self.movie {
+            MovieMode::Off => (),
+            MovieMode::Recording(movie) => movie.inputs.push(self.frame, event),
+            // Inputs come from the movie until it ends
+            MovieMode::Playing(_) => return,
+        }
+        event.apply_hw(&mut self.hw);
+    }
+
+    pub fn dtcm_base(&self) -> u32 {
+        self.hw.cp15.dtcm_base()
     }
 
     pub fn render_palettes(
@@ -96,6 +472,22 @@ impl NDS {
         self.hw.render_map(engine, bg_i)
     }
 
+    pub fn layer_toggles(&self, engine: Engine) -> LayerToggles {
+        self.hw.layer_toggles(engine)
+    }
+
+    pub fn set_layer_toggles(&mut self, engine: Engine, layers: LayerToggles) {
+        self.hw.set_layer_toggles(engine, layers)
+    }
+
+    pub fn objs(&self, engine: Engine) -> Vec<OBJInfo> {
+        self.hw.objs(engine)
+    }
+
+    pub fn render_obj(&self, engine: Engine, obj: &OBJInfo) -> (Vec<u16>, usize, usize) {
+        self.hw.render_obj(engine, obj)
+    }
+
     pub fn render_tiles(
         &self,
         engine: Engine,
@@ -119,10 +511,243 @@ impl NDS {
         )
     }
 
+    // Keeps a copy of the polygons each 3D frame renders, for scene_snapshot
+    pub fn set_capture_scene(&mut self, capture: bool) {
+        self.hw.gpu.engine3d.set_capture_scene(capture)
+    }
+
+    pub fn scene_snapshot(&self) -> Option<&SceneSnapshot> {
+        self.hw.gpu.engine3d.scene_snapshot()
+    }
+
+    // Starts recording once the current 3D frame renders and stops once the next one does
+    pub fn record_geometry_frame(&mut self) {
+        self.hw.gpu.engine3d.record_geometry_frame()
+    }
+
+    pub fn is_recording_geometry(&self) -> bool {
+        self.hw.gpu.engine3d.is_recording_geometry()
+    }
+
+    pub fn take_geometry_dump(&mut self) -> Option<GeometryDump> {
+        self.hw.gpu.engine3d.take_geometry_dump()
+    }
+
+    // The 3D engine draws through engine A, this tells which screen shows it
+    pub fn engine_a_on_top(&self) -> bool {
+        self.hw.gpu.engine_a_on_top()
+    }
+
     pub fn render_bank(&self, bank: usize, ignore_alpha: bool) -> (Vec<u16>, usize, usize) {
         self.hw.render_bank(ignore_alpha, bank)
     }
+
+    pub fn scheduler_cycle(&self) -> usize {
+        self.hw.scheduler_cycle()
+    }
+
+    pub fn scheduled_events(&self) -> impl Iterator<Item = ScheduledEvent> + '_ {
+        self.hw.scheduled_events()
+    }
+
+    // Oldest first, only recorded while enabled
+    pub fn fired_events(&self) -> &VecDeque<FiredEvent> {
+        self.hw.fired_events()
+    }
+
+    pub fn set_record_events(&mut self, record: bool) {
+        self.hw.set_record_events(record)
+    }
+
+    // Transfers show up once the emulation call they ran in returns
+    pub fn dma_trace(&self) -> &VecDeque<DMATransfer> {
+        self.hw.dma_trace()
+    }
+
+    pub fn set_trace_dmas(&mut self, trace: bool) {
+        self.hw.set_trace_dmas(trace)
+    }
+
+    pub fn clear_dma_trace(&mut self) {
+        self.hw.clear_dma_trace()
+    }
+
+    pub fn ipc_trace(&self) -> &VecDeque<IPCMessage> {
+        self.hw.ipc_trace()
+    }
+
+    pub fn set_trace_ipc(&mut self, trace: bool) {
+        self.hw.set_trace_ipc(trace)
+    }
+
+    pub fn clear_ipc_trace(&mut self) {
+        self.hw.clear_ipc_trace()
+    }
+
+    pub fn audio_channels(&self) -> Vec<ChannelInfo> {
+        self.hw.audio_channels()
+    }
+
+    pub fn set_audio_channel_muted(&mut self, num: usize, muted: bool) {
+        self.hw.set_audio_channel_muted(num, muted)
+    }
+
+    pub fn set_audio_channel_soloed(&mut self, num: usize, soloed: bool) {
+        self.hw.set_audio_channel_soloed(num, soloed)
+    }
+
+    pub fn set_track_audio_peaks(&mut self, track: bool) {
+        self.hw.set_track_audio_peaks(track)
+    }
+
+    // Peak absolute output of each channel since the previous call, full scale is 0x2000_0000
+    pub fn take_audio_peaks(&mut self) -> [u32; 16] {
+        self.hw.take_audio_peaks()
+    }
 }
 
 pub const WIDTH: usize = crate::hw::GPU::WIDTH;
 pub const HEIGHT: usize = crate::hw::GPU::HEIGHT;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::hw::SaveType;
+
+    // Both CPUs spin incrementing r0 so every frame changes the CPU state
+    pub(crate) fn test_rom() -> Vec<u8> {
+        const LOOP: [u32; 2] = [
+            0xE280_0001, // add r0, r0, #1
+            0xEAFF_FFFD, // b -8
+        ];
+        let mut rom = vec![0; 0x8200];
+        rom[0x0C..0x10].copy_from_slice(b"TEST");
+        let binaries = [(0x20, 0x4000, 0x0200_0000u32), (0x30, 0x8000, 0x037F_8000)];
+        for &(header_addr, offset, ram_addr) in binaries.iter() {
+            let header = [offset as u32, ram_addr, ram_addr, 8];
+            for (i, word) in header.iter().enumerate() {
+                let addr = header_addr + i * 4;
+                rom[addr..addr + 4].copy_from_slice(&word.to_le_bytes());
+            }
+            for (i, instr) in LOOP.iter().enumerate() {
+                let addr = offset + i * 4;
+                rom[addr..addr + 4].copy_from_slice(&instr.to_le_bytes());
+            }
+        }
+        rom
+    }
+
+    pub(crate) fn test_nds() -> NDS {
+        NDSBuilder::new(vec![], vec![], vec![], test_rom())
+            .hle_bios(true)
+            .save_type(SaveType::None)
+            .build()
+            .unwrap()
+    }
+
+    pub(crate) fn test_hw() -> HW {
+        test_nds().hw
+    }
+
+    #[test]
+    fn save_state_round_trip() {
+        let mut nds = test_nds();
+        nds.emulate_frame();
+        let state = nds.save_state();
+        let regs = (nds.reg(CPU::ARM9, 0), nds.reg(CPU::ARM7, 0));
+        nds.emulate_frame();
+        let after = nds.save_state();
+        assert_ne!(nds.reg(CPU::ARM9, 0), regs.0);
+
+        nds.load_state(&state).unwrap();
+        assert_eq!((nds.reg(CPU::ARM9, 0), nds.reg(CPU::ARM7, 0)), regs);
+        assert_eq!(nds.save_state(), state);
+        nds.emulate_frame();
+        assert_eq!(nds.save_state(), after);
+    }
+
+    #[test]
+    fn load_state_errors() {
+        let mut nds = test_nds();
+        nds.emulate_frame();
+        let state = nds.save_state();
+
+        let mut wrong_game = state.clone();
+        wrong_game[8..12].copy_from_slice(b"ABCD");
+        let mut wrong_version = state.clone();
+        wrong_version[4] ^= 0xFF;
+        type Check = fn(&LoadStateError) -> bool;
+        let cases: [(&[u8], Check); 4] = [
+            (b"NDS", |err| matches!(err, LoadStateError::InvalidHeader)),
+            (&wrong_version, |err| {
+                matches!(err, LoadStateError::UnsupportedVersion(_))
+            }),
+            (
+                &wrong_game,
+                |err| matches!(err, LoadStateError::WrongGame { found, .. } if found == b"ABCD"),
+            ),
+            (&state[..state.len() / 2], |err| {
+                matches!(err, LoadStateError::Corrupted(_))
+            }),
+        ];
+        nds.emulate_frame();
+        let current = nds.save_state();
+        for (data, expected) in cases.iter() {
+            let err = nds.load_state(data).unwrap_err();
+            assert!(expected(&err), "unexpected error: {}", err);
+            // A failed load leaves the machine as it was
+            assert_eq!(nds.save_state(), current);
+        }
+    }
+
+    thread_local! {
+        static WARNINGS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Counts the warnings logged by the current test
+    struct WarningCounter;
+
+    impl log::Log for WarningCounter {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.with(|warnings| warnings.set(warnings.get() + 1));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn peek_is_silent() {
+        static LOGGER: WarningCounter = WarningCounter;
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Warn);
+        let nds = test_nds();
+        let addrs = (0x0400_0000..0x0400_2000)
+            .chain(0x0410_0000..0x0410_0020)
+            .chain(0x0480_0000..0x0480_0010)
+            .chain(
+                [0x0100_0000, 0x0B00_0000, 0x1000_0000, 0xF000_0000]
+                    .iter()
+                    .copied(),
+            );
+        WARNINGS.with(|warnings| warnings.set(0));
+        for addr in addrs {
+            nds.peek9::<u32>(addr);
+            nds.peek7::<u32>(addr);
+        }
+        assert_eq!(WARNINGS.with(|warnings| warnings.get()), 0);
+        // Normal reads of the same registers still warn
+        let mut hw = test_hw();
+        hw.arm9_read::<u8>(0x0400_2000);
+        hw.arm7_read::<u8>(0x0400_2000);
+        hw.arm9_read::<u8>(0x0B00_0000);
+        assert_eq!(WARNINGS.with(|warnings| warnings.get()), 3);
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const MAGIC: &[u8; 4] = b"NDSS";
+// Bump whenever the layout of any saved struct changes
+const VERSION: u32 = 4;
+const HEADER_LEN: usize = 12;
+
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new(game_code: [u8; 4]) -> Self {
+        let mut data = Vec::with_capacity(HEADER_LEN);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&game_code);
+        StateWriter { data }
+    }
+
+    pub fn write<T: Serialize>(&mut self, value: &T) {
+        bincode::serialize_into(&mut self.data, value).unwrap();
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8], game_code: [u8; 4]) -> Result<Self, LoadStateError> {
+        if data.len() < HEADER_LEN || &data[0..4] != MAGIC {
+            return Err(LoadStateError::InvalidHeader);
+        }
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        if version != VERSION {
+            return Err(LoadStateError::UnsupportedVersion(version));
+        }
+        let found = [data[8], data[9], data[10], data[11]];
+        if found != game_code {
+            return Err(LoadStateError::WrongGame {
+                expected: game_code,
+                found,
+            });
+        }
+        Ok(StateReader {
+            data: &data[HEADER_LEN..],
+        })
+    }
+
+    pub fn read<T: DeserializeOwned>(&mut self) -> Result<T, LoadStateError> {
+        bincode::deserialize_from(&mut self.data)
+            .map_err(|e| LoadStateError::Corrupted(e.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadStateError {
+    InvalidHeader,
+    UnsupportedVersion(u32),
+    WrongGame { expected: [u8; 4], found: [u8; 4] },
+    Corrupted(String),
+}
+
+impl fmt::Display for LoadStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadStateError::InvalidHeader => write!(f, "Not a save state"),
+            LoadStateError::UnsupportedVersion(version) => write!(
+                f,
+                "Save state version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            LoadStateError::WrongGame { expected, found } => write!(
+                f,
+                "Save state is for game {} but {} is loaded",
+                String::from_utf8_lossy(found),
+                String::from_utf8_lossy(expected)
+            ),
+            LoadStateError::Corrupted(reason) => write!(f, "Save state is corrupted: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadStateError {}
@@ -0,0 +1,183 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use nds_core::input::InputScript;
+use nds_core::log::*;
+use nds_core::nds::{BootMode, LoadError, Movie, NDSBuilder, HEIGHT, NDS, WIDTH};
+use nds_core::simplelog::*;
+use sha2::{Digest, Sha256};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "nds-headless",
+    about = "Runs a Nintendo DS ROM without a window and dumps the screens"
+)]
+struct Args {
+    /// ROM to load
+    #[structopt(parse(from_os_str))]
+    rom: PathBuf,
+    /// Number of frames to emulate, defaults to the movie length or 60
+    #[structopt(long)]
+    frames: Option<usize>,
+    /// Input script with one "<frame> <event>" per line
+    #[structopt(long, parse(from_os_str))]
+    input: Option<PathBuf>,
+    /// Plays back a recorded movie instead of the input script
+    #[structopt(long, parse(from_os_str))]
+    movie: Option<PathBuf>,
+    /// Records the inputs to a movie
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    #[structopt(long, default_value = "bios7.bin", parse(from_os_str))]
+    bios7: PathBuf,
+    #[structopt(long, default_value = "bios9.bin", parse(from_os_str))]
+    bios9: PathBuf,
+    #[structopt(long, default_value = "firmware.bin", parse(from_os_str))]
+    firmware: PathBuf,
+    /// Boots through the firmware (needs BIOS and firmware dumps) or directly into the ROM
+    #[structopt(long, default_value = "direct")]
+    boot: BootMode,
+    /// Initial save data, never written back
+    #[structopt(long, parse(from_os_str))]
+    save: Option<PathBuf>,
+    /// Writes the top screen to a PNG file
+    #[structopt(long, parse(from_os_str))]
+    top: Option<PathBuf>,
+    /// Writes the bottom screen to a PNG file
+    #[structopt(long, parse(from_os_str))]
+    bottom: Option<PathBuf>,
+    /// Exits with an error if the SHA-256 of both screens doesn't match
+    #[structopt(long)]
+    expect: Option<String>,
+}
+
+fn main() {
+    let args = Args::from_args();
+    TermLogger::init(LevelFilter::Warn, Config::default(), TerminalMode::Mixed).unwrap();
+
+    let mut script = match &args.input {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .unwrap_or_else(|err| fail(&format!("Unable to read input script: {}", err)));
+            InputScript::parse(&text)
+                .unwrap_or_else(|err| fail(&format!("Invalid input script: {}", err)))
+        }
+        None => InputScript::new(),
+    };
+    let movie = args.movie.as_ref().map(|path| {
+        let data =
+            fs::read(path).unwrap_or_else(|err| fail(&format!("Unable to read movie: {}", err)));
+        Movie::from_bytes(&data).unwrap_or_else(|err| fail(&format!("Invalid movie: {}", err)))
+    });
+    let frames = args
+        .frames
+        .or_else(|| movie.as_ref().map(|movie| movie.frames))
+        .unwrap_or(60);
+    let mut nds =
+        load_rom(&args, movie).unwrap_or_else(|err| fail(&format!("Unable to load ROM: {}", err)));
+
+    for frame in 0..frames {
+        script.apply(&mut nds, frame);
+        nds.emulate_frame();
+    }
+    if let (Some(path), Some(movie)) = (&args.record, nds.stop_recording()) {
+        fs::write(path, movie.to_bytes())
+            .unwrap_or_else(|err| fail(&format!("Unable to write movie: {}", err)));
+    }
+
+    let screens = nds.get_screens();
+    if let Some(path) = &args.top {
+        write_png(path, screens[0])
+            .unwrap_or_else(|err| fail(&format!("Unable to write top screen: {}", err)));
+    }
+    if let Some(path) = &args.bottom {
+        write_png(path, screens[1])
+            .unwrap_or_else(|err| fail(&format!("Unable to write bottom screen: {}", err)));
+    }
+
+    let hash = hash_screens(screens);
+    println!("{}", hash);
+    if let Some(expected) = &args.expect {
+        if !expected.eq_ignore_ascii_case(&hash) {
+            fail(&format!("Screens don't match, expected {}", expected));
+        }
+    }
+}
+
+fn load_rom(args: &Args, movie: Option<Movie>) -> Result<NDS, LoadError> {
+    let firmware_boot = args.boot == BootMode::Firmware;
+    // Fall back to the HLE BIOS when no dumps are available and booting directly
+    let (bios7, bios9, hle_bios) = match (fs::read(&args.bios7), fs::read(&args.bios9)) {
+        (Ok(bios7), Ok(bios9)) => (bios7, bios9, false),
+        (Err(err), _) | (_, Err(err)) if firmware_boot => {
+            fail(&format!("Firmware boot needs BIOS dumps: {}", err))
+        }
+        _ => (Vec::new(), Vec::new(), true),
+    };
+    let firmware = match fs::read(&args.firmware) {
+        Ok(firmware) => firmware,
+        Err(err) if firmware_boot => fail(&format!("Firmware boot needs a firmware dump: {}", err)),
+        Err(_) => Vec::new(),
+    };
+    let save = match &args.save {
+        Some(path) => {
+            fs::read(path).unwrap_or_else(|err| fail(&format!("Unable to read save: {}", err)))
+        }
+        None => Vec::new(),
+    };
+    let rom =
+        fs::read(&args.rom).unwrap_or_else(|err| fail(&format!("Unable to read ROM: {}", err)));
+    let builder = NDSBuilder::new(bios7, bios9, firmware, rom)
+        .boot_mode(args.boot)
+        .hle_bios(hle_bios)
+        .save_data(save)
+        .record_movie(args.record.is_some());
+    match movie {
+        Some(movie) => builder.play_movie(movie),
+        None => builder,
+    }
+    .build()
+}
+
+// Converts from RGBA 1_5_5_5_REV to RGB 8_8_8
+fn to_rgb8(screen: &[u16]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(screen.len() * 3);
+    for pixel in screen.iter() {
+        for shift in [0, 5, 10].iter() {
+            let color = (pixel >> shift & 0x1F) as u8;
+            data.push(color << 3 | color >> 2);
+        }
+    }
+    data
+}
+
+fn write_png(path: &Path, screen: &[u16]) -> Result<(), png::EncodingError> {
+    let file = fs::File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), WIDTH as u32, HEIGHT as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&to_rgb8(screen))
+}
+
+fn hash_screens(screens: [&Vec<u16>; 2]) -> String {
+    let mut hasher = Sha256::new();
+    for screen in screens.iter() {
+        for pixel in screen.iter() {
+            // Bit 15 isn't part of the color
+            hasher.update((pixel & 0x7FFF).to_le_bytes());
+        }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
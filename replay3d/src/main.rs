@@ -0,0 +1,61 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use nds_core::log::*;
+use nds_core::nds::{GeometryDump, HEIGHT, WIDTH};
+use nds_core::simplelog::*;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "nds-replay3d",
+    about = "Renders a recorded 3D frame without the game and writes it to a PNG file"
+)]
+struct Args {
+    /// Geometry dump recorded from the 3D Scene window
+    #[structopt(parse(from_os_str))]
+    dump: PathBuf,
+    /// PNG file to write the rendered frame to
+    #[structopt(parse(from_os_str))]
+    output: PathBuf,
+}
+
+fn main() {
+    let args = Args::from_args();
+    TermLogger::init(LevelFilter::Warn, Config::default(), TerminalMode::Mixed).unwrap();
+
+    let data = fs::read(&args.dump)
+        .unwrap_or_else(|err| fail(&format!("Unable to read geometry dump: {}", err)));
+    let dump = GeometryDump::from_bytes(&data).unwrap_or_else(|err| fail(&err.to_string()));
+    let frame = dump.replay().unwrap_or_else(|err| fail(&err.to_string()));
+    write_png(&args.output, &frame)
+        .unwrap_or_else(|err| fail(&format!("Unable to write frame: {}", err)));
+    println!("Replayed {} writes", dump.num_writes());
+}
+
+// Converts from RGBA 1_5_5_5_REV to RGB 8_8_8
+fn to_rgb8(screen: &[u16]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(screen.len() * 3);
+    for pixel in screen.iter() {
+        for shift in [0, 5, 10].iter() {
+            let color = (pixel >> shift & 0x1F) as u8;
+            data.push(color << 3 | color >> 2);
+        }
+    }
+    data
+}
+
+fn write_png(path: &Path, screen: &[u16]) -> Result<(), png::EncodingError> {
+    let file = fs::File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), WIDTH as u32, HEIGHT as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&to_rgb8(screen))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use nds_core::log::*;
+use nds_core::nds::{AudioSink, AUDIO_SAMPLE_RATE};
+use ringbuf::RingBuffer;
+
+// Plays samples on the default output device, linearly resampled to its sample rate
+pub struct CpalSink {
+    _stream: cpal::Stream,
+    prod: ringbuf::Producer<[f32; 2]>,
+    // Resampling
+    step: f32,
+    pos: f32,
+    prev_sample: [f32; 2],
+}
+
+impl CpalSink {
+    const BUFFER_LEN: usize = 2048;
+
+    pub fn new() -> Self {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .expect("No audio output device available!");
+        let config = device
+            .default_output_config()
+            .expect("No audio output config available!");
+
+        match config.sample_format() {
+            cpal::SampleFormat::F32 => CpalSink::init::<f32>(device, config.into()),
+            cpal::SampleFormat::I16 => CpalSink::init::<i16>(device, config.into()),
+            cpal::SampleFormat::U16 => CpalSink::init::<u16>(device, config.into()),
+        }
+    }
+
+    fn init<T: cpal::Sample>(device: cpal::Device, config: cpal::StreamConfig) -> Self {
+        let buffer = RingBuffer::<[f32; 2]>::new(CpalSink::BUFFER_LEN);
+        let (prod, mut cons) = buffer.split();
+
+        let output_config = OutputConfig::from(config.channels);
+        let stream = device
+            .build_output_stream(
+                &config,
+                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                    for frame in data.chunks_mut(output_config as usize) {
+                        let samples = cons.pop().unwrap_or([0.0, 0.0]);
+                        match output_config {
+                            OutputConfig::Mono => {
+                                let sample = samples.iter().sum::<f32>() / 2.0;
+                                frame[0] = cpal::Sample::from::<f32>(&sample);
+                            }
+                            OutputConfig::Stereo => {
+                                frame[0] = cpal::Sample::from::<f32>(&(samples[0]));
+                                frame[1] = cpal::Sample::from::<f32>(&(samples[1]));
+                            }
+                        }
+                    }
+                },
+                |err| error!("Audio Stream Error: {}", err),
+            )
+            .unwrap();
+        stream.play().unwrap();
+
+        CpalSink {
+            _stream: stream,
+            prod,
+            // Resampling
+            step: AUDIO_SAMPLE_RATE as f32 / config.sample_rate.0 as f32,
+            pos: 0.0,
+            prev_sample: [0.0, 0.0],
+        }
+    }
+}
+
+impl AudioSink for CpalSink {
+    fn push_sample(&mut self, left_sample: f32, right_sample: f32) {
+        let sample = [left_sample, right_sample];
+        // Outputs every device sample between the previous sample and this one
+        while self.pos < 1.0 {
+            let lerp =
+                |i: usize| self.prev_sample[i] + (sample[i] - self.prev_sample[i]) * self.pos;
+            let output = [lerp(0), lerp(1)];
+            // Throttles emulation to the speed the device consumes samples at
+            while self.prod.is_full() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            self.prod.push(output).unwrap();
+            self.pos += self.step;
+        }
+        self.pos -= 1.0;
+        self.prev_sample = sample;
+    }
+}
+
+#[derive(Clone, Copy)]
+enum OutputConfig {
+    Mono = 1,
+    Stereo = 2,
+}
+
+impl From<u16> for OutputConfig {
+    fn from(value: u16) -> Self {
+        use OutputConfig::*;
+        match value {
+            1 => Mono,
+            2 => Stereo,
+            _ => panic!("Only Mono and Stereo audio devices supported!"),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use imgui::*;
+
+use super::NDS;
+use nds_core::nds::ChannelFormat;
+
+pub struct AudioWindow {
+    opened: bool,
+    levels: [f32; 16],
+}
+
+impl AudioWindow {
+    const FULL_SCALE: f32 = 0x2000_0000 as f32;
+    const LEVEL_DECAY: f32 = 0.9;
+    const METER_SIZE: [f32; 2] = [100.0, 0.0];
+
+    pub fn new() -> Self {
+        AudioWindow {
+            opened: false,
+            levels: [0.0; 16],
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.opened
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let peaks = nds.take_audio_peaks();
+        for (level, peak) in self.levels.iter_mut().zip(peaks.iter()) {
+            let peak = *peak as f32 / AudioWindow::FULL_SCALE;
+            *level = peak.max(*level * AudioWindow::LEVEL_DECAY);
+        }
+        let channels = nds.audio_channels();
+
+        let mut opened = self.opened;
+        Window::new(im_str!("Audio Channels"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.text(
+                    "Ch Format  Repeat   Source   Loop   Length    Timer        Rate Vol Div Pan",
+                );
+                for (num, channel) in channels.iter().enumerate() {
+                    let format = match channel.format {
+                        ChannelFormat::PSG => format!("PSG {}/8", channel.wave_duty + 1),
+                        format => format!("{:?}", format),
+                    };
+                    ui.text(format!(
+                        "{:2} {:<7} {:<8} {:08X} {:04X} {:06X} {:04X} {:9.1}Hz {:3} {:3} {:3} {}{}",
+                        num,
+                        format,
+                        format!("{:?}", channel.repeat_mode),
+                        channel.src_addr,
+                        channel.loop_start,
+                        channel.len,
+                        channel.timer_val,
+                        channel.frequency,
+                        channel.volume,
+                        channel.volume_shift,
+                        channel.pan,
+                        if channel.hold { "H" } else { " " },
+                        if channel.busy { "B" } else { " " },
+                    ));
+                    ui.same_line(0.0);
+                    ProgressBar::new(self.levels[num].min(1.0))
+                        .size(AudioWindow::METER_SIZE)
+                        .overlay_text(im_str!(""))
+                        .build(ui);
+                    ui.same_line(0.0);
+                    let mut muted = channel.muted;
+                    if ui.checkbox(&ImString::new(format!("Mute##{}", num)), &mut muted) {
+                        nds.set_audio_channel_muted(num, muted);
+                    }
+                    ui.same_line(0.0);
+                    let mut soloed = channel.soloed;
+                    if ui.checkbox(&ImString::new(format!("Solo##{}", num)), &mut soloed) {
+                        nds.set_audio_channel_soloed(num, soloed);
+                    }
+                }
+                if ui.small_button(im_str!("Unmute All")) {
+                    for num in 0..channels.len() {
+                        nds.set_audio_channel_muted(num, false);
+                        nds.set_audio_channel_soloed(num, false);
+                    }
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("Audio Channels"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+
+use imgui::*;
+
+use super::NDS;
+use nds_core::disassembler::{disassemble_arm, disassemble_thumb};
+use nds_core::nds::{StopReason, CPU};
+
+pub struct CPUWindow {
+    opened: bool,
+    cpu: usize,
+    // Disassembly rows scrolled away from the PC
+    offset: i32,
+    cursor: Option<u32>,
+    // Breakpoint added by run to cursor, removed at the next stop
+    temp_breakpoint: Option<(CPU, u32)>,
+    last_stop: Option<StopReason>,
+}
+
+impl CPUWindow {
+    const CPUS: [CPU; 2] = [CPU::ARM9, CPU::ARM7];
+    const ROWS_BEFORE: i32 = 8;
+    const ROWS_AFTER: i32 = 16;
+    const FLAGS: [(&'static str, u32); 8] = [
+        ("N", 31),
+        ("Z", 30),
+        ("C", 29),
+        ("V", 28),
+        ("Q", 27),
+        ("I", 7),
+        ("F", 6),
+        ("T", 5),
+    ];
+    const PC_COLOR: [f32; 4] = [1.0, 1.0, 0.4, 1.0];
+    const BREAKPOINT_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+
+    pub fn new() -> Self {
+        CPUWindow {
+            opened: false,
+            cpu: 0,
+            offset: 0,
+            cursor: None,
+            temp_breakpoint: None,
+            last_stop: None,
+        }
+    }
+
+    // Called when emulation stopped before the end of a frame, pauses it
+    pub fn stopped(&mut self, nds: &mut NDS, reason: StopReason, paused: &mut bool) {
+        *paused = true;
+        if let Some((cpu, addr)) = self.temp_breakpoint.take() {
+            nds.remove_breakpoint(cpu, addr);
+        }
+        if let Some(cpu) = reason.cpu() {
+            self.cpu = cpu as usize;
+        }
+        self.offset = 0;
+        self.last_stop = Some(reason);
+    }
+
+    fn mode_name(cpsr: u32) -> &'static str {
+        match cpsr & 0x1F {
+            0b10000 => "USR",
+            0b10001 => "FIQ",
+            0b10010 => "IRQ",
+            0b10011 => "SVC",
+            0b10111 => "ABT",
+            0b11011 => "UND",
+            0b11111 => "SYS",
+            _ => "???",
+        }
+    }
+
+    fn flags(psr: u32) -> String {
+        CPUWindow::FLAGS
+            .iter()
+            .map(|&(name, bit)| if psr & 1 << bit != 0 { name } else { "-" })
+            .collect()
+    }
+
+    fn render_regs(nds: &NDS, cpu: CPU, ui: &Ui) {
+        ui.text(format!("{:?}", cpu));
+        for i in 0..16 {
+            let name = match i {
+                13 => "sp ".to_string(),
+                14 => "lr ".to_string(),
+                15 => "pc ".to_string(),
+                _ => format!("r{:<2}", i),
+            };
+            ui.text(format!("{} {:08X}", name, nds.reg(cpu, i)));
+        }
+        let cpsr = nds.reg(cpu, 16);
+        let spsr = nds.reg(cpu, 17);
+        ui.text(format!(
+            "cpsr {:08X} {} {}",
+            cpsr,
+            CPUWindow::flags(cpsr),
+            CPUWindow::mode_name(cpsr)
+        ));
+        ui.text(format!(
+            "spsr {:08X} {} {}",
+            spsr,
+            CPUWindow::flags(spsr),
+            CPUWindow::mode_name(spsr)
+        ));
+        ui.separator();
+        for (name, value) in nds.banked_regs(cpu) {
+            ui.text(format!("{:<8} {:08X}", name, value));
+        }
+    }
+
+    fn disassemble(nds: &NDS, cpu: CPU, addr: u32, thumb: bool) -> String {
+        let peek16 = |addr| match cpu {
+            CPU::ARM9 => nds.peek9::<u16>(addr),
+            CPU::ARM7 => nds.peek7::<u16>(addr),
+        };
+        if thumb {
+            disassemble_thumb(peek16(addr), peek16(addr.wrapping_add(2)), addr, cpu)
+        } else {
+            let instr = match cpu {
+                CPU::ARM9 => nds.peek9::<u32>(addr),
+                CPU::ARM7 => nds.peek7::<u32>(addr),
+            };
+            disassemble_arm(instr, addr, cpu)
+        }
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui, paused: &mut bool) {
+        if !self.opened {
+            return;
+        }
+        let mut opened = self.opened;
+        Window::new(im_str!("CPU"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.checkbox(im_str!("Paused"), paused);
+                let mut stop = None;
+                if *paused {
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Step ARM9")) {
+                        stop = Some(nds.step_arm9());
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Step ARM7")) {
+                        stop = Some(nds.step_arm7());
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Step Scanline")) {
+                        stop = Some(nds.step_scanline());
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Step Frame")) {
+                        stop = Some(nds.run_until(nds.frame() + 1));
+                    }
+                }
+                if let Some(reason) = stop {
+                    self.stopped(nds, reason, paused);
+                }
+                if let Some(reason) = self.last_stop {
+                    ui.text(format!("Stopped: {:?}", reason));
+                }
+                ui.separator();
+
+                ui.columns(2, im_str!("Registers"), true);
+                for &cpu in CPUWindow::CPUS.iter() {
+                    CPUWindow::render_regs(nds, cpu, ui);
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!("Registers"), false);
+                ui.separator();
+
+                let combo_width = ui.window_size()[0] * 0.2;
+                ui.set_next_item_width(combo_width);
+                let cpu_changed = ComboBox::new(im_str!("Disassembly")).build_simple(
+                    ui,
+                    &mut self.cpu,
+                    &CPUWindow::CPUS,
+                    &(|cpu| Cow::from(ImString::new(format!("{:?}", cpu)))),
+                );
+                if cpu_changed {
+                    self.offset = 0;
+                    self.cursor = None;
+                }
+                let cpu = CPUWindow::CPUS[self.cpu];
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Follow PC")) {
+                    self.offset = 0;
+                }
+                if let Some(cursor) = self.cursor {
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Toggle Breakpoint")) {
+                        if nds.breakpoints(cpu).contains(&cursor) {
+                            nds.remove_breakpoint(cpu, cursor);
+                        } else {
+                            nds.add_breakpoint(cpu, cursor);
+                        }
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Run to Cursor")) {
+                        if let Some((cpu, addr)) = self.temp_breakpoint.take() {
+                            nds.remove_breakpoint(cpu, addr);
+                        }
+                        if !nds.breakpoints(cpu).contains(&cursor) {
+                            nds.add_breakpoint(cpu, cursor);
+                            self.temp_breakpoint = Some((cpu, cursor));
+                        }
+                        *paused = false;
+                    }
+                }
+
+                let pc = nds.reg(cpu, 15);
+                let thumb = nds.reg(cpu, 16) & 0x20 != 0;
+                let instr_len = if thumb { 2 } else { 4 };
+                for row in -CPUWindow::ROWS_BEFORE..CPUWindow::ROWS_AFTER {
+                    let addr = pc.wrapping_add(((row + self.offset) * instr_len) as u32);
+                    let breakpoint = nds.breakpoints(cpu).contains(&addr);
+                    let color = if addr == pc {
+                        Some(ui.push_style_color(StyleColor::Text, CPUWindow::PC_COLOR))
+                    } else if breakpoint {
+                        Some(ui.push_style_color(StyleColor::Text, CPUWindow::BREAKPOINT_COLOR))
+                    } else {
+                        None
+                    };
+                    let label = ImString::new(format!(
+                        "{}{} {:08X}  {}",
+                        if breakpoint { '*' } else { ' ' },
+                        if addr == pc { '>' } else { ' ' },
+                        addr,
+                        CPUWindow::disassemble(nds, cpu, addr, thumb),
+                    ));
+                    let clicked = Selectable::new(&label)
+                        .selected(self.cursor == Some(addr))
+                        .build(ui);
+                    if let Some(color) = color {
+                        color.pop(ui);
+                    }
+                    if clicked {
+                        self.cursor = Some(addr);
+                    }
+                }
+                // Mouse wheel scrolls by instructions
+                if ui.is_window_hovered() {
+                    self.offset -= ui.io().mouse_wheel as i32;
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("CPU"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
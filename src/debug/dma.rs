@@ -0,0 +1,173 @@
+use std::fs;
+use std::io::{BufWriter, Write};
+
+use imgui::*;
+
+use super::{trace_list, TraceFilter, NDS};
+use nds_core::nds::{DMAOccasion, DMATransfer, CPU};
+
+pub struct DMAWindow {
+    opened: bool,
+    tracing: bool,
+    cpu: TraceFilter<CPU>,
+    channel: TraceFilter<usize>,
+    occasion: TraceFilter<DMAOccasion>,
+    dest_start: ImString,
+    dest_end: ImString,
+    export_path: ImString,
+    status: String,
+}
+
+impl DMAWindow {
+    const CPUS: [Option<CPU>; 3] = [None, Some(CPU::ARM9), Some(CPU::ARM7)];
+    const CHANNELS: [Option<usize>; 5] = [None, Some(0), Some(1), Some(2), Some(3)];
+    const OCCASIONS: [Option<DMAOccasion>; 10] = [
+        None,
+        Some(DMAOccasion::Immediate),
+        Some(DMAOccasion::VBlank),
+        Some(DMAOccasion::HBlank),
+        Some(DMAOccasion::StartOfDisplay),
+        Some(DMAOccasion::MainMemoryDisplay),
+        Some(DMAOccasion::DSCartridge),
+        Some(DMAOccasion::GBACartridge),
+        Some(DMAOccasion::GeometryCommandFIFO),
+        Some(DMAOccasion::WirelessInterrupt),
+    ];
+
+    pub fn new() -> Self {
+        let mut export_path = ImString::with_capacity(256);
+        export_path.push_str("dma_trace.csv");
+        DMAWindow {
+            opened: false,
+            tracing: true,
+            cpu: TraceFilter::new(im_str!("CPU"), &DMAWindow::CPUS),
+            channel: TraceFilter::new(im_str!("Channel"), &DMAWindow::CHANNELS),
+            occasion: TraceFilter::new(im_str!("Occasion"), &DMAWindow::OCCASIONS),
+            dest_start: ImString::with_capacity(8),
+            dest_end: ImString::with_capacity(8),
+            export_path,
+            status: String::new(),
+        }
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.opened && self.tracing
+    }
+
+    fn matches(&self, transfer: &DMATransfer) -> bool {
+        let dest_start = u32::from_str_radix(self.dest_start.to_str(), 16).unwrap_or(0);
+        let dest_end = u32::from_str_radix(self.dest_end.to_str(), 16).unwrap_or(0xFFFF_FFFF);
+        let (dest_first, dest_last) = transfer.dest_range();
+        self.cpu.matches(transfer.cpu)
+            && self.channel.matches(transfer.channel)
+            && self.occasion.matches(transfer.occasion)
+            && dest_first <= dest_end
+            && dest_last >= dest_start
+    }
+
+    fn export(path: &str, transfers: &[&DMATransfer]) -> std::io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        writeln!(
+            file,
+            "frame,scanline,cycle,cpu,channel,occasion,source,destination,count,unit_size"
+        )?;
+        for transfer in transfers.iter() {
+            writeln!(
+                file,
+                "{},{},{},{:?},{},{:?},{:08X},{:08X},{},{}",
+                transfer.frame,
+                transfer.vcount,
+                transfer.cycle,
+                transfer.cpu,
+                transfer.channel,
+                transfer.occasion,
+                transfer.src_addr,
+                transfer.dest_addr,
+                transfer.count,
+                transfer.unit_size,
+            )?;
+        }
+        file.flush()
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let mut opened = self.opened;
+        Window::new(im_str!("DMA Trace"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.checkbox(im_str!("Trace"), &mut self.tracing);
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Clear")) {
+                    nds.clear_dma_trace();
+                }
+
+                let combo_width = ui.window_size()[0] * 0.25;
+                self.cpu.render(ui, combo_width);
+                ui.same_line(0.0);
+                self.channel.render(ui, combo_width);
+                self.occasion.render(ui, combo_width);
+                ui.set_next_item_width(combo_width);
+                InputText::new(ui, im_str!("Dest From"), &mut self.dest_start)
+                    .chars_hexadecimal(true)
+                    .build();
+                ui.same_line(0.0);
+                ui.set_next_item_width(combo_width);
+                InputText::new(ui, im_str!("Dest To"), &mut self.dest_end)
+                    .chars_hexadecimal(true)
+                    .build();
+
+                let trace = nds.dma_trace();
+                let transfers: Vec<&DMATransfer> = trace
+                    .iter()
+                    .filter(|transfer| self.matches(transfer))
+                    .collect();
+                ui.text(format!(
+                    "{} of {} transfers match, newest first",
+                    transfers.len(),
+                    trace.len()
+                ));
+                ui.text("   Frame Line       Cycle CPU  Ch Occasion             Source   Dest      Count");
+                trace_list(ui, "Transfer List", &transfers, |transfer| {
+                    ui.text(format!(
+                        "{:8} {:4} {:11} {:?} {:2} {:<20} {:08X} {:08X} {:6}x{}",
+                        transfer.frame,
+                        transfer.vcount,
+                        transfer.cycle,
+                        transfer.cpu,
+                        transfer.channel,
+                        format!("{:?}", transfer.occasion),
+                        transfer.src_addr,
+                        transfer.dest_addr,
+                        transfer.count,
+                        transfer.unit_size,
+                    ));
+                });
+
+                InputText::new(ui, im_str!("CSV Path"), &mut self.export_path).build();
+                if ui.small_button(im_str!("Export CSV")) {
+                    let path = self.export_path.to_str();
+                    self.status = match DMAWindow::export(path, &transfers) {
+                        Ok(()) => format!("Exported {} transfers to {}", transfers.len(), path),
+                        Err(err) => format!("Unable to export DMA trace: {}", err),
+                    };
+                }
+                if !self.status.is_empty() {
+                    ui.text(&self.status);
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("DMA Trace"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use imgui::*;
+
+use super::NDS;
+use nds_core::nds::ScheduledEvent;
+
+pub struct EventsWindow {
+    opened: bool,
+    filter: ImString,
+}
+
+impl EventsWindow {
+    const LIST_HEIGHT: f32 = 200.0;
+
+    pub fn new() -> Self {
+        EventsWindow {
+            opened: false,
+            filter: ImString::with_capacity(32),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.opened
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let mut opened = self.opened;
+        Window::new(im_str!("Scheduler Events"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.text(format!(
+                    "Frame {}, ARM7 cycle {}",
+                    nds.frame(),
+                    nds.scheduler_cycle()
+                ));
+                ui.set_next_item_width(ui.window_size()[0] * 0.5);
+                InputText::new(ui, im_str!("Filter"), &mut self.filter).build();
+                let filter = self.filter.to_str().to_lowercase();
+                let matches = |name: &str| name.to_lowercase().contains(&filter);
+
+                let mut scheduled: Vec<ScheduledEvent> = nds.scheduled_events().collect();
+                scheduled.sort_by_key(|scheduled| scheduled.cycle);
+                ui.text(format!("{} pending", scheduled.len()));
+                ui.text("     Cycle   Relative  Event");
+                ChildWindow::new("Pending Events")
+                    .size([0.0, EventsWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for scheduled in scheduled.iter() {
+                            let name = format!("{:?}", scheduled.event);
+                            if !matches(&name) {
+                                continue;
+                            }
+                            ui.text(format!(
+                                "{:10} {:+10}  {}",
+                                scheduled.cycle, scheduled.relative_cycle, name
+                            ));
+                        }
+                    });
+
+                let fired = nds.fired_events();
+                ui.text(format!("Last {} fired, newest first", fired.len()));
+                ui.text(" Fired at       Late  Event");
+                ChildWindow::new("Fired Events")
+                    .size([0.0, EventsWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for fired in fired.iter().rev() {
+                            let name = format!("{:?}", fired.event);
+                            if !matches(&name) {
+                                continue;
+                            }
+                            ui.text(format!(
+                                "{:10} {:10}  {}",
+                                fired.fired_cycle,
+                                fired.fired_cycle - fired.cycle,
+                                name
+                            ));
+                        }
+                    });
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("Scheduler Events"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::borrow::Cow;
+
+use imgui::*;
+
+use super::NDS;
+use nds_core::nds::{io_registers, IOAccess, CPU};
+
+pub struct IOWindow {
+    opened: bool,
+    cpu: usize,
+    filter: ImString,
+    // Index into the CPU's register table
+    selected: Option<usize>,
+    value: ImString,
+}
+
+impl IOWindow {
+    const CPUS: [CPU; 2] = [CPU::ARM9, CPU::ARM7];
+    const LIST_HEIGHT: f32 = 300.0;
+
+    pub fn new() -> Self {
+        IOWindow {
+            opened: false,
+            cpu: 0,
+            filter: ImString::with_capacity(16),
+            selected: None,
+            value: ImString::with_capacity(8),
+        }
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let mut opened = self.opened;
+        Window::new(im_str!("IO Registers"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                let combo_width = ui.window_size()[0] * 0.3;
+                ui.set_next_item_width(combo_width);
+                let cpu_changed = ComboBox::new(im_str!("CPU")).build_simple(
+                    ui,
+                    &mut self.cpu,
+                    &IOWindow::CPUS,
+                    &(|cpu| Cow::from(ImString::new(format!("{:?}", cpu)))),
+                );
+                if cpu_changed {
+                    self.selected = None;
+                }
+                let cpu = IOWindow::CPUS[self.cpu];
+                let registers = io_registers(cpu);
+                ui.same_line(0.0);
+                ui.set_next_item_width(combo_width);
+                InputText::new(ui, im_str!("Filter"), &mut self.filter).build();
+
+                let filter = self.filter.to_str().to_uppercase();
+                ChildWindow::new("Register List")
+                    .size([0.0, IOWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for (i, reg) in registers.iter().enumerate() {
+                            if !reg.name.contains(&filter) {
+                                continue;
+                            }
+                            let value = nds.read_io(cpu, reg);
+                            let label = ImString::new(format!(
+                                "{:<16} {:08X}  {:0width$X}",
+                                reg.name,
+                                reg.addr,
+                                value,
+                                width = reg.size * 2,
+                            ));
+                            let clicked = Selectable::new(&label)
+                                .selected(self.selected == Some(i))
+                                .build(ui);
+                            if clicked {
+                                self.selected = Some(i);
+                                self.value = ImString::new(format!("{:X}", value));
+                            }
+                        }
+                    });
+
+                let reg = match self.selected {
+                    Some(i) => &registers[i],
+                    None => return,
+                };
+                let reg_value = nds.read_io(cpu, reg);
+                ui.separator();
+                ui.text(format!("{} at {:08X}", reg.name, reg.addr));
+                ui.set_next_item_width(combo_width);
+                let edited = InputText::new(ui, im_str!("Value"), &mut self.value)
+                    .chars_hexadecimal(true)
+                    .enter_returns_true(true)
+                    .build();
+                if edited {
+                    if let Ok(value) = u32::from_str_radix(self.value.to_str(), 16) {
+                        nds.write_io(cpu, reg, value);
+                    }
+                }
+                for field in reg.fields {
+                    let label = ImString::new(field.name);
+                    let value = field.get(reg_value);
+                    if !field.editable() {
+                        ui.text(format!("{}: {:X}", field.name, value));
+                    } else if field.access == IOAccess::WriteOneToClear {
+                        // Unchecking acknowledges the bits, they can't be set from here
+                        let mut set = value != 0;
+                        if ui.checkbox(&label, &mut set) && !set {
+                            nds.write_io(cpu, reg, reg.write_field(reg_value, field, value));
+                        }
+                    } else if field.len == 1 {
+                        let mut set = value != 0;
+                        if ui.checkbox(&label, &mut set) {
+                            nds.write_io(cpu, reg, reg.write_field(reg_value, field, set as u32));
+                        }
+                    } else {
+                        let mut value = value as i32;
+                        ui.set_next_item_width(combo_width);
+                        let edited = InputInt::new(ui, &label, &mut value)
+                            .enter_returns_true(true)
+                            .build();
+                        if edited {
+                            nds.write_io(cpu, reg, reg.write_field(reg_value, field, value as u32));
+                        }
+                    }
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("IO Registers"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
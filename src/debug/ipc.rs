@@ -0,0 +1,218 @@
+use imgui::*;
+
+use super::{trace_list, TraceFilter, NDS};
+use nds_core::nds::{IPCAccess, IPCMessage, CPU};
+
+// How libnds packs a FIFO word, see fifocommon.h
+#[derive(Clone, Copy)]
+enum FIFOWord {
+    Address(usize, u32),
+    Value32(usize, u32),
+    // The full value is in the next word
+    Extra(usize),
+    ExtraValue(usize),
+    Data(usize, u32),
+    Payload(usize),
+}
+
+impl FIFOWord {
+    const CHANNEL_SHIFT: u32 = 28;
+    const ADDRESS_BIT: u32 = 1 << 27;
+    const IMMEDIATE_BIT: u32 = 1 << 26;
+    const EXTRA_BIT: u32 = 1 << 25;
+    const VALUE32_MASK: u32 = FIFOWord::EXTRA_BIT - 1;
+    const DATA_LENGTH_MASK: u32 = 0xFFFF;
+    const ADDRESS_MASK: u32 = 0x00FF_FFFF;
+    const ADDRESS_BASE: u32 = 0x0200_0000;
+
+    const CHANNELS: [&'static str; 16] = [
+        "PM", "Sound", "System", "Maxmod", "DSWifi", "SDMMC", "Firmware", "Reserved", "User 1",
+        "User 2", "User 3", "User 4", "User 5", "User 6", "User 7", "User 8",
+    ];
+
+    fn new(value: u32) -> Self {
+        let channel = (value >> FIFOWord::CHANNEL_SHIFT) as usize;
+        if value & FIFOWord::ADDRESS_BIT != 0 {
+            FIFOWord::Address(
+                channel,
+                value & FIFOWord::ADDRESS_MASK | FIFOWord::ADDRESS_BASE,
+            )
+        } else if value & FIFOWord::IMMEDIATE_BIT != 0 && value & FIFOWord::EXTRA_BIT != 0 {
+            FIFOWord::Extra(channel)
+        } else if value & FIFOWord::IMMEDIATE_BIT != 0 {
+            FIFOWord::Value32(channel, value & FIFOWord::VALUE32_MASK)
+        } else {
+            FIFOWord::Data(channel, value & FIFOWord::DATA_LENGTH_MASK)
+        }
+    }
+
+    // Words that follow a header belong to it, so each CPU's sends and receives are decoded in order
+    fn decode(trace: &[&IPCMessage]) -> Vec<Option<FIFOWord>> {
+        // Indexed by CPU and access, with the last header and how many words follow it
+        let mut following = [[(FIFOWord::Payload(0), 0); 2]; 2];
+        trace
+            .iter()
+            .map(|message| {
+                if message.access == IPCAccess::Sync || message.lost {
+                    return None;
+                }
+                let cpu = (message.cpu == CPU::ARM7) as usize;
+                let recv = (message.access == IPCAccess::Recv) as usize;
+                let (header, remaining) = &mut following[cpu][recv];
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Some(match *header {
+                        FIFOWord::Extra(channel) => FIFOWord::ExtraValue(channel),
+                        FIFOWord::Data(channel, _) => FIFOWord::Payload(channel),
+                        _ => unreachable!(),
+                    });
+                }
+                let word = FIFOWord::new(message.value);
+                *remaining = match word {
+                    FIFOWord::Extra(_) => 1,
+                    FIFOWord::Data(_, len) => (len as usize + 3) / 4,
+                    _ => 0,
+                };
+                *header = word;
+                Some(word)
+            })
+            .collect()
+    }
+
+    fn label(&self, value: u32) -> String {
+        let (channel, text) = match *self {
+            FIFOWord::Address(channel, addr) => (channel, format!("Address {:08X}", addr)),
+            FIFOWord::Value32(channel, value) => (channel, format!("Value {:07X}", value)),
+            FIFOWord::Extra(channel) => (channel, "Value follows".to_string()),
+            FIFOWord::ExtraValue(channel) => (channel, format!("Value {:08X}", value)),
+            FIFOWord::Data(channel, len) => (channel, format!("Data, {} bytes", len)),
+            FIFOWord::Payload(channel) => (channel, "Data".to_string()),
+        };
+        format!("{:<8} {}", FIFOWord::CHANNELS[channel], text)
+    }
+}
+
+pub struct IPCWindow {
+    opened: bool,
+    tracing: bool,
+    decode: bool,
+    lost_only: bool,
+    cpu: TraceFilter<CPU>,
+    access: TraceFilter<IPCAccess>,
+}
+
+impl IPCWindow {
+    const CPUS: [Option<CPU>; 3] = [None, Some(CPU::ARM9), Some(CPU::ARM7)];
+    const ACCESSES: [Option<IPCAccess>; 4] = [
+        None,
+        Some(IPCAccess::Send),
+        Some(IPCAccess::Recv),
+        Some(IPCAccess::Sync),
+    ];
+
+    pub fn new() -> Self {
+        IPCWindow {
+            opened: false,
+            tracing: true,
+            decode: true,
+            lost_only: false,
+            cpu: TraceFilter::new(im_str!("CPU"), &IPCWindow::CPUS),
+            access: TraceFilter::new(im_str!("Access"), &IPCWindow::ACCESSES),
+        }
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.opened && self.tracing
+    }
+
+    fn matches(&self, message: &IPCMessage) -> bool {
+        self.cpu.matches(message.cpu)
+            && self.access.matches(message.access)
+            && (!self.lost_only || message.lost)
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let mut opened = self.opened;
+        Window::new(im_str!("IPC Trace"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.checkbox(im_str!("Trace"), &mut self.tracing);
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Clear")) {
+                    nds.clear_ipc_trace();
+                }
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Decode libnds FIFO"), &mut self.decode);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Lost Only"), &mut self.lost_only);
+
+                let combo_width = ui.window_size()[0] * 0.25;
+                self.cpu.render(ui, combo_width);
+                ui.same_line(0.0);
+                self.access.render(ui, combo_width);
+
+                let trace: Vec<&IPCMessage> = nds.ipc_trace().iter().collect();
+                let words = if self.decode {
+                    FIFOWord::decode(&trace)
+                } else {
+                    vec![None; trace.len()]
+                };
+                let messages: Vec<(&IPCMessage, Option<FIFOWord>)> = trace
+                    .iter()
+                    .copied()
+                    .zip(words.into_iter())
+                    .filter(|(message, _)| self.matches(message))
+                    .collect();
+                let lost = trace.iter().filter(|message| message.lost).count();
+                ui.text(format!(
+                    "{} of {} accesses match, {} lost, newest first",
+                    messages.len(),
+                    trace.len(),
+                    lost
+                ));
+                ui.text("   Frame Line       Cycle CPU  Access Value    Pending Interrupt");
+                trace_list(ui, "Message List", &messages, |(message, word)| {
+                    let value = match message.access {
+                        IPCAccess::Sync => format!("{:X}", message.value),
+                        _ => format!("{:08X}", message.value),
+                    };
+                    let interrupt = match message.interrupt {
+                        Some(interrupt) => format!("{:?}", interrupt),
+                        None => String::new(),
+                    };
+                    let text = format!(
+                        "{:8} {:4} {:11} {:?} {:<6} {:<8} {:7} {:<16} {}{}",
+                        message.frame,
+                        message.vcount,
+                        message.cycle,
+                        message.cpu,
+                        format!("{:?}", message.access),
+                        value,
+                        message.pending,
+                        interrupt,
+                        if message.lost { "LOST " } else { "" },
+                        word.map_or(String::new(), |word| word.label(message.value)),
+                    );
+                    if message.lost {
+                        ui.text_colored([1.0, 0.4, 0.4, 1.0], text);
+                    } else {
+                        ui.text(text);
+                    }
+                });
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("IPC Trace"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use imgui::*;
+
+use super::{Engine, NDS};
+use nds_core::nds::LayerToggles;
+
+pub struct LayersMenu {
+    layers: [LayerToggles; 2],
+}
+
+impl LayersMenu {
+    const ENGINES: [Engine; 2] = [Engine::A, Engine::B];
+
+    pub fn new() -> Self {
+        LayersMenu {
+            layers: [LayerToggles::default(); 2],
+        }
+    }
+
+    // Toggles aren't saved in save states, so they're applied every frame
+    pub fn apply(&self, nds: &mut NDS) {
+        for (engine, layers) in LayersMenu::ENGINES.iter().zip(self.layers.iter()) {
+            nds.set_layer_toggles(*engine, *layers);
+        }
+    }
+
+    pub fn menu(&mut self, ui: &Ui) {
+        ui.menu(im_str!("Layers"), true, || {
+            for (engine, layers) in LayersMenu::ENGINES.iter().zip(self.layers.iter_mut()) {
+                let label = ImString::new(format!("Engine {}", engine.label()));
+                ui.menu(&label, true, || {
+                    for (bg_i, enabled) in layers.bgs.iter_mut().enumerate() {
+                        let label = ImString::new(format!("BG{}", bg_i));
+                        MenuItem::new(&label).build_with_ref(ui, enabled);
+                    }
+                    MenuItem::new(im_str!("OBJ")).build_with_ref(ui, &mut layers.obj);
+                    if *engine == Engine::A {
+                        MenuItem::new(im_str!("3D")).build_with_ref(ui, &mut layers.engine3d);
+                    }
+                    ui.separator();
+                    MenuItem::new(im_str!("Windows")).build_with_ref(ui, &mut layers.windows);
+                    MenuItem::new(im_str!("Blending")).build_with_ref(ui, &mut layers.blending);
+                    MenuItem::new(im_str!("Master Brightness"))
+                        .build_with_ref(ui, &mut layers.master_bright);
+                });
+            }
+            ui.separator();
+            if MenuItem::new(im_str!("Enable All")).build(ui) {
+                self.layers = [LayerToggles::default(); 2];
+            }
+        });
+    }
+}
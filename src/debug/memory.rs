@@ -0,0 +1,225 @@
+use std::borrow::Cow;
+
+use imgui::*;
+
+use super::NDS;
+use nds_core::nds::CPU;
+
+pub struct MemoryWindow {
+    opened: bool,
+    cpu: usize,
+    addr: u32,
+    goto_addr: ImString,
+    selected: Option<u32>,
+    value: ImString,
+    // Bytes shown at the end of the previous frame to highlight changes
+    snapshot: Vec<u8>,
+    snapshot_key: (usize, u32),
+    changed: Vec<bool>,
+    frame: usize,
+}
+
+impl MemoryWindow {
+    const CPUS: [CPU; 2] = [CPU::ARM9, CPU::ARM7];
+    const BYTES_PER_ROW: u32 = 0x10;
+    const ROWS: u32 = 0x20;
+    const PAGE_LEN: u32 = MemoryWindow::BYTES_PER_ROW * MemoryWindow::ROWS;
+    const CHANGED_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+
+    pub fn new() -> Self {
+        MemoryWindow {
+            opened: false,
+            cpu: 0,
+            addr: 0x0200_0000,
+            goto_addr: ImString::with_capacity(8),
+            selected: None,
+            value: ImString::with_capacity(2),
+            snapshot: Vec::new(),
+            snapshot_key: (0, 0),
+            changed: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    // The DTCM moves with CP15 so its shortcut is looked up every time
+    fn regions(cpu: CPU, nds: &NDS) -> Vec<(&'static str, u32)> {
+        match cpu {
+            CPU::ARM9 => vec![
+                ("Main RAM", 0x0200_0000),
+                ("ITCM", 0x0000_0000),
+                ("DTCM", nds.dtcm_base()),
+                ("Shared WRAM", 0x0300_0000),
+                ("IO", 0x0400_0000),
+                ("Palettes", 0x0500_0000),
+                ("VRAM", 0x0600_0000),
+                ("OAM", 0x0700_0000),
+            ],
+            CPU::ARM7 => vec![
+                ("Main RAM", 0x0200_0000),
+                ("Shared WRAM", 0x0300_0000),
+                ("IWRAM", 0x0380_0000),
+                ("IO", 0x0400_0000),
+                ("VRAM", 0x0600_0000),
+            ],
+        }
+    }
+
+    fn peek(cpu: CPU, nds: &NDS, addr: u32) -> u8 {
+        match cpu {
+            CPU::ARM9 => nds.peek9(addr),
+            CPU::ARM7 => nds.peek7(addr),
+        }
+    }
+
+    fn poke(cpu: CPU, nds: &mut NDS, addr: u32, value: u8) {
+        match cpu {
+            CPU::ARM9 => nds.poke9(addr, value),
+            CPU::ARM7 => nds.poke7(addr, value),
+        }
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let cpu = MemoryWindow::CPUS[self.cpu];
+        let bytes: Vec<u8> = (0..MemoryWindow::PAGE_LEN)
+            .map(|i| MemoryWindow::peek(cpu, nds, self.addr.wrapping_add(i)))
+            .collect();
+        if self.snapshot_key != (self.cpu, self.addr) {
+            self.snapshot = bytes.clone();
+            self.snapshot_key = (self.cpu, self.addr);
+            self.changed = vec![false; bytes.len()];
+        } else if self.frame != nds.frame() {
+            self.changed = self
+                .snapshot
+                .iter()
+                .zip(bytes.iter())
+                .map(|(old, new)| old != new)
+                .collect();
+            self.snapshot = bytes.clone();
+        }
+        self.frame = nds.frame();
+
+        let mut opened = self.opened;
+        Window::new(im_str!("Memory"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                let combo_width = ui.window_size()[0] * 0.2;
+                ui.set_next_item_width(combo_width);
+                let cpu_changed = ComboBox::new(im_str!("Bus")).build_simple(
+                    ui,
+                    &mut self.cpu,
+                    &MemoryWindow::CPUS,
+                    &(|cpu| Cow::from(ImString::new(format!("{:?}", cpu)))),
+                );
+                if cpu_changed {
+                    self.selected = None;
+                }
+
+                for (i, (name, addr)) in MemoryWindow::regions(cpu, nds).into_iter().enumerate() {
+                    if i != 0 {
+                        ui.same_line(0.0);
+                    }
+                    if ui.small_button(&ImString::new(name)) {
+                        self.addr = addr;
+                    }
+                }
+
+                ui.set_next_item_width(combo_width);
+                let goto = InputText::new(ui, im_str!("Go to"), &mut self.goto_addr)
+                    .chars_hexadecimal(true)
+                    .enter_returns_true(true)
+                    .build();
+                if goto {
+                    if let Ok(addr) = u32::from_str_radix(self.goto_addr.to_str(), 16) {
+                        self.addr = addr & !(MemoryWindow::BYTES_PER_ROW - 1);
+                        self.selected = Some(addr);
+                    }
+                }
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Page Up")) {
+                    self.addr = self.addr.wrapping_sub(MemoryWindow::PAGE_LEN);
+                }
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Page Down")) {
+                    self.addr = self.addr.wrapping_add(MemoryWindow::PAGE_LEN);
+                }
+                ui.separator();
+
+                for row in 0..MemoryWindow::ROWS {
+                    let row_addr = self.addr.wrapping_add(row * MemoryWindow::BYTES_PER_ROW);
+                    ui.text(format!("{:08X}", row_addr));
+                    for col in 0..MemoryWindow::BYTES_PER_ROW {
+                        let i = (row * MemoryWindow::BYTES_PER_ROW + col) as usize;
+                        let addr = row_addr.wrapping_add(col);
+                        ui.same_line(0.0);
+                        let color = if self.changed[i] {
+                            Some(ui.push_style_color(StyleColor::Text, MemoryWindow::CHANGED_COLOR))
+                        } else {
+                            None
+                        };
+                        let label = ImString::new(format!("{:02X}##{:X}", bytes[i], addr));
+                        let clicked = Selectable::new(&label)
+                            .selected(self.selected == Some(addr))
+                            .size(ui.calc_text_size(im_str!("00"), false, 0.0))
+                            .build(ui);
+                        if let Some(color) = color {
+                            color.pop(ui);
+                        }
+                        if clicked {
+                            self.selected = Some(addr);
+                            self.value = ImString::new(format!("{:02X}", bytes[i]));
+                        }
+                    }
+                    ui.same_line(0.0);
+                    let start = (row * MemoryWindow::BYTES_PER_ROW) as usize;
+                    let ascii: String = bytes[start..start + MemoryWindow::BYTES_PER_ROW as usize]
+                        .iter()
+                        .map(|&byte| {
+                            if byte.is_ascii_graphic() {
+                                byte as char
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect();
+                    ui.text(ascii);
+                }
+                // Mouse wheel scrolls by rows
+                if ui.is_window_hovered() {
+                    let rows = ui.io().mouse_wheel as i32;
+                    self.addr = self
+                        .addr
+                        .wrapping_sub((rows * MemoryWindow::BYTES_PER_ROW as i32) as u32);
+                }
+
+                if let Some(addr) = self.selected {
+                    ui.separator();
+                    ui.text(format!("{:08X}", addr));
+                    ui.same_line(0.0);
+                    ui.set_next_item_width(combo_width);
+                    let edited = InputText::new(ui, im_str!("Value"), &mut self.value)
+                        .chars_hexadecimal(true)
+                        .enter_returns_true(true)
+                        .build();
+                    if edited {
+                        if let Ok(value) = u8::from_str_radix(self.value.to_str(), 16) {
+                            MemoryWindow::poke(cpu, nds, addr, value);
+                        }
+                    }
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("Memory"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -1,11 +1,35 @@
+mod audio;
+mod cpu;
+mod dma;
+mod events;
+mod io;
+mod ipc;
+mod layers;
+mod memory;
+mod objs;
+mod scene;
+mod textures;
 mod windows;
 
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt::Debug;
 
 use glfw::Key;
 use imgui::*;
 
 use super::{Engine, GraphicsType, NDS};
+pub use audio::*;
+pub use cpu::*;
+pub use dma::*;
+pub use events::*;
+pub use io::*;
+pub use ipc::*;
+pub use layers::*;
+pub use memory::*;
+pub use objs::*;
+pub use scene::*;
+pub use textures::*;
 pub use windows::*;
 
 pub struct DebugWindow<S>
@@ -80,6 +104,60 @@ pub trait DebugWindowState {
     const GRAPHICS_TYPES: [GraphicsType; 2] = [GraphicsType::BG, GraphicsType::OBJ];
 }
 
+// A combo box filter for trace windows, the first option is None and matches anything
+struct TraceFilter<T: 'static> {
+    name: &'static ImStr,
+    options: &'static [Option<T>],
+    selected: usize,
+}
+
+impl<T: Copy + PartialEq + Debug> TraceFilter<T> {
+    fn new(name: &'static ImStr, options: &'static [Option<T>]) -> Self {
+        TraceFilter {
+            name,
+            options,
+            selected: 0,
+        }
+    }
+
+    fn label<'a>(option: &'a Option<T>) -> Cow<'a, ImStr> {
+        match option {
+            Some(value) => Cow::from(ImString::new(format!("{:?}", value))),
+            None => Cow::from(im_str!("Any")),
+        }
+    }
+
+    fn render(&mut self, ui: &Ui, width: f32) {
+        ui.set_next_item_width(width);
+        ComboBox::new(self.name).build_simple(
+            ui,
+            &mut self.selected,
+            self.options,
+            &TraceFilter::label,
+        );
+    }
+
+    fn matches(&self, value: T) -> bool {
+        self.options[self.selected].map_or(true, |option| option == value)
+    }
+}
+
+const TRACE_LIST_HEIGHT: f32 = 300.0;
+// Traces hold far more entries than can be listed without slowing down the UI
+const TRACE_MAX_ROWS: usize = 1000;
+
+// Lists the newest entries first
+fn trace_list<T>(ui: &Ui, id: &str, entries: &[T], mut row: impl FnMut(&T)) {
+    ChildWindow::new(id)
+        .size([0.0, TRACE_LIST_HEIGHT])
+        .border(true)
+        .build(ui, || {
+            for entry in entries.iter().rev().take(TRACE_MAX_ROWS) {
+                row(entry);
+            }
+        });
+}
+
 struct Texture {
     tex: u32,
     width: f32,
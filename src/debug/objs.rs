@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+
+use imgui::*;
+
+use super::{Engine, Texture, NDS};
+use nds_core::nds::{self, OBJInfo};
+
+pub struct OBJWindow {
+    opened: bool,
+    engine: usize,
+    selected: Option<usize>,
+    hide_disabled: bool,
+    texture: Texture,
+}
+
+impl OBJWindow {
+    const ENGINES: [Engine; 2] = [Engine::A, Engine::B];
+    const SHAPES: [&'static str; 4] = ["Square", "Horizontal", "Vertical", "Prohibited"];
+    const LIST_HEIGHT: f32 = 250.0;
+    const SCALE: f32 = 4.0;
+    const HIGHLIGHT_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+    pub fn new() -> Self {
+        OBJWindow {
+            opened: false,
+            engine: 0,
+            selected: None,
+            hide_disabled: true,
+            texture: Texture::new(),
+        }
+    }
+
+    // screens are the top left corner and scale of the screens showing engines A and B
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui, screens: [([f32; 2], f32); 2]) {
+        if !self.opened {
+            return;
+        }
+        let engine = OBJWindow::ENGINES[self.engine];
+        let objs = nds.objs(engine);
+        if let Some(i) = self.selected {
+            let (pixels, width, height) = nds.render_obj(engine, &objs[i]);
+            self.texture.update_pixels(pixels, width, height);
+        }
+
+        let mut opened = self.opened;
+        Window::new(im_str!("OBJs"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.set_next_item_width(ui.window_size()[0] * 0.3);
+                let engine_changed = ComboBox::new(im_str!("Engine")).build_simple(
+                    ui,
+                    &mut self.engine,
+                    &OBJWindow::ENGINES,
+                    &(|engine| Cow::from(ImString::new(engine.label()))),
+                );
+                if engine_changed {
+                    self.selected = None;
+                }
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Hide Disabled"), &mut self.hide_disabled);
+
+                ChildWindow::new("OBJ List")
+                    .size([0.0, OBJWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for (i, obj) in objs.iter().enumerate() {
+                            if self.hide_disabled && obj.disabled {
+                                continue;
+                            }
+                            let label = ImString::new(format!(
+                                "{:3} {:4},{:3} {:2}x{:2} {:<15} prio {} tile {:03X}{}",
+                                i,
+                                obj.x,
+                                obj.y,
+                                obj.width,
+                                obj.height,
+                                format!("{:?}", obj.mode),
+                                obj.priority,
+                                obj.tile,
+                                if obj.disabled { " disabled" } else { "" },
+                            ));
+                            let clicked = Selectable::new(&label)
+                                .selected(self.selected == Some(i))
+                                .build(ui);
+                            if clicked {
+                                self.selected = if self.selected == Some(i) {
+                                    None
+                                } else {
+                                    Some(i)
+                                };
+                            }
+                        }
+                    });
+
+                if let Some(i) = self.selected {
+                    ui.separator();
+                    OBJWindow::render_obj(&objs[i], ui);
+                    self.texture.render(OBJWindow::SCALE).build(ui);
+                }
+            });
+        self.opened = opened;
+
+        if let Some(i) = self.selected {
+            let obj = &objs[i];
+            if !obj.disabled {
+                OBJWindow::outline(obj, ui, screens[self.engine]);
+            }
+        }
+    }
+
+    fn render_obj(obj: &OBJInfo, ui: &Ui) {
+        let flag = |set: bool| if set { "yes" } else { "no" };
+        ui.text(format!(
+            "Position {}, {}, {} {}x{} (shape {}, size {})",
+            obj.x,
+            obj.y,
+            OBJWindow::SHAPES[obj.shape as usize],
+            obj.width,
+            obj.height,
+            obj.shape,
+            obj.size
+        ));
+        ui.text(format!(
+            "Mode {:?}, priority {}, mosaic {}, disabled {}",
+            obj.mode,
+            obj.priority,
+            flag(obj.mosaic),
+            flag(obj.disabled)
+        ));
+        ui.text(format!(
+            "Tile {:03X}, {}, palette {}",
+            obj.tile,
+            if obj.bpp8 { "256 colors" } else { "16 colors" },
+            obj.palette
+        ));
+        if obj.affine {
+            let params = obj.affine_params;
+            ui.text(format!(
+                "Affine group {}, double size {}",
+                obj.affine_group,
+                flag(obj.double_size)
+            ));
+            ui.text(format!(
+                "PA {:8.3} PB {:8.3} PC {:8.3} PD {:8.3}",
+                params[0], params[1], params[2], params[3]
+            ));
+        } else {
+            ui.text(format!(
+                "Flip X {}, flip Y {}",
+                flag(obj.flip[0]),
+                flag(obj.flip[1])
+            ));
+        }
+    }
+
+    fn outline(obj: &OBJInfo, ui: &Ui, screen: ([f32; 2], f32)) {
+        let (origin, scale) = screen;
+        let (width, height) = obj.bounds();
+        // OBJs that go past the bottom of the 256 line area wrap to the top
+        let y = if obj.y as usize + height > 256 {
+            obj.y as i32 - 256
+        } else {
+            obj.y as i32
+        };
+        let corner = |x: i32, y: i32| [origin[0] + x as f32 * scale, origin[1] + y as f32 * scale];
+        let draw_list = ui.get_background_draw_list();
+        let screen_end = corner(nds::WIDTH as i32, nds::HEIGHT as i32);
+        draw_list.with_clip_rect_intersect(origin, screen_end, || {
+            draw_list
+                .add_rect(
+                    corner(obj.x as i32, y),
+                    corner(obj.x as i32 + width as i32, y + height as i32),
+                    OBJWindow::HIGHLIGHT_COLOR,
+                )
+                .thickness(2.0)
+                .build();
+        });
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("OBJs"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use imgui::*;
+
+use super::NDS;
+use nds_core::nds::{PolygonSnapshot, SceneSnapshot, TextureFormat};
+
+pub struct SceneWindow {
+    opened: bool,
+    selected: Option<usize>,
+    dump_path: ImString,
+    status: String,
+}
+
+impl SceneWindow {
+    const LIST_HEIGHT: f32 = 200.0;
+    const HIGHLIGHT_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+    pub fn new() -> Self {
+        let mut dump_path = ImString::with_capacity(256);
+        dump_path.push_str("frame.ndsg");
+        SceneWindow {
+            opened: false,
+            selected: None,
+            dump_path,
+            status: String::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.opened
+    }
+
+    // screen is the top left corner of the screen showing engine A and its scale
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui, screen: ([f32; 2], f32)) {
+        if !self.opened {
+            return;
+        }
+        if let Some(dump) = nds.take_geometry_dump() {
+            let path = self.dump_path.to_str();
+            self.status = match std::fs::write(path, dump.to_bytes()) {
+                Ok(()) => format!("Recorded {} writes to {}", dump.num_writes(), path),
+                Err(err) => format!("Unable to write geometry dump: {}", err),
+            };
+        }
+        let recording = nds.is_recording_geometry();
+        let mut record = false;
+        let mut opened = self.opened;
+        // Nothing has been captured until the next 3D frame renders
+        let no_snapshot = SceneSnapshot::default();
+        let snapshot = nds.scene_snapshot().unwrap_or(&no_snapshot);
+        if let Some(selected) = self.selected {
+            if selected >= snapshot.polygons.len() {
+                self.selected = None;
+            }
+        }
+        Window::new(im_str!("3D Scene"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                let vertex_count: usize = snapshot
+                    .polygons
+                    .iter()
+                    .map(|polygon| polygon.vertices.len())
+                    .sum();
+                ui.text(format!(
+                    "{} polygons, {} vertices",
+                    snapshot.polygons.len(),
+                    vertex_count
+                ));
+                InputText::new(ui, im_str!("Dump Path"), &mut self.dump_path).build();
+                if recording {
+                    ui.text("Recording the next 3D frame...");
+                } else if ui.small_button(im_str!("Record Frame")) {
+                    record = true;
+                }
+                if !self.status.is_empty() {
+                    ui.text(&self.status);
+                }
+                ChildWindow::new("Polygon List")
+                    .size([0.0, SceneWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for (i, polygon) in snapshot.polygons.iter().enumerate() {
+                            let label = ImString::new(format!(
+                                "{:4} {:?} alpha {:2} id {:2} {} verts {:?}",
+                                i,
+                                polygon.mode,
+                                polygon.alpha,
+                                polygon.id,
+                                polygon.vertices.len(),
+                                polygon.texture.format,
+                            ));
+                            let clicked = Selectable::new(&label)
+                                .selected(self.selected == Some(i))
+                                .build(ui);
+                            if clicked {
+                                self.selected = if self.selected == Some(i) {
+                                    None
+                                } else {
+                                    Some(i)
+                                };
+                            }
+                        }
+                    });
+
+                if let Some(i) = self.selected {
+                    ui.separator();
+                    SceneWindow::render_polygon(&snapshot.polygons[i], ui);
+                }
+
+                if CollapsingHeader::new(im_str!("Matrices")).build(ui) {
+                    let matrices = &snapshot.matrices;
+                    ui.text(format!(
+                        "Stack levels: projection {}, position {}, texture {}",
+                        matrices.projection_level, matrices.position_level, matrices.texture_level
+                    ));
+                    for (name, matrix) in [
+                        ("Projection", &matrices.projection),
+                        ("Position", &matrices.position),
+                        ("Vector", &matrices.vector),
+                        ("Texture", &matrices.texture),
+                    ]
+                    .iter()
+                    {
+                        ui.text(name);
+                        for row in matrix.chunks(4) {
+                            ui.text(format!(
+                                "{:10.4} {:10.4} {:10.4} {:10.4}",
+                                row[0], row[1], row[2], row[3]
+                            ));
+                        }
+                    }
+                }
+            });
+        self.opened = opened;
+
+        if let Some(i) = self.selected {
+            let (origin, scale) = screen;
+            let points: Vec<[f32; 2]> = snapshot.polygons[i]
+                .vertices
+                .iter()
+                .map(|vert| {
+                    [
+                        origin[0] + (vert.screen_coords[0] as f32 + 0.5) * scale,
+                        origin[1] + (vert.screen_coords[1] as f32 + 0.5) * scale,
+                    ]
+                })
+                .collect();
+            let draw_list = ui.get_background_draw_list();
+            for (i, &point) in points.iter().enumerate() {
+                let next = points[(i + 1) % points.len()];
+                draw_list
+                    .add_line(point, next, SceneWindow::HIGHLIGHT_COLOR)
+                    .thickness(2.0)
+                    .build();
+            }
+        }
+        // The snapshot borrows nds until here
+        if record {
+            nds.record_geometry_frame();
+        }
+    }
+
+    fn render_polygon(polygon: &PolygonSnapshot, ui: &Ui) {
+        let flag = |set: bool| if set { "yes" } else { "no" };
+        ui.text(format!(
+            "Mode {:?}, alpha {}, ID {}, {} facing",
+            polygon.mode,
+            polygon.alpha,
+            polygon.id,
+            if polygon.is_front { "front" } else { "back" }
+        ));
+        ui.text(format!(
+            "Render front {}, back {}, fog {}, depth test equal {}",
+            flag(polygon.render_front),
+            flag(polygon.render_back),
+            flag(polygon.fog),
+            flag(polygon.depth_test_eq)
+        ));
+        let lights: Vec<String> = polygon
+            .lights_enabled
+            .iter()
+            .enumerate()
+            .filter(|(_, &enabled)| enabled)
+            .map(|(i, _)| i.to_string())
+            .collect();
+        ui.text(format!("Lights [{}]", lights.join(", ")));
+        let texture = &polygon.texture;
+        if texture.format == TextureFormat::NoTexture {
+            ui.text("No texture");
+        } else {
+            ui.text(format!(
+                "Texture {:?} {}x{} at {:05X}, palette {:05X}",
+                texture.format,
+                texture.size[0],
+                texture.size[1],
+                texture.vram_offset,
+                texture.palette_base
+            ));
+            ui.text(format!(
+                "Repeat S {} T {}, flip S {} T {}, color 0 transparent {}",
+                flag(polygon.tex_repeat[0]),
+                flag(polygon.tex_repeat[1]),
+                flag(polygon.tex_flip[0]),
+                flag(polygon.tex_flip[1]),
+                flag(texture.color0_transparent)
+            ));
+        }
+        ui.text("   X   Y    Depth      W  Color   S        T");
+        for vert in polygon.vertices.iter() {
+            ui.text(format!(
+                "{:4}{:4} {:8X} {:6} {:02X}{:02X}{:02X} {:8.3} {:8.3}",
+                vert.screen_coords[0],
+                vert.screen_coords[1],
+                vert.depth,
+                vert.w,
+                vert.color[0],
+                vert.color[1],
+                vert.color[2],
+                vert.tex_coord[0] as f32 / 16.0,
+                vert.tex_coord[1] as f32 / 16.0,
+            ));
+        }
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("3D Scene"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
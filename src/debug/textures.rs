@@ -0,0 +1,187 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use imgui::*;
+
+use super::{Texture, NDS};
+use nds_core::nds::{SceneSnapshot, TextureInfo};
+
+pub struct TexturesWindow {
+    opened: bool,
+    selected: Option<usize>,
+    texture: Texture,
+    export_dir: ImString,
+    status: String,
+}
+
+impl TexturesWindow {
+    const LIST_HEIGHT: f32 = 200.0;
+    const SCALE: f32 = 2.0;
+    const TEXTURE_SLOT_LEN: usize = 0x2_0000;
+    const PALETTE_SLOT_LEN: usize = 0x4000;
+
+    pub fn new() -> Self {
+        let mut export_dir = ImString::with_capacity(256);
+        export_dir.push_str("textures");
+        TexturesWindow {
+            opened: false,
+            selected: None,
+            texture: Texture::new(),
+            export_dir,
+            status: String::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.opened
+    }
+
+    fn describe(texture: &TextureInfo) -> String {
+        format!(
+            "{:?} {}x{} slot {} + {:05X}, palette slot {} + {:04X}",
+            texture.format,
+            texture.size[0],
+            texture.size[1],
+            texture.vram_offset / TexturesWindow::TEXTURE_SLOT_LEN,
+            texture.vram_offset % TexturesWindow::TEXTURE_SLOT_LEN,
+            texture.palette_base / TexturesWindow::PALETTE_SLOT_LEN,
+            texture.palette_base % TexturesWindow::PALETTE_SLOT_LEN,
+        )
+    }
+
+    fn file_name(texture: &TextureInfo) -> String {
+        format!(
+            "texture_{:05X}_{:05X}_{:?}_{}x{}.png",
+            texture.vram_offset,
+            texture.palette_base,
+            texture.format,
+            texture.size[0],
+            texture.size[1],
+        )
+    }
+
+    fn export(
+        snapshot: &SceneSnapshot,
+        dir: &Path,
+        textures: &[TextureInfo],
+    ) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        for texture in textures.iter() {
+            let (pixels, width, height) = snapshot.decode_texture(texture);
+            let file = fs::File::create(dir.join(TexturesWindow::file_name(texture)))
+                .map_err(|err| err.to_string())?;
+            let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+            encoder.set_color(png::ColorType::RGBA);
+            encoder.set_depth(png::BitDepth::Eight);
+            let data: Vec<u8> = pixels.iter().flatten().copied().collect();
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&data))
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn render(&mut self, nds: &mut NDS, ui: &Ui) {
+        if !self.opened {
+            return;
+        }
+        let no_snapshot = SceneSnapshot::default();
+        let snapshot = nds.scene_snapshot().unwrap_or(&no_snapshot);
+        let textures = snapshot.textures();
+        if let Some(selected) = self.selected {
+            if selected >= textures.len() {
+                self.selected = None;
+            }
+        }
+        if let Some(i) = self.selected {
+            let (pixels, width, height) = snapshot.decode_texture(&textures[i]);
+            // Partially transparent texels are shown as opaque
+            let pixels = pixels
+                .iter()
+                .map(|&[r, g, b, a]| {
+                    (r as u16 >> 3)
+                        | (g as u16 >> 3) << 5
+                        | (b as u16 >> 3) << 10
+                        | if a == 0 { 0 } else { 0x8000 }
+                })
+                .collect();
+            self.texture.update_pixels(pixels, width, height);
+        }
+
+        let mut opened = self.opened;
+        Window::new(im_str!("Textures"))
+            .always_auto_resize(true)
+            .opened(&mut opened)
+            .build(ui, || {
+                ui.text(format!(
+                    "{} textures used by the last 3D frame",
+                    textures.len()
+                ));
+                ChildWindow::new("Texture List")
+                    .size([0.0, TexturesWindow::LIST_HEIGHT])
+                    .border(true)
+                    .build(ui, || {
+                        for (i, texture) in textures.iter().enumerate() {
+                            let label = ImString::new(format!(
+                                "{:3} {}",
+                                i,
+                                TexturesWindow::describe(texture)
+                            ));
+                            let clicked = Selectable::new(&label)
+                                .selected(self.selected == Some(i))
+                                .build(ui);
+                            if clicked {
+                                self.selected = Some(i);
+                            }
+                        }
+                    });
+
+                InputText::new(ui, im_str!("Export Directory"), &mut self.export_dir).build();
+                let dir = Path::new(self.export_dir.to_str());
+                let export = if ui.small_button(im_str!("Export All")) {
+                    Some(&textures[..])
+                } else {
+                    None
+                };
+                let export = match self.selected {
+                    Some(i) => {
+                        ui.same_line(0.0);
+                        if ui.small_button(im_str!("Export Selected")) {
+                            Some(&textures[i..=i])
+                        } else {
+                            export
+                        }
+                    }
+                    None => export,
+                };
+                if let Some(export) = export {
+                    self.status = match TexturesWindow::export(snapshot, dir, export) {
+                        Ok(()) => {
+                            format!("Exported {} textures to {}", export.len(), dir.display())
+                        }
+                        Err(err) => format!("Unable to export textures: {}", err),
+                    };
+                }
+                if !self.status.is_empty() {
+                    ui.text(&self.status);
+                }
+
+                if self.selected.is_some() {
+                    ui.separator();
+                    self.texture.render(TexturesWindow::SCALE).build(ui);
+                }
+            });
+        self.opened = opened;
+    }
+
+    pub fn menu_item(&mut self, ui: &Ui) {
+        let clicked = MenuItem::new(im_str!("Textures"))
+            .selected(self.opened)
+            .build(ui);
+        if clicked {
+            self.opened = !self.opened
+        }
+    }
+}
@@ -24,14 +24,13 @@ pub struct Display {
 impl Display {
     const WIDTH: usize = nds::WIDTH;
     const HEIGHT: usize = 2 * nds::HEIGHT;
-    const SCALE: usize = 1;
 
-    pub fn new(imgui: &mut imgui::Context) -> Display {
+    pub fn new(imgui: &mut imgui::Context, scale: usize) -> Display {
         let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
         glfw.set_error_callback(glfw::FAIL_ON_ERRORS);
 
-        let width = (Display::WIDTH * Display::SCALE) as u32;
-        let height = 19 + (Display::HEIGHT * Display::SCALE) as u32; // TODO: Don't hardcode main menu bar height
+        let width = (Display::WIDTH * scale) as u32;
+        let height = 19 + (Display::HEIGHT * scale) as u32; // TODO: Don't hardcode main menu bar height
         let (mut window, events) = glfw
             .create_window(width, height, "GBA Emulator", glfw::WindowMode::Windowed)
             .expect("Failed to create GLFW window!");
@@ -189,25 +188,7 @@ impl Display {
         main_menu_height: f32,
     ) -> (HashSet<glfw::Key>, Vec<PathBuf>) {
         let screens = nds.get_screens();
-        let (width, height) = self.window.get_size();
-        let height = height - main_menu_height as i32;
-
-        let (tex_x, tex_y) = if width * Display::HEIGHT as i32 > height * Display::WIDTH as i32 {
-            let scaled_width =
-                (Display::WIDTH as f32 / Display::HEIGHT as f32 * height as f32) as i32;
-            ((width - scaled_width) / 2, 0)
-        } else if width * (Display::HEIGHT as i32) < height * Display::WIDTH as i32 {
-            let scaled_height =
-                (Display::HEIGHT as f32 / Display::WIDTH as f32 * width as f32) as i32;
-            (0, (height - scaled_height) / 2)
-        } else {
-            (0, 0)
-        };
-
-        let x_start = tex_x;
-        let y_start = tex_y;
-        let x_end = width - tex_x;
-        let y_end = height - tex_y;
+        let (x_start, y_start, x_end, y_end) = self.screen_bounds(main_menu_height);
 
         unsafe {
             gl::ClearColor(0.0, 0.0, 0.0, 1.0);
@@ -323,6 +304,39 @@ impl Display {
         (keys_pressed, files_dropped)
     }
 
+    // Where the screens are drawn in the window below the main menu, as start and end coordinates
+    fn screen_bounds(&self, main_menu_height: f32) -> (i32, i32, i32, i32) {
+        let (width, height) = self.window.get_size();
+        let height = height - main_menu_height as i32;
+
+        let (tex_x, tex_y) = if width * Display::HEIGHT as i32 > height * Display::WIDTH as i32 {
+            let scaled_width =
+                (Display::WIDTH as f32 / Display::HEIGHT as f32 * height as f32) as i32;
+            ((width - scaled_width) / 2, 0)
+        } else if width * (Display::HEIGHT as i32) < height * Display::WIDTH as i32 {
+            let scaled_height =
+                (Display::HEIGHT as f32 / Display::WIDTH as f32 * width as f32) as i32;
+            (0, (height - scaled_height) / 2)
+        } else {
+            (0, 0)
+        };
+
+        (tex_x, tex_y, width - tex_x, height - tex_y)
+    }
+
+    // Top left corner of a screen in imgui coordinates and the number of pixels per NDS pixel
+    pub fn screen_rect(&self, top: bool, main_menu_height: f32) -> ([f32; 2], f32) {
+        let (x_start, y_start, x_end, _) = self.screen_bounds(main_menu_height);
+        let scale = (x_end - x_start) as f32 / Display::WIDTH as f32;
+        let y = main_menu_height + y_start as f32;
+        let y = if top {
+            y
+        } else {
+            y + nds::HEIGHT as f32 * scale
+        };
+        ([x_start as f32, y], scale)
+    }
+
     pub fn render_imgui<F>(
         &mut self,
         imgui: &mut imgui::Context,
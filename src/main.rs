@@ -1,39 +1,88 @@
+mod audio;
 mod debug;
 mod display;
 
+use std::error::Error;
 use std::fs;
 use std::path::PathBuf;
 
+use nds_core::gdb::GdbStub;
 use nds_core::log::*;
-use nds_core::nds::{Engine, GraphicsType, NDS};
+use nds_core::nds::{BootMode, Engine, GraphicsType, Movie, NDSBuilder, StopReason, NDS};
 use nds_core::simplelog::*;
+use structopt::StructOpt;
 
+use audio::CpalSink;
 use debug::*;
 use display::Display;
 use imgui::*;
 
-fn main() {
-    let rom_path = PathBuf::from("examples/3D/BoxTest.nds");
-    let bios7_path = PathBuf::from("bios7.bin");
-    let bios9_path = PathBuf::from("bios9.bin");
-    let firmware_path = PathBuf::from("firmware.bin");
+#[derive(StructOpt)]
+#[structopt(name = "nds-emulator", about = "A Nintendo DS emulator")]
+struct Args {
+    /// ROM to load
+    #[structopt(parse(from_os_str))]
+    rom: PathBuf,
+    #[structopt(long, default_value = "bios7.bin", parse(from_os_str))]
+    bios7: PathBuf,
+    #[structopt(long, default_value = "bios9.bin", parse(from_os_str))]
+    bios9: PathBuf,
+    #[structopt(long, default_value = "firmware.bin", parse(from_os_str))]
+    firmware: PathBuf,
+    /// Boots through the firmware (needs BIOS and firmware dumps) or directly into the ROM
+    #[structopt(long, default_value = "direct")]
+    boot: BootMode,
+    /// Save file, defaults to the ROM path with a .sav extension
+    #[structopt(long, parse(from_os_str))]
+    save: Option<PathBuf>,
+    /// Instruction trace level for the ARM7 (off, error, warn, info, debug, trace)
+    #[structopt(long, default_value = "off")]
+    trace7: LevelFilter,
+    /// Instruction trace level for the ARM9 (off, error, warn, info, debug, trace)
+    #[structopt(long, default_value = "off")]
+    trace9: LevelFilter,
+    #[structopt(long, default_value = "arm7.log", parse(from_os_str))]
+    trace7_file: PathBuf,
+    #[structopt(long, default_value = "arm9.log", parse(from_os_str))]
+    trace9_file: PathBuf,
+    /// Start with emulation paused
+    #[structopt(long)]
+    paused: bool,
+    /// Initial window size as a multiple of the screen size
+    #[structopt(long, default_value = "1")]
+    scale: usize,
+    /// Records inputs from power on and writes the movie on exit
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    /// Plays back a recorded movie
+    #[structopt(long, parse(from_os_str))]
+    play: Option<PathBuf>,
+    /// Listens for GDB on the given local port, the ARM9 is thread 1 and the ARM7 is thread 2
+    #[structopt(long)]
+    gdb: Option<u16>,
+}
 
-    std::env::set_current_dir("ROMs").unwrap();
-    let arm7_file_name = "arm7.log";
-    let arm9_file_name = "arm9.log";
-    let instructions7_filter = LevelFilter::Off;
-    let instructions9_filter = LevelFilter::Off;
+fn main() {
+    let args = Args::from_args();
 
-    let arm7_file = fs::File::create(arm7_file_name);
-    let arm9_file = fs::File::create(arm9_file_name);
+    let arm7_file = if args.trace7 != LevelFilter::Off {
+        Some(fs::File::create(&args.trace7_file).unwrap())
+    } else {
+        None
+    };
+    let arm9_file = if args.trace9 != LevelFilter::Off {
+        Some(fs::File::create(&args.trace9_file).unwrap())
+    } else {
+        None
+    };
     let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
         LevelFilter::Warn,
         Config::default(),
         TerminalMode::Mixed,
     )];
-    if let Ok(file) = arm7_file {
+    if let Some(file) = arm7_file {
         loggers.push(WriteLogger::new(
-            instructions7_filter,
+            args.trace7,
             ConfigBuilder::new()
                 .set_time_level(LevelFilter::Off)
                 .set_thread_level(LevelFilter::Off)
@@ -46,9 +95,9 @@ fn main() {
             file,
         ));
     }
-    if let Ok(file) = arm9_file {
+    if let Some(file) = arm9_file {
         loggers.push(WriteLogger::new(
-            instructions9_filter,
+            args.trace9,
             ConfigBuilder::new()
                 .set_time_level(LevelFilter::Off)
                 .set_thread_level(LevelFilter::Off)
@@ -64,32 +113,91 @@ fn main() {
     CombinedLogger::init(loggers).unwrap();
 
     let mut imgui = Context::create();
-    let mut display = Display::new(&mut imgui);
+    let mut display = Display::new(&mut imgui, args.scale);
 
-    let mut nds = load_rom(&bios7_path, &bios9_path, &firmware_path, &rom_path);
+    let save_path = args
+        .save
+        .clone()
+        .unwrap_or_else(|| args.rom.with_extension("sav"));
+    let movie = args.play.as_ref().map(|path| {
+        let data = fs::read(path).unwrap_or_else(|err| panic!("Unable to read movie: {}", err));
+        Movie::from_bytes(&data).unwrap_or_else(|err| panic!("Unable to load movie: {}", err))
+    });
+    let mut nds = load_rom(&args, &args.rom, save_path, movie)
+        .unwrap_or_else(|err| panic!("Unable to load ROM: {}", err));
+    let mut paused = args.paused;
+    let mut gdb = args.gdb.map(|port| {
+        GdbStub::new(port).unwrap_or_else(|err| panic!("Unable to start GDB stub: {}", err))
+    });
 
     let mut main_menu_height = 0.0;
     let mut palettes_window = DebugWindow::<PalettesWindowState>::new("Palettes");
     let mut maps_window = DebugWindow::<MapsWindowState>::new("Maps");
     let mut tiles_window = DebugWindow::<TilesWindowState>::new("Tiles");
     let mut vram_window = DebugWindow::<VRAMWindowState>::new("VRAM");
+    let mut memory_window = MemoryWindow::new();
+    let mut cpu_window = CPUWindow::new();
+    let mut io_window = IOWindow::new();
+    let mut events_window = EventsWindow::new();
+    let mut audio_window = AudioWindow::new();
+    let mut dma_window = DMAWindow::new();
+    let mut ipc_window = IPCWindow::new();
+    let mut layers_menu = LayersMenu::new();
+    let mut objs_window = OBJWindow::new();
+    let mut scene_window = SceneWindow::new();
+    let mut textures_window = TexturesWindow::new();
     let mut stats_window = StatsWindow::new();
 
     while !display.should_close() {
-        nds.emulate_frame();
-        stats_window.frame_completed();
+        match &mut gdb {
+            Some(gdb) if paused => gdb.poll(&mut nds),
+            Some(gdb) => match gdb.emulate_frame(&mut nds) {
+                Some(StopReason::Frame) => stats_window.frame_completed(),
+                Some(reason) => cpu_window.stopped(&mut nds, reason, &mut paused),
+                None => (),
+            },
+            None if paused => (),
+            None => match nds.run_until(nds.frame() + 1) {
+                StopReason::Frame => stats_window.frame_completed(),
+                reason => cpu_window.stopped(&mut nds, reason, &mut paused),
+            },
+        }
 
         let (keys_pressed, files_dropped) =
             display.render_main(&mut nds, &mut imgui, main_menu_height);
+        let screens = [
+            display.screen_rect(nds.engine_a_on_top(), main_menu_height),
+            display.screen_rect(!nds.engine_a_on_top(), main_menu_height),
+        ];
+        nds.set_capture_scene(scene_window.is_open() || textures_window.is_open());
+        nds.set_record_events(events_window.is_open());
+        nds.set_track_audio_peaks(audio_window.is_open());
+        nds.set_trace_dmas(dma_window.is_tracing());
+        nds.set_trace_ipc(ipc_window.is_tracing());
+        layers_menu.apply(&mut nds);
         display.render_imgui(&mut imgui, keys_pressed, |ui, keys_pressed| {
             ui.main_menu_bar(|| {
+                ui.menu(im_str!("Emulation"), true, || {
+                    MenuItem::new(im_str!("Paused")).build_with_ref(ui, &mut paused);
+                });
                 ui.menu(im_str!("Debug Windows"), true, || {
                     palettes_window.menu_item(ui);
                     maps_window.menu_item(ui);
                     tiles_window.menu_item(ui);
                     vram_window.menu_item(ui);
+                    memory_window.menu_item(ui);
+                    cpu_window.menu_item(ui);
+                    io_window.menu_item(ui);
+                    events_window.menu_item(ui);
+                    audio_window.menu_item(ui);
+                    dma_window.menu_item(ui);
+                    ipc_window.menu_item(ui);
+                    objs_window.menu_item(ui);
+                    scene_window.menu_item(ui);
+                    textures_window.menu_item(ui);
                     stats_window.menu_item(ui);
                 });
+                layers_menu.menu(ui);
                 main_menu_height = ui.window_size()[1];
             });
 
@@ -97,6 +205,16 @@ fn main() {
             maps_window.render(&mut nds, ui, &keys_pressed);
             tiles_window.render(&mut nds, ui, &keys_pressed);
             vram_window.render(&mut nds, ui, &keys_pressed);
+            memory_window.render(&mut nds, ui);
+            cpu_window.render(&mut nds, ui, &mut paused);
+            io_window.render(&mut nds, ui);
+            events_window.render(&mut nds, ui);
+            audio_window.render(&mut nds, ui);
+            dma_window.render(&mut nds, ui);
+            ipc_window.render(&mut nds, ui);
+            objs_window.render(&mut nds, ui, screens);
+            scene_window.render(&mut nds, ui, screens[0]);
+            textures_window.render(&mut nds, ui);
             stats_window.render(ui);
         });
 
@@ -104,7 +222,11 @@ fn main() {
             if let Some(ext) = files_dropped[0].extension() {
                 if let Some(str) = ext.to_str() {
                     if str.to_lowercase() == "nds" {
-                        nds = load_rom(&bios7_path, &bios9_path, &firmware_path, &files_dropped[0]);
+                        let save_path = files_dropped[0].with_extension("sav");
+                        match load_rom(&args, &files_dropped[0], save_path, None) {
+                            Ok(new_nds) => nds = new_nds,
+                            Err(err) => error!("Unable to load ROM: {}", err),
+                        }
                     } else {
                         error!("File is not a .nds file!")
                     }
@@ -117,18 +239,49 @@ fn main() {
         }
     }
 
+    if let (Some(path), Some(movie)) = (&args.record, nds.stop_recording()) {
+        fs::write(path, movie.to_bytes())
+            .unwrap_or_else(|err| error!("Unable to write movie: {}", err));
+    }
+
     fn load_rom(
-        bios7_path: &PathBuf,
-        bios9_path: &PathBuf,
-        firmware_path: &PathBuf,
+        args: &Args,
         rom_path: &PathBuf,
-    ) -> NDS {
-        NDS::new(
-            fs::read(bios7_path).unwrap(),
-            fs::read(bios9_path).unwrap(),
-            fs::read(firmware_path).unwrap(),
-            fs::read(rom_path).unwrap(),
-            rom_path.with_extension("sav"),
-        )
+        save_path: PathBuf,
+        movie: Option<Movie>,
+    ) -> Result<NDS, Box<dyn Error>> {
+        let firmware_boot = args.boot == BootMode::Firmware;
+        // Fall back to the HLE BIOS when no dumps are available and booting directly
+        let (bios7, bios9, hle_bios) = match (fs::read(&args.bios7), fs::read(&args.bios9)) {
+            (Ok(bios7), Ok(bios9)) => (bios7, bios9, false),
+            (Err(err), _) | (_, Err(err)) if firmware_boot => {
+                return Err(format!("Firmware boot needs BIOS dumps: {}", err).into())
+            }
+            _ => {
+                info!("BIOS dumps not found, using HLE BIOS");
+                (Vec::new(), Vec::new(), true)
+            }
+        };
+        let firmware = match fs::read(&args.firmware) {
+            Ok(firmware) => firmware,
+            Err(err) if firmware_boot => {
+                return Err(format!("Firmware boot needs a firmware dump: {}", err).into())
+            }
+            Err(_) => {
+                info!("Firmware dump not found, generating one");
+                Vec::new()
+            }
+        };
+        let builder = NDSBuilder::new(bios7, bios9, firmware, fs::read(rom_path)?)
+            .boot_mode(args.boot)
+            .hle_bios(hle_bios)
+            .save_file(save_path)
+            .audio(Box::new(CpalSink::new()))
+            .record_movie(args.record.is_some());
+        let builder = match movie {
+            Some(movie) => builder.play_movie(movie),
+            None => builder,
+        };
+        Ok(builder.build()?)
     }
 }